@@ -1,13 +1,13 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 mod hash;
+mod secp256k1;
 mod uint;
 
 pub use ethbloom::{Bloom, BloomRef, Input as BloomInput};
 pub use hash::{BigEndianHash, H128, H160, H256, H264, H32, H512, H520, H64};
+pub use secp256k1::{Public, Signature};
 pub use uint::{FromDecStrErr, U128, U256, U512, U64};
 
 pub type Address = H160;
 pub type Secret = H256;
-pub type Public = H512;
-pub type Signature = H520;
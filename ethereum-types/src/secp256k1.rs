@@ -0,0 +1,147 @@
+use crate::{H256, H512, H520, U256};
+use fixed_hash::*;
+use impl_rlp::impl_fixed_hash_rlp;
+#[cfg(feature = "serialize")]
+use impl_serde::impl_fixed_hash_serde;
+
+construct_fixed_hash! {
+	/// Uncompressed secp256k1 public key, the 64 bytes of the point without the `0x04` prefix.
+	pub struct Public(64);
+}
+impl_fixed_hash_rlp!(Public, 64);
+#[cfg(feature = "serialize")]
+impl_fixed_hash_serde!(Public, 64);
+
+construct_fixed_hash! {
+	/// Recoverable secp256k1 signature, `r` and `s` in big-endian followed by the recovery id `v`.
+	pub struct Signature(65);
+}
+impl_fixed_hash_rlp!(Signature, 65);
+#[cfg(feature = "serialize")]
+impl_fixed_hash_serde!(Signature, 65);
+
+/// Order of the secp256k1 curve.
+const SECP256K1_N: U256 = U256([0xBFD25E8CD0364141, 0xBAAEDCE6AF48A03B, 0xFFFFFFFFFFFFFFFE, 0xFFFFFFFFFFFFFFFF]);
+/// Half of the order of the secp256k1 curve, rounded down.
+const SECP256K1_HALF_N: U256 = U256([0xDFE92F46681B20A0, 0x5D576E7357A4501D, 0xFFFFFFFFFFFFFFFF, 0x7FFFFFFFFFFFFFFF]);
+
+impl Signature {
+	/// Create a signature from its components.
+	pub fn from_rsv(r: &H256, s: &H256, v: u8) -> Self {
+		let mut sig = Signature::zero();
+		sig.0[0..32].copy_from_slice(r.as_bytes());
+		sig.0[32..64].copy_from_slice(s.as_bytes());
+		sig.0[64] = v;
+		sig
+	}
+
+	/// The `r` component.
+	pub fn r(&self) -> H256 {
+		H256::from_slice(&self.0[0..32])
+	}
+
+	/// The `s` component.
+	pub fn s(&self) -> H256 {
+		H256::from_slice(&self.0[32..64])
+	}
+
+	/// The raw `v` byte.
+	pub fn v(&self) -> u8 {
+		self.0[64]
+	}
+
+	/// The recovery id, 0 or 1, accepting both the raw `v` and the legacy Ethereum `v` of 27 or 28.
+	pub fn recovery_id(&self) -> Option<u8> {
+		match self.v() {
+			v @ 0..=1 => Some(v),
+			v @ 27..=28 => Some(v - 27),
+			_ => None,
+		}
+	}
+
+	/// Whether `s` is in the lower half of the curve order, as required for transactions since Homestead.
+	pub fn is_low_s(&self) -> bool {
+		U256::from_big_endian(&self.0[32..64]) <= SECP256K1_HALF_N
+	}
+
+	/// Whether `r` and `s` are in the range `[1, n)` and the recovery id is valid.
+	pub fn is_valid(&self) -> bool {
+		let r = U256::from_big_endian(&self.0[0..32]);
+		let s = U256::from_big_endian(&self.0[32..64]);
+		let in_range = |x: U256| !x.is_zero() && x < SECP256K1_N;
+		in_range(r) && in_range(s) && self.recovery_id().is_some()
+	}
+}
+
+impl From<H512> for Public {
+	fn from(hash: H512) -> Self {
+		Public(hash.0)
+	}
+}
+
+impl From<Public> for H512 {
+	fn from(public: Public) -> Self {
+		H512(public.0)
+	}
+}
+
+impl From<H520> for Signature {
+	fn from(hash: H520) -> Self {
+		Signature(hash.0)
+	}
+}
+
+impl From<Signature> for H520 {
+	fn from(signature: Signature) -> Self {
+		H520(signature.0)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Signature, SECP256K1_HALF_N, SECP256K1_N};
+	use crate::{BigEndianHash, H256, U256};
+
+	#[test]
+	fn half_n_is_half_of_n() {
+		assert_eq!(SECP256K1_N >> 1, SECP256K1_HALF_N);
+	}
+
+	#[test]
+	fn signature_components() {
+		let r = H256::from_low_u64_be(1);
+		let s = H256::from_low_u64_be(2);
+		let sig = Signature::from_rsv(&r, &s, 28);
+		assert_eq!(sig.r(), r);
+		assert_eq!(sig.s(), s);
+		assert_eq!(sig.v(), 28);
+		assert_eq!(sig.recovery_id(), Some(1));
+		assert!(sig.is_low_s());
+		assert!(sig.is_valid());
+
+		assert_eq!(Signature::from_rsv(&r, &s, 2).recovery_id(), None);
+		assert!(!Signature::from_rsv(&r, &s, 2).is_valid());
+		assert!(!Signature::from_rsv(&H256::zero(), &s, 0).is_valid());
+	}
+
+	#[test]
+	fn low_s() {
+		let r = H256::from_low_u64_be(1);
+		let half = H256::from_uint(&SECP256K1_HALF_N);
+		assert!(Signature::from_rsv(&r, &half, 0).is_low_s());
+		let high = H256::from_uint(&(SECP256K1_HALF_N + U256::one()));
+		let sig = Signature::from_rsv(&r, &high, 0);
+		assert!(!sig.is_low_s());
+		assert!(sig.is_valid());
+		assert!(!Signature::from_rsv(&r, &H256::from_uint(&SECP256K1_N), 0).is_valid());
+	}
+
+	#[cfg(feature = "serialize")]
+	#[test]
+	fn serialize_signature() {
+		let sig = Signature::from_rsv(&H256::from_low_u64_be(1), &H256::from_low_u64_be(2), 27);
+		let json = serde_json::to_string(&sig).unwrap();
+		assert_eq!(json, format!("\"0x{}01{}02{}\"", "0".repeat(62), "0".repeat(62), "1b"));
+		assert_eq!(serde_json::from_str::<Signature>(&json).unwrap(), sig);
+	}
+}
@@ -77,6 +77,14 @@ mod tests {
 		assert_eq!(raw, new_raw);
 	}
 
+	#[test]
+	fn words_roundtrip() {
+		let value = U256::from(MAX) + 1;
+		assert_eq!(value.into_words(), [0, 1, 0, 0]);
+		assert_eq!(primitive_types::U256::from_words(value.into_words()), value);
+		assert_eq!(U512::from_words([1, 2, 3, 4, 5, 6, 7, 8]).into_words(), [1, 2, 3, 4, 5, 6, 7, 8]);
+	}
+
 	#[test]
 	fn u256_multi_full_mul() {
 		let result = U256([0, 0, 0, 0]).full_mul(U256([0, 0, 0, 0]));
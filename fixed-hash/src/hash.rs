@@ -113,6 +113,9 @@ macro_rules! construct_fixed_hash {
 		}
 
 		impl $name {
+			/// The size of this hash in bytes.
+			pub const LEN: usize = $n_bytes;
+
 			/// Returns a new fixed hash where all bits are set to the given byte.
 			#[inline]
 			pub fn repeat_byte(byte: u8) -> $name {
@@ -128,7 +131,7 @@ macro_rules! construct_fixed_hash {
 			/// Returns the size of this hash in bytes.
 			#[inline]
 			pub fn len_bytes() -> usize {
-				$n_bytes
+				Self::LEN
 			}
 
 			/// Extracts a byte slice containing the entire fixed hash.
@@ -214,6 +217,88 @@ macro_rules! construct_fixed_hash {
 			pub fn is_zero(&self) -> bool {
 				self.as_bytes().iter().all(|&byte| byte == 0u8)
 			}
+
+			/// Returns the number of leading zero bits, reading the hash as a big-endian integer.
+			pub fn leading_zeros(&self) -> u32 {
+				let mut zeros = 0;
+				for byte in self.as_bytes() {
+					zeros += byte.leading_zeros();
+					if *byte != 0 {
+						break;
+					}
+				}
+				zeros
+			}
+
+			/// Returns the number of trailing zero bits, reading the hash as a big-endian integer.
+			pub fn trailing_zeros(&self) -> u32 {
+				let mut zeros = 0;
+				for byte in self.as_bytes().iter().rev() {
+					zeros += byte.trailing_zeros();
+					if *byte != 0 {
+						break;
+					}
+				}
+				zeros
+			}
+
+			/// Adds `other` to `self`, reading both as big-endian integers.
+			/// Returns `None` on overflow.
+			pub fn checked_add(&self, other: &Self) -> Option<Self> {
+				let mut ret = Self::zero();
+				let mut carry = false;
+				for ((r, a), b) in ret.0.iter_mut().zip(self.0.iter()).zip(other.0.iter()).rev() {
+					let (sum, overflow_a) = a.overflowing_add(*b);
+					let (sum, overflow_carry) = sum.overflowing_add(carry as u8);
+					*r = sum;
+					carry = overflow_a || overflow_carry;
+				}
+				if carry {
+					None
+				} else {
+					Some(ret)
+				}
+			}
+
+			fn low_u64_bytes(&self) -> [u8; 8] {
+				let mut buf = [0x0; 8];
+				let capped = $crate::core_::cmp::min(Self::len_bytes(), 8);
+				buf[(8 - capped)..].copy_from_slice(&self[(Self::len_bytes() - capped)..]);
+				buf
+			}
+
+			/// Returns the lowest 8 bytes interpreted as big-endian.
+			///
+			/// # Note
+			///
+			/// For hash type with less than 8 bytes the missing bytes
+			/// are interpreted as being zero.
+			#[inline]
+			pub fn to_low_u64_be(&self) -> u64 {
+				u64::from_be_bytes(self.low_u64_bytes())
+			}
+
+			/// Returns the lowest 8 bytes interpreted as little-endian.
+			///
+			/// # Note
+			///
+			/// For hash type with less than 8 bytes the missing bytes
+			/// are interpreted as being zero.
+			#[inline]
+			pub fn to_low_u64_le(&self) -> u64 {
+				u64::from_le_bytes(self.low_u64_bytes())
+			}
+
+			/// Returns the lowest 8 bytes interpreted as native-endian.
+			///
+			/// # Note
+			///
+			/// For hash type with less than 8 bytes the missing bytes
+			/// are interpreted as being zero.
+			#[inline]
+			pub fn to_low_u64_ne(&self) -> u64 {
+				u64::from_ne_bytes(self.low_u64_bytes())
+			}
 		}
 
 		impl $crate::core_::fmt::Debug for $name {
@@ -260,6 +345,32 @@ macro_rules! construct_fixed_hash {
 			}
 		}
 
+		impl $crate::FixedHash for $name {
+			type Bytes = [u8; $n_bytes];
+
+			const LEN: usize = $n_bytes;
+
+			#[inline]
+			fn as_fixed_bytes(&self) -> &Self::Bytes {
+				&self.0
+			}
+
+			#[inline]
+			fn as_fixed_bytes_mut(&mut self) -> &mut Self::Bytes {
+				&mut self.0
+			}
+
+			#[inline]
+			fn to_fixed_bytes(self) -> Self::Bytes {
+				self.0
+			}
+
+			#[inline]
+			fn from_fixed_bytes(bytes: Self::Bytes) -> Self {
+				$name(bytes)
+			}
+		}
+
 		impl $crate::core_::marker::Copy for $name {}
 
 		#[cfg_attr(feature = "dev", allow(expl_impl_clone_on_copy))]
@@ -319,6 +430,27 @@ macro_rules! construct_fixed_hash {
 		impl_ops_for_hash!($name, BitAnd, bitand, BitAndAssign, bitand_assign, &, &=);
 		impl_ops_for_hash!($name, BitXor, bitxor, BitXorAssign, bitxor_assign, ^, ^=);
 
+		impl<'a> $crate::core_::ops::Not for &'a $name {
+			type Output = $name;
+
+			fn not(self) -> $name {
+				let mut ret = self.clone();
+				for byte in ret.as_bytes_mut() {
+					*byte = !*byte;
+				}
+				ret
+			}
+		}
+
+		impl $crate::core_::ops::Not for $name {
+			type Output = $name;
+
+			#[inline]
+			fn not(self) -> $name {
+				!&self
+			}
+		}
+
 		impl_byteorder_for_fixed_hash!($name);
 		impl_rand_for_fixed_hash!($name);
 		impl_libc_for_fixed_hash!($name);
@@ -355,60 +487,6 @@ macro_rules! impl_byteorder_for_fixed_hash {
 	( $name:ident ) => {
 		/// Utilities using the `byteorder` crate.
 		impl $name {
-			/// Returns the least significant `n` bytes as slice.
-			///
-			/// # Panics
-			///
-			/// If `n` is greater than the number of bytes in `self`.
-			#[inline]
-			fn least_significant_bytes(&self, n: usize) -> &[u8] {
-				$crate::core_::assert_eq!(true, n <= Self::len_bytes());
-				&self[(Self::len_bytes() - n)..]
-			}
-
-			fn to_low_u64_with_byteorder<B>(&self) -> u64
-			where
-				B: $crate::byteorder::ByteOrder,
-			{
-				let mut buf = [0x0; 8];
-				let capped = $crate::core_::cmp::min(Self::len_bytes(), 8);
-				buf[(8 - capped)..].copy_from_slice(self.least_significant_bytes(capped));
-				B::read_u64(&buf)
-			}
-
-			/// Returns the lowest 8 bytes interpreted as big-endian.
-			///
-			/// # Note
-			///
-			/// For hash type with less than 8 bytes the missing bytes
-			/// are interpreted as being zero.
-			#[inline]
-			pub fn to_low_u64_be(&self) -> u64 {
-				self.to_low_u64_with_byteorder::<$crate::byteorder::BigEndian>()
-			}
-
-			/// Returns the lowest 8 bytes interpreted as little-endian.
-			///
-			/// # Note
-			///
-			/// For hash type with less than 8 bytes the missing bytes
-			/// are interpreted as being zero.
-			#[inline]
-			pub fn to_low_u64_le(&self) -> u64 {
-				self.to_low_u64_with_byteorder::<$crate::byteorder::LittleEndian>()
-			}
-
-			/// Returns the lowest 8 bytes interpreted as native-endian.
-			///
-			/// # Note
-			///
-			/// For hash type with less than 8 bytes the missing bytes
-			/// are interpreted as being zero.
-			#[inline]
-			pub fn to_low_u64_ne(&self) -> u64 {
-				self.to_low_u64_with_byteorder::<$crate::byteorder::NativeEndian>()
-			}
-
 			fn from_low_u64_with_byteorder<B>(val: u64) -> Self
 			where
 				B: $crate::byteorder::ByteOrder,
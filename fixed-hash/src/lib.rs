@@ -60,6 +60,28 @@ extern crate rand_xorshift;
 #[macro_use]
 mod hash;
 
+/// Implemented by all types created with `construct_fixed_hash!`, to write code generic over
+/// the size of the hash.
+pub trait FixedHash: Copy + Default + Eq + AsRef<[u8]> + AsMut<[u8]> {
+	/// The byte array of the hash, `[u8; Self::LEN]`.
+	type Bytes: Copy + AsRef<[u8]> + AsMut<[u8]>;
+
+	/// The size of the hash in bytes.
+	const LEN: usize;
+
+	/// Extracts a reference to the byte array containing the entire fixed hash.
+	fn as_fixed_bytes(&self) -> &Self::Bytes;
+
+	/// Extracts a mutable reference to the byte array containing the entire fixed hash.
+	fn as_fixed_bytes_mut(&mut self) -> &mut Self::Bytes;
+
+	/// Returns the inner bytes array.
+	fn to_fixed_bytes(self) -> Self::Bytes;
+
+	/// Creates the hash from its bytes array.
+	fn from_fixed_bytes(bytes: Self::Bytes) -> Self;
+}
+
 #[cfg(test)]
 mod tests;
 
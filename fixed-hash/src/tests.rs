@@ -31,6 +31,23 @@ fn len_bytes() {
 	assert_eq!(H256::len_bytes(), 32);
 }
 
+#[test]
+fn generic_over_size() {
+	use crate::FixedHash;
+
+	fn reversed<H: FixedHash>(hash: H) -> H {
+		let mut bytes = hash.to_fixed_bytes();
+		bytes.as_mut().reverse();
+		H::from_fixed_bytes(bytes)
+	}
+
+	assert_eq!(H32::LEN, 4);
+	assert_eq!(<H256 as FixedHash>::LEN, 32);
+	assert_eq!(reversed(H32::from([1, 2, 3, 4])), H32::from([4, 3, 2, 1]));
+	assert_eq!(reversed(H64::from([0, 0, 0, 0, 0, 0, 0, 1])), H64::from([1, 0, 0, 0, 0, 0, 0, 0]));
+	assert_eq!(FixedHash::as_fixed_bytes(&H32::from(&[5; 4])), &[5; 4]);
+}
+
 #[test]
 fn as_bytes() {
 	assert_eq!(H32::from([0x55; 4]).as_bytes(), &[0x55; 4]);
@@ -147,7 +164,6 @@ mod is_zero {
 	}
 }
 
-#[cfg(feature = "byteorder")]
 mod to_low_u64 {
 	use super::*;
 
@@ -405,4 +421,49 @@ mod ops {
 			])
 		)
 	}
+
+	#[test]
+	fn not() {
+		assert_eq!(!lhs(), H32::from([0b1100_1001, 0b1110_1100, 0b0101_0101, 0b1110_1101]));
+		assert_eq!(!&H32::zero(), H32::repeat_byte(0xff));
+	}
+}
+
+mod zeros {
+	use super::*;
+
+	#[test]
+	fn leading_and_trailing() {
+		assert_eq!(H32::zero().leading_zeros(), 32);
+		assert_eq!(H32::zero().trailing_zeros(), 32);
+		assert_eq!(H32::repeat_byte(0xff).leading_zeros(), 0);
+		assert_eq!(H32::repeat_byte(0xff).trailing_zeros(), 0);
+		assert_eq!(H32::from([0x00, 0x10, 0x00, 0x00]).leading_zeros(), 11);
+		assert_eq!(H32::from([0x00, 0x10, 0x00, 0x00]).trailing_zeros(), 20);
+		assert_eq!(H32::from([0x80, 0x00, 0x00, 0x01]).leading_zeros(), 0);
+		assert_eq!(H32::from([0x80, 0x00, 0x00, 0x01]).trailing_zeros(), 0);
+	}
+}
+
+mod checked_add {
+	use super::*;
+
+	#[test]
+	fn carries_between_bytes() {
+		assert_eq!(
+			H32::from([0x00, 0x00, 0x01, 0xff]).checked_add(&H32::from([0x00, 0x00, 0x00, 0x01])),
+			Some(H32::from([0x00, 0x00, 0x02, 0x00]))
+		);
+		assert_eq!(
+			H32::from([0x00, 0xff, 0xff, 0xff]).checked_add(&H32::from([0x00, 0x00, 0x00, 0x01])),
+			Some(H32::from([0x01, 0x00, 0x00, 0x00]))
+		);
+	}
+
+	#[test]
+	fn overflows() {
+		assert_eq!(H32::repeat_byte(0xff).checked_add(&H32::from([0x00, 0x00, 0x00, 0x01])), None);
+		assert_eq!(H32::from([0x80, 0, 0, 0]).checked_add(&H32::from([0x80, 0, 0, 0])), None);
+		assert_eq!(H32::repeat_byte(0xff).checked_add(&H32::zero()), Some(H32::repeat_byte(0xff)));
+	}
 }
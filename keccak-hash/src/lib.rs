@@ -14,10 +14,21 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
+//! Keccak hashing utilities.
+//!
+//! The crate is `no_std` without the default `std` feature, the functions returning a `Vec`
+//! requiring only the `alloc` feature. The hashing itself is plain Rust with no platform
+//! specific code, so `wasm32-unknown-unknown` and embedded targets get the same hashes as the node.
+
 #![cfg_attr(not(feature = "std"), no_std)]
 
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+extern crate alloc;
+
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::vec::Vec;
 #[cfg(feature = "std")]
-use std::io;
+use std::{fs, io, path::Path};
 
 pub use primitive_types::H256;
 use tiny_keccak::{Hasher, Keccak};
@@ -70,12 +81,87 @@ pub fn keccak_512(input: &[u8], output: &mut [u8]) {
 	keccak512.finalize(output);
 }
 
+/// Computes keccak256 hash of `input` into a fixed-size `output`.
+pub fn keccak_256_into(input: &[u8], output: &mut [u8; 32]) {
+	write_keccak(input, output);
+}
+
 pub fn write_keccak<T: AsRef<[u8]>>(s: T, dest: &mut [u8]) {
 	let mut keccak256 = Keccak::v256();
 	keccak256.update(s.as_ref());
 	keccak256.finalize(dest);
 }
 
+/// Minimal number of inputs hashed by a single rayon task.
+#[cfg(feature = "parallel")]
+const PARALLEL_MIN_CHUNK: usize = 64;
+
+/// Computes keccak256 hashes of all `inputs`, in order.
+///
+/// With the `parallel` feature the batch is spread over the rayon thread pool. The inputs are
+/// required to be `Sync` either way, so that enabling the feature doesn't break callers.
+#[cfg(feature = "alloc")]
+pub fn keccak_batch<T: AsRef<[u8]> + Sync>(inputs: &[T]) -> Vec<H256> {
+	#[cfg(feature = "parallel")]
+	{
+		use rayon::prelude::*;
+
+		inputs.par_iter().with_min_len(PARALLEL_MIN_CHUNK).map(keccak).collect()
+	}
+	#[cfg(not(feature = "parallel"))]
+	{
+		inputs.iter().map(keccak).collect()
+	}
+}
+
+/// Incremental keccak256 hasher.
+///
+/// Produces the same hash as `keccak` applied to the concatenation of all the
+/// updates, without building that concatenation in memory.
+///
+/// ```rust
+/// use keccak_hash::{keccak, Keccak256};
+///
+/// let mut hasher = Keccak256::new();
+/// hasher.update(b"hello");
+/// hasher.update(b" world");
+/// assert_eq!(hasher.finalize(), keccak(b"hello world"));
+/// ```
+#[derive(Clone)]
+pub struct Keccak256 {
+	inner: Keccak,
+}
+
+impl Default for Keccak256 {
+	fn default() -> Self {
+		Keccak256::new()
+	}
+}
+
+impl Keccak256 {
+	/// Create a new hasher with no input.
+	pub fn new() -> Self {
+		Keccak256 { inner: Keccak::v256() }
+	}
+
+	/// Absorb more input.
+	pub fn update(&mut self, input: &[u8]) {
+		self.inner.update(input);
+	}
+
+	/// Consume the hasher and return the hash of all input so far.
+	pub fn finalize(self) -> H256 {
+		let mut output = [0u8; 32];
+		self.finalize_into(&mut output);
+		H256(output)
+	}
+
+	/// Consume the hasher and write the hash of all input so far into `output`.
+	pub fn finalize_into(self, output: &mut [u8; 32]) {
+		self.inner.finalize(output);
+	}
+}
+
 #[cfg(feature = "std")]
 pub fn keccak_pipe(r: &mut dyn io::BufRead, w: &mut dyn io::Write) -> Result<H256, io::Error> {
 	let mut output = [0u8; 32];
@@ -101,6 +187,30 @@ pub fn keccak_buffer(r: &mut dyn io::BufRead) -> Result<H256, io::Error> {
 	keccak_pipe(r, &mut io::sink())
 }
 
+/// Computes keccak256 hash of everything `r` yields, reading it in fixed-size chunks.
+#[cfg(feature = "std")]
+pub fn keccak_reader<R: io::Read>(mut r: R) -> Result<H256, io::Error> {
+	let mut input = [0u8; 8 * 1024];
+	let mut keccak256 = Keccak256::new();
+
+	loop {
+		match r.read(&mut input) {
+			Ok(0) => break,
+			Ok(some) => keccak256.update(&input[0..some]),
+			Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+			Err(e) => return Err(e),
+		}
+	}
+
+	Ok(keccak256.finalize())
+}
+
+/// Computes keccak256 hash of the file at `path` without loading it into memory.
+#[cfg(feature = "std")]
+pub fn keccak_file<P: AsRef<Path>>(path: P) -> Result<H256, io::Error> {
+	keccak_reader(fs::File::open(path)?)
+}
+
 #[cfg(test)]
 mod tests {
 	#[cfg(not(feature = "std"))]
@@ -139,6 +249,39 @@ mod tests {
 		assert_eq!(dest, expected.as_ref());
 	}
 
+	#[test]
+	fn keccak_256_into_matches_keccak() {
+		let mut dest = [0u8; 32];
+		keccak_256_into(b"hello world", &mut dest);
+		assert_eq!(H256(dest), keccak(b"hello world"));
+	}
+
+	#[test]
+	fn streaming_hasher() {
+		let data = [0x41u8; 300];
+		let mut hasher = Keccak256::new();
+		for chunk in data.chunks(7) {
+			hasher.update(chunk);
+		}
+		let mut dest = [0u8; 32];
+		hasher.clone().finalize_into(&mut dest);
+		assert_eq!(H256(dest), keccak(&data[..]));
+		assert_eq!(hasher.finalize(), keccak(&data[..]));
+		assert_eq!(Keccak256::default().finalize(), KECCAK_EMPTY);
+	}
+
+	#[cfg(feature = "alloc")]
+	#[test]
+	fn keccak_batch_preserves_order() {
+		let inputs: Vec<Vec<u8>> = (0..1000u32).map(|i| i.to_be_bytes().to_vec()).collect();
+		let hashes = keccak_batch(&inputs);
+		assert_eq!(hashes.len(), inputs.len());
+		for (input, hash) in inputs.iter().zip(hashes) {
+			assert_eq!(keccak(input), hash);
+		}
+		assert_eq!(keccak_batch(&[&b""[..]]), vec![KECCAK_EMPTY]);
+	}
+
 	#[cfg(feature = "std")]
 	#[test]
 	fn should_keccak_a_file() {
@@ -161,5 +304,14 @@ mod tests {
 
 		// then
 		assert_eq!(format!("{:x}", hash), "68371d7e884c168ae2022c82bd837d51837718a7f7dfb7aa3f753074a35e1d87");
+		assert_eq!(keccak_file(&path).unwrap(), hash);
+	}
+
+	#[cfg(feature = "std")]
+	#[test]
+	fn should_keccak_a_reader_larger_than_buffer() {
+		let data: Vec<u8> = (0..100_000u32).map(|i| i as u8).collect();
+		let hash = keccak_reader(&data[..]).unwrap();
+		assert_eq!(hash, keccak(&data));
 	}
 }
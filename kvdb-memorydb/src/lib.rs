@@ -14,18 +14,58 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-use kvdb::{DBOp, DBTransaction, DBValue, KeyValueDB};
+mod sortedness;
+
+pub use sortedness::SortednessCheckDb;
+
+use kvdb::{
+	ColumnGuard, ColumnLocks, DBOp, DBTransaction, DBValue, IterOptions, KeyValueDB, Metadata, SizeLimits, UpdateFn,
+};
 use parking_lot::RwLock;
+use rand::{
+	seq::{index, SliceRandom},
+	RngCore, SeedableRng,
+};
+use rand_xorshift::XorShiftRng;
 use std::{
 	collections::{BTreeMap, HashMap},
 	io,
+	ops::Bound,
+	sync::atomic::{AtomicU64, Ordering},
 };
 
+/// Order in which the iterators of an `InMemory` database yield the keys of a column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IterationOrder {
+	/// Ascending key order, as required by `KeyValueDB`.
+	Sorted,
+	/// A shuffled order, the same for the same seed and contents of the column.
+	///
+	/// This breaks the ordering guarantee of `KeyValueDB`, to test that code doesn't depend on it.
+	Shuffled {
+		/// Seed of the shuffle.
+		seed: u64,
+	},
+}
+
+impl Default for IterationOrder {
+	fn default() -> Self {
+		IterationOrder::Sorted
+	}
+}
+
 /// A key-value database fulfilling the `KeyValueDB` trait, living in memory.
 /// This is generally intended for tests and is not particularly optimized.
+///
+/// Reads return copies of the stored values, so callers can't modify the stored values through them.
 #[derive(Default)]
 pub struct InMemory {
 	columns: RwLock<HashMap<u32, BTreeMap<Vec<u8>, DBValue>>>,
+	metadata: RwLock<Option<DBValue>>,
+	last_committed: AtomicU64,
+	column_locks: ColumnLocks,
+	size_limits: SizeLimits,
+	iteration_order: IterationOrder,
 }
 
 /// Create an in-memory database with the given number of columns.
@@ -37,7 +77,89 @@ pub fn create(num_cols: u32) -> InMemory {
 		cols.insert(idx, BTreeMap::new());
 	}
 
-	InMemory { columns: RwLock::new(cols) }
+	InMemory {
+		columns: RwLock::new(cols),
+		metadata: RwLock::new(None),
+		last_committed: AtomicU64::new(0),
+		column_locks: ColumnLocks::new(),
+		size_limits: SizeLimits::unlimited(),
+		iteration_order: IterationOrder::Sorted,
+	}
+}
+
+/// Create an in-memory database with the given number of columns, rejecting writes
+/// exceeding the given limits.
+///
+/// `write` and `write_buffered` return a `SizeLimitError` for transactions violating the limits,
+/// without applying any of their changes.
+pub fn create_with_size_limits(num_cols: u32, size_limits: SizeLimits) -> InMemory {
+	InMemory { size_limits, ..create(num_cols) }
+}
+
+/// Create an in-memory database with the given number of columns, whose iterators yield keys
+/// in the given order.
+pub fn create_with_iteration_order(num_cols: u32, iteration_order: IterationOrder) -> InMemory {
+	InMemory { iteration_order, ..create(num_cols) }
+}
+
+/// Create an in-memory database with the given metadata header.
+/// Columns will be indexable by 0..`metadata.columns`
+pub fn create_with_metadata(metadata: &Metadata) -> InMemory {
+	let db = create(metadata.columns);
+	*db.metadata.write() = Some(DBValue::from_slice(&metadata.encode()));
+	db
+}
+
+impl InMemory {
+	/// The number of columns.
+	pub fn num_columns(&self) -> u32 {
+		self.columns.read().len() as u32
+	}
+
+	/// Add a new empty column, with index `num_columns()`.
+	pub fn add_column(&self) -> io::Result<()> {
+		let mut columns = self.columns.write();
+		let col = columns.len() as u32;
+		columns.insert(col, BTreeMap::new());
+		Ok(())
+	}
+
+	/// The order of the keys yielded by iterators.
+	pub fn iteration_order(&self) -> IterationOrder {
+		self.iteration_order
+	}
+
+	/// Remove the last column together with its contents, so that columns stay indexable
+	/// by `0..num_columns()`. Does nothing if there are no columns.
+	///
+	/// Only the last column can be removed, as with `Database::remove_last_column` of `kvdb-rocksdb`:
+	/// RocksDB column families are named after their index, so removing another one would
+	/// renumber the columns after it. Code tested against this database has to run on RocksDB too.
+	pub fn remove_last_column(&self) -> io::Result<()> {
+		let mut columns = self.columns.write();
+		let col = match columns.len() {
+			0 => return Ok(()),
+			len => len as u32 - 1,
+		};
+		columns.remove(&col);
+		Ok(())
+	}
+}
+
+impl InMemory {
+	fn ordered<'a, I>(&self, iter: I) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a>
+	where
+		I: Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a,
+	{
+		match self.iteration_order {
+			IterationOrder::Sorted => Box::new(iter),
+			IterationOrder::Shuffled { seed } => {
+				let mut pairs = iter.collect::<Vec<_>>();
+				pairs.shuffle(&mut XorShiftRng::seed_from_u64(seed));
+				Box::new(pairs.into_iter())
+			}
+		}
+	}
 }
 
 impl KeyValueDB for InMemory {
@@ -59,32 +181,82 @@ impl KeyValueDB for InMemory {
 		}
 	}
 
-	fn write_buffered(&self, transaction: DBTransaction) {
+	fn write_buffered(&self, transaction: DBTransaction) -> io::Result<u64> {
+		self.size_limits.check(&transaction)?;
 		let mut columns = self.columns.write();
 		let ops = transaction.ops;
 		for op in ops {
 			match op {
 				DBOp::Insert { col, key, value } => {
-					if let Some(col) = columns.get_mut(&col) {
-						col.insert(key.into_vec(), value);
+					if let Some(map) = columns.get_mut(&col) {
+						map.insert(key.into_vec(), value);
 					}
 				}
 				DBOp::Delete { col, key } => {
-					if let Some(col) = columns.get_mut(&col) {
-						col.remove(&*key);
+					if let Some(map) = columns.get_mut(&col) {
+						map.remove(&*key);
 					}
 				}
 			}
 		}
+		Ok(self.last_committed.fetch_add(1, Ordering::SeqCst) + 1)
+	}
+
+	fn last_committed(&self) -> u64 {
+		self.last_committed.load(Ordering::SeqCst)
 	}
 
 	fn flush(&self) -> io::Result<()> {
 		Ok(())
 	}
 
+	fn update(&self, col: u32, key: &[u8], f: &UpdateFn<'_>) -> io::Result<()> {
+		let mut columns = self.columns.write();
+		match columns.get_mut(&col) {
+			None => Err(io::Error::new(io::ErrorKind::Other, format!("No such column family: {:?}", col))),
+			Some(map) => {
+				let mut transaction = DBTransaction::with_capacity(1);
+				match f(map.get(key).map(|v| &v[..])) {
+					Some(value) => transaction.put_vec(col, key, value),
+					None => transaction.delete(col, key),
+				};
+				self.size_limits.check(&transaction)?;
+				for op in transaction.ops {
+					match op {
+						DBOp::Insert { key, value, .. } => map.insert(key.into_vec(), value),
+						DBOp::Delete { key, .. } => map.remove(&*key),
+					};
+				}
+				self.last_committed.fetch_add(1, Ordering::SeqCst);
+				Ok(())
+			}
+		}
+	}
+
+	fn delete_by_prefix(&self, col: u32, prefix: &[u8]) -> io::Result<u64> {
+		self.flush()?;
+		let mut columns = self.columns.write();
+		let map = match columns.get_mut(&col) {
+			Some(map) => map,
+			None => return Err(io::Error::new(io::ErrorKind::Other, format!("No such column family: {:?}", col))),
+		};
+		let keys = map
+			.range::<[u8], _>((Bound::Included(prefix), Bound::Unbounded))
+			.take_while(|(key, _)| key.starts_with(prefix))
+			.map(|(key, _)| key.clone())
+			.collect::<Vec<_>>();
+		for key in &keys {
+			map.remove(key);
+		}
+		if !keys.is_empty() {
+			self.last_committed.fetch_add(1, Ordering::SeqCst);
+		}
+		Ok(keys.len() as u64)
+	}
+
 	fn iter<'a>(&'a self, col: u32) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
 		match self.columns.read().get(&col) {
-			Some(map) => Box::new(
+			Some(map) => self.ordered(
 				// TODO: worth optimizing at all?
 				map.clone().into_iter().map(|(k, v)| (k.into_boxed_slice(), v.into_vec().into_boxed_slice())),
 			),
@@ -92,13 +264,46 @@ impl KeyValueDB for InMemory {
 		}
 	}
 
+	// iterators work on a copy of the column, so they are always snapshots
+	fn iter_with_options<'a>(
+		&'a self,
+		col: u32,
+		_options: IterOptions,
+	) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
+		self.iter(col)
+	}
+
+	// picks the positions first, so that only the sampled keys are copied
+	fn sample_keys(&self, col: u32, n: usize, rng: &mut dyn RngCore) -> Vec<Box<[u8]>> {
+		let columns = self.columns.read();
+		let map = match columns.get(&col) {
+			Some(map) => map,
+			None => return Vec::new(),
+		};
+		let mut positions = index::sample(rng, map.len(), n.min(map.len())).into_vec();
+		positions.sort_unstable();
+		let mut positions = positions.into_iter().peekable();
+		let mut sample = Vec::with_capacity(positions.len());
+		for (i, key) in map.keys().enumerate() {
+			match positions.peek() {
+				Some(&p) if p == i => {
+					sample.push(key.clone().into_boxed_slice());
+					positions.next();
+				}
+				Some(_) => {}
+				None => break,
+			}
+		}
+		sample
+	}
+
 	fn iter_from_prefix<'a>(
 		&'a self,
 		col: u32,
 		prefix: &'a [u8],
 	) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
 		match self.columns.read().get(&col) {
-			Some(map) => Box::new(
+			Some(map) => self.ordered(
 				map.clone()
 					.into_iter()
 					.filter(move |&(ref k, _)| k.starts_with(prefix))
@@ -111,11 +316,35 @@ impl KeyValueDB for InMemory {
 	fn restore(&self, _new_db: &str) -> io::Result<()> {
 		Err(io::Error::new(io::ErrorKind::Other, "Attempted to restore in-memory database"))
 	}
+
+	fn lock_column(&self, col: u32) -> ColumnGuard<'_> {
+		self.column_locks.lock(col)
+	}
+
+	fn lock_column_shared(&self, col: u32) -> ColumnGuard<'_> {
+		self.column_locks.lock_shared(col)
+	}
+
+	fn metadata(&self) -> io::Result<Option<DBValue>> {
+		Ok(self.metadata.read().clone())
+	}
+
+	fn set_metadata(&self, value: &[u8]) -> io::Result<()> {
+		*self.metadata.write() = Some(DBValue::from_slice(value));
+		Ok(())
+	}
 }
 
 #[cfg(test)]
 mod tests {
-	use super::{create, KeyValueDB};
+	use super::{
+		create, create_with_iteration_order, create_with_metadata, create_with_size_limits, IterationOrder, KeyValueDB,
+		SortednessCheckDb,
+	};
+	use kvdb::{check_metadata, IterOptions, Metadata, SizeLimitError, SizeLimits};
+	use rand::SeedableRng;
+	use rand_xorshift::XorShiftRng;
+	use std::{io, sync::Arc, thread};
 
 	#[test]
 	fn get_fails_with_non_existing_column() {
@@ -131,7 +360,7 @@ mod tests {
 
 		let mut transaction = db.transaction();
 		transaction.put(0, key1, b"horse");
-		db.write_buffered(transaction);
+		db.write_buffered(transaction).unwrap();
 		assert_eq!(&*db.get(0, key1).unwrap().unwrap(), b"horse");
 	}
 
@@ -143,12 +372,12 @@ mod tests {
 
 		let mut transaction = db.transaction();
 		transaction.put(0, key1, b"horse");
-		db.write_buffered(transaction);
+		db.write_buffered(transaction).unwrap();
 		assert_eq!(&*db.get(0, key1).unwrap().unwrap(), b"horse");
 
 		let mut transaction = db.transaction();
 		transaction.delete(0, key1);
-		db.write_buffered(transaction);
+		db.write_buffered(transaction).unwrap();
 		assert!(db.get(0, key1).unwrap().is_none());
 	}
 
@@ -162,7 +391,7 @@ mod tests {
 		let mut transaction = db.transaction();
 		transaction.put(0, key1, key1);
 		transaction.put(0, key2, key2);
-		db.write_buffered(transaction);
+		db.write_buffered(transaction).unwrap();
 
 		let contents: Vec<_> = db.iter(0).into_iter().collect();
 		assert_eq!(contents.len(), 2);
@@ -172,6 +401,49 @@ mod tests {
 		assert_eq!(&*contents[1].1, key2);
 	}
 
+	#[test]
+	fn snapshot_iter_ignores_later_writes() {
+		let db = create(1);
+		let mut transaction = db.transaction();
+		transaction.put(0, b"key1", b"old");
+		db.write_buffered(transaction).unwrap();
+
+		let mut iter = db.iter_with_options(0, IterOptions { snapshot: true });
+		let mut transaction = db.transaction();
+		transaction.put(0, b"key1", b"new");
+		transaction.put(0, b"key2", b"new");
+		db.write_buffered(transaction).unwrap();
+
+		assert_eq!(iter.next(), Some((b"key1".to_vec().into_boxed_slice(), b"old".to_vec().into_boxed_slice())));
+		assert_eq!(iter.next(), None);
+	}
+
+	#[test]
+	fn sample_keys() {
+		let db = create(1);
+		let mut transaction = db.transaction();
+		for i in 0u8..100 {
+			transaction.put(0, &[i], &[i]);
+		}
+		db.write_buffered(transaction).unwrap();
+
+		let mut rng = XorShiftRng::seed_from_u64(42);
+		let sample = db.sample_keys(0, 10, &mut rng);
+		assert_eq!(sample.len(), 10);
+		assert!(sample.windows(2).all(|w| w[0] < w[1]));
+		assert_eq!(db.sample_keys(0, 200, &mut rng).len(), 100);
+		assert!(db.sample_keys(1, 10, &mut rng).is_empty());
+
+		// the wrapper uses the default implementation
+		let db = SortednessCheckDb::new(db);
+		let mut sample = db.sample_keys(0, 10, &mut rng);
+		sample.sort();
+		sample.dedup();
+		assert_eq!(sample.len(), 10);
+		assert_eq!(db.sample_keys(0, 200, &mut rng).len(), 100);
+		assert_eq!(db.sample_keys(0, usize::MAX, &mut rng).len(), 100);
+	}
+
 	#[test]
 	fn iter_from_prefix() {
 		let db = create(1);
@@ -184,7 +456,7 @@ mod tests {
 		transaction.put(0, key1, key1);
 		transaction.put(0, key2, key2);
 		transaction.put(0, key3, key3);
-		db.write_buffered(transaction);
+		db.write_buffered(transaction).unwrap();
 
 		let contents: Vec<_> = db.iter_from_prefix(0, b"").into_iter().collect();
 		assert_eq!(contents.len(), 3);
@@ -215,4 +487,226 @@ mod tests {
 		let contents: Vec<_> = db.iter_from_prefix(0, b"abc").into_iter().collect();
 		assert_eq!(contents.len(), 0);
 	}
+
+	#[test]
+	fn delete_by_prefix() {
+		let db = create(1);
+		let keys: [&[u8]; 6] = [&[1, 0], &[1, 1], &[1, 0xff], &[2], &[0xff], &[0xff, 1]];
+		let mut batch = db.transaction();
+		for key in &keys[..5] {
+			batch.put(0, key, b"value");
+		}
+		db.write(batch).unwrap();
+		let mut batch = db.transaction();
+		batch.put(0, keys[5], b"buffered");
+		db.write_buffered(batch).unwrap();
+
+		assert_eq!(db.delete_by_prefix(0, &[1]).unwrap(), 3);
+		assert_eq!(db.delete_by_prefix(0, &[1]).unwrap(), 0);
+		assert!(db.get(0, &[1, 0xff]).unwrap().is_none());
+		assert_eq!(db.delete_by_prefix(0, &[0xff]).unwrap(), 2);
+		assert_eq!(db.iter(0).map(|(key, _)| key.into_vec()).collect::<Vec<_>>(), vec![vec![2]]);
+		assert_eq!(db.delete_by_prefix(0, &[]).unwrap(), 1);
+		assert_eq!(db.iter(0).count(), 0);
+		assert_eq!(db.last_committed(), 5);
+		assert!(db.delete_by_prefix(1, &[]).is_err());
+	}
+
+	#[test]
+	fn create_with_metadata_writes_the_header() {
+		let metadata = Metadata::new(1, 2);
+		let db = create_with_metadata(&metadata);
+		assert_eq!(db.num_columns(), 2);
+		assert!(check_metadata(&db, &metadata).is_ok());
+	}
+
+	#[test]
+	fn update() {
+		let db = create(1);
+
+		db.update(0, b"key", &|v| {
+			assert!(v.is_none());
+			Some(b"value".to_vec())
+		})
+		.unwrap();
+		assert_eq!(&*db.get(0, b"key").unwrap().unwrap(), b"value");
+
+		db.update(0, b"key", &|_| None).unwrap();
+		assert!(db.get(0, b"key").unwrap().is_none());
+		assert!(db.update(1, b"key", &|_| None).is_err());
+	}
+
+	#[test]
+	fn concurrent_updates_are_not_lost() {
+		let db = Arc::new(create(1));
+		let increment = |v: Option<&[u8]>| {
+			let count = v.map_or(0, |v| v[0]);
+			Some(vec![count + 1])
+		};
+
+		let threads: Vec<_> = (0..4)
+			.map(|_| {
+				let db = db.clone();
+				thread::spawn(move || {
+					for _ in 0..50 {
+						db.update(0, b"counter", &increment).unwrap();
+					}
+				})
+			})
+			.collect();
+		for thread in threads {
+			thread.join().unwrap();
+		}
+
+		assert_eq!(&*db.get(0, b"counter").unwrap().unwrap(), &[200]);
+	}
+
+	#[test]
+	fn add_and_remove_columns() {
+		let db = create(1);
+		assert_eq!(db.num_columns(), 1);
+
+		db.add_column().unwrap();
+		assert_eq!(db.num_columns(), 2);
+		assert_eq!(db.iter(1).count(), 0);
+		assert!(db.get(1, b"key").unwrap().is_none());
+
+		let mut transaction = db.transaction();
+		transaction.put(1, b"key", b"value");
+		db.write_buffered(transaction).unwrap();
+		assert_eq!(&*db.get(1, b"key").unwrap().unwrap(), b"value");
+
+		db.remove_last_column().unwrap();
+		assert_eq!(db.num_columns(), 1);
+		assert!(db.get(1, b"key").is_err());
+		assert_eq!(db.iter(1).count(), 0);
+
+		// the removed column comes back empty
+		db.add_column().unwrap();
+		assert_eq!(db.num_columns(), 2);
+		assert_eq!(db.iter(1).count(), 0);
+		assert!(db.get(1, b"key").unwrap().is_none());
+
+		// columns are always indexed by 0..num_columns()
+		db.remove_last_column().unwrap();
+		db.remove_last_column().unwrap();
+		db.remove_last_column().unwrap();
+		assert_eq!(db.num_columns(), 0);
+		db.add_column().unwrap();
+		assert_eq!(db.num_columns(), 1);
+		assert!(db.get(0, b"key").unwrap().is_none());
+	}
+
+	#[test]
+	fn sequence_numbers() {
+		let db = create(1);
+		assert_eq!(db.last_committed(), 0);
+
+		let mut transaction = db.transaction();
+		transaction.put(0, b"key", b"value");
+		assert_eq!(db.write_buffered(transaction).unwrap(), 1);
+		assert_eq!(db.write(db.transaction()).unwrap(), 2);
+		db.update(0, b"key", &|_| None).unwrap();
+		assert_eq!(db.last_committed(), 3);
+	}
+
+	#[test]
+	fn multi_column_write_is_atomic() {
+		let db = Arc::new(create_with_size_limits(2, SizeLimits { max_key_len: None, max_value_len: Some(4) }));
+
+		// a rejected transaction leaves all of its columns untouched
+		let mut transaction = db.transaction();
+		transaction.put(1, b"key", b"one");
+		transaction.put(0, b"key", b"large");
+		assert!(db.write(transaction).is_err());
+		assert!(db.get(0, b"key").unwrap().is_none());
+		assert!(db.get(1, b"key").unwrap().is_none());
+
+		// column 1 is written first, a reader seeing it must see column 0 as well
+		let reader = {
+			let db = db.clone();
+			thread::spawn(move || {
+				for i in 0..1000u32 {
+					let key = i.to_be_bytes();
+					if db.get(1, &key).unwrap().is_some() {
+						assert!(db.get(0, &key).unwrap().is_some());
+					}
+				}
+			})
+		};
+		for i in 0..1000u32 {
+			let mut transaction = db.transaction();
+			transaction.put(1, &i.to_be_bytes(), b"1");
+			transaction.put(0, &i.to_be_bytes(), b"0");
+			db.write(transaction).unwrap();
+		}
+		reader.join().unwrap();
+	}
+
+	#[test]
+	fn size_limits() {
+		let db = create_with_size_limits(1, SizeLimits { max_key_len: None, max_value_len: Some(4) });
+
+		let mut transaction = db.transaction();
+		transaction.put(0, b"small", b"four");
+		assert_eq!(db.write(transaction).unwrap(), 1);
+
+		let mut transaction = db.transaction();
+		transaction.put(0, b"other", b"1");
+		transaction.put(0, b"large", b"five!");
+		let err = db.write(transaction).unwrap_err();
+		assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+		let err = err.into_inner().unwrap().downcast::<SizeLimitError>().unwrap();
+		assert_eq!(*err, SizeLimitError::ValueTooLong { col: 0, key: b"large".to_vec(), len: 5, limit: 4 });
+		assert!(db.get(0, b"other").unwrap().is_none());
+		assert_eq!(db.last_committed(), 1);
+
+		// buffered writes are rejected the same way
+		let mut transaction = db.transaction();
+		transaction.put(0, b"large", b"five!");
+		assert_eq!(db.write_buffered(transaction).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+		assert!(db.get(0, b"large").unwrap().is_none());
+		assert_eq!(db.last_committed(), 1);
+		assert!(db.flush().is_ok());
+
+		assert!(db.update(0, b"small", &|_| Some(b"large".to_vec())).is_err());
+		assert_eq!(&*db.get(0, b"small").unwrap().unwrap(), b"four");
+	}
+
+	#[test]
+	fn shuffled_iteration_is_reproducible() {
+		let fill = |db: &super::InMemory| {
+			let mut transaction = db.transaction();
+			for i in 0..64u8 {
+				transaction.put(0, &[i], &[i]);
+			}
+			db.write(transaction).unwrap();
+		};
+		let keys = |db: &super::InMemory| db.iter(0).map(|(key, _)| key[0]).collect::<Vec<_>>();
+		let sorted = (0..64u8).collect::<Vec<_>>();
+
+		let db = create(1);
+		fill(&db);
+		assert_eq!(db.iteration_order(), IterationOrder::Sorted);
+		assert_eq!(keys(&db), sorted);
+
+		let shuffled = create_with_iteration_order(1, IterationOrder::Shuffled { seed: 42 });
+		fill(&shuffled);
+		let order = keys(&shuffled);
+		assert_ne!(order, sorted);
+		assert_eq!(keys(&shuffled), order);
+		let mut resorted = order.clone();
+		resorted.sort();
+		assert_eq!(resorted, sorted);
+
+		let same_seed = create_with_iteration_order(1, IterationOrder::Shuffled { seed: 42 });
+		fill(&same_seed);
+		assert_eq!(keys(&same_seed), order);
+
+		let other_seed = create_with_iteration_order(1, IterationOrder::Shuffled { seed: 43 });
+		fill(&other_seed);
+		assert_ne!(keys(&other_seed), order);
+
+		assert_eq!(shuffled.iter_from_prefix(0, &[7]).count(), 1);
+	}
 }
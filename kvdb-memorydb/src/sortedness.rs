@@ -0,0 +1,244 @@
+// Copyright 2015-2019 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Test wrapper checking the iteration order guaranteed by `KeyValueDB`.
+
+use kvdb::{display::hex, ColumnGuard, DBTransaction, DBValue, DbHealth, IterOptions, KeyValueDB, UpdateFn};
+use std::io;
+
+/// A `KeyValueDB` wrapper asserting that every iterator of the inner database yields keys in
+/// strictly ascending order and, for `iter_from_prefix`, only keys starting with the prefix.
+///
+/// Panics on violation, printing the offending keys. Meant for tests of backends
+/// and of code relying on ordered iteration.
+pub struct SortednessCheckDb<D> {
+	inner: D,
+}
+
+impl<D: KeyValueDB> SortednessCheckDb<D> {
+	/// Wrap the given database.
+	pub fn new(inner: D) -> Self {
+		SortednessCheckDb { inner }
+	}
+
+	/// Borrow the wrapped database.
+	pub fn inner(&self) -> &D {
+		&self.inner
+	}
+
+	/// Unwrap the database.
+	pub fn into_inner(self) -> D {
+		self.inner
+	}
+}
+
+type KeyValuePair = (Box<[u8]>, Box<[u8]>);
+
+struct CheckedIter<'a> {
+	inner: Box<dyn Iterator<Item = KeyValuePair> + 'a>,
+	col: u32,
+	prefix: Option<&'a [u8]>,
+	previous: Option<Box<[u8]>>,
+	position: usize,
+}
+
+impl<'a> Iterator for CheckedIter<'a> {
+	type Item = KeyValuePair;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let (key, value) = self.inner.next()?;
+		if let Some(prefix) = self.prefix {
+			assert!(
+				key.starts_with(prefix),
+				"column {}: key #{} is outside of the prefix\n  prefix: {}\n     key: {}",
+				self.col,
+				self.position,
+				hex(prefix),
+				hex(&key),
+			);
+		}
+		if let Some(ref previous) = self.previous {
+			assert!(
+				*previous < key,
+				"column {}: key #{} is not greater than the previous one\n  previous: {}\n       key: {}",
+				self.col,
+				self.position,
+				hex(previous),
+				hex(&key),
+			);
+		}
+		self.previous = Some(key.clone());
+		self.position += 1;
+		Some((key, value))
+	}
+}
+
+impl<D: KeyValueDB> KeyValueDB for SortednessCheckDb<D> {
+	fn get(&self, col: u32, key: &[u8]) -> io::Result<Option<DBValue>> {
+		self.inner.get(col, key)
+	}
+
+	fn get_by_prefix(&self, col: u32, prefix: &[u8]) -> Option<Box<[u8]>> {
+		self.inner.get_by_prefix(col, prefix)
+	}
+
+	fn write_buffered(&self, transaction: DBTransaction) -> io::Result<u64> {
+		self.inner.write_buffered(transaction)
+	}
+
+	fn write(&self, transaction: DBTransaction) -> io::Result<u64> {
+		self.inner.write(transaction)
+	}
+
+	fn last_committed(&self) -> u64 {
+		self.inner.last_committed()
+	}
+
+	fn flush(&self) -> io::Result<()> {
+		self.inner.flush()
+	}
+
+	fn update(&self, col: u32, key: &[u8], f: &UpdateFn<'_>) -> io::Result<()> {
+		self.inner.update(col, key, f)
+	}
+
+	fn delete_by_prefix(&self, col: u32, prefix: &[u8]) -> io::Result<u64> {
+		self.inner.delete_by_prefix(col, prefix)
+	}
+
+	fn iter<'a>(&'a self, col: u32) -> Box<dyn Iterator<Item = KeyValuePair> + 'a> {
+		Box::new(CheckedIter { inner: self.inner.iter(col), col, prefix: None, previous: None, position: 0 })
+	}
+
+	fn iter_with_options<'a>(&'a self, col: u32, options: IterOptions) -> Box<dyn Iterator<Item = KeyValuePair> + 'a> {
+		let inner = self.inner.iter_with_options(col, options);
+		Box::new(CheckedIter { inner, col, prefix: None, previous: None, position: 0 })
+	}
+
+	fn iter_from_prefix<'a>(&'a self, col: u32, prefix: &'a [u8]) -> Box<dyn Iterator<Item = KeyValuePair> + 'a> {
+		let inner = self.inner.iter_from_prefix(col, prefix);
+		Box::new(CheckedIter { inner, col, prefix: Some(prefix), previous: None, position: 0 })
+	}
+
+	fn restore(&self, new_db: &str) -> io::Result<()> {
+		self.inner.restore(new_db)
+	}
+
+	fn lock_column(&self, col: u32) -> ColumnGuard<'_> {
+		self.inner.lock_column(col)
+	}
+
+	fn lock_column_shared(&self, col: u32) -> ColumnGuard<'_> {
+		self.inner.lock_column_shared(col)
+	}
+
+	fn health(&self) -> io::Result<DbHealth> {
+		self.inner.health()
+	}
+
+	fn metadata(&self) -> io::Result<Option<DBValue>> {
+		self.inner.metadata()
+	}
+
+	fn set_metadata(&self, value: &[u8]) -> io::Result<()> {
+		self.inner.set_metadata(value)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{KeyValuePair, SortednessCheckDb};
+	use crate::{create, InMemory};
+	use kvdb::{ColumnGuard, DBTransaction, DBValue, KeyValueDB};
+	use std::io;
+
+	/// Returns the contents of a column in reverse order.
+	struct Reversed(InMemory);
+
+	impl KeyValueDB for Reversed {
+		fn get(&self, col: u32, key: &[u8]) -> io::Result<Option<DBValue>> {
+			self.0.get(col, key)
+		}
+
+		fn get_by_prefix(&self, col: u32, prefix: &[u8]) -> Option<Box<[u8]>> {
+			self.0.get_by_prefix(col, prefix)
+		}
+
+		fn write_buffered(&self, transaction: DBTransaction) -> io::Result<u64> {
+			self.0.write_buffered(transaction)
+		}
+
+		fn last_committed(&self) -> u64 {
+			self.0.last_committed()
+		}
+
+		fn flush(&self) -> io::Result<()> {
+			Ok(())
+		}
+
+		fn iter<'a>(&'a self, col: u32) -> Box<dyn Iterator<Item = KeyValuePair> + 'a> {
+			Box::new(self.0.iter(col).collect::<Vec<_>>().into_iter().rev())
+		}
+
+		fn iter_from_prefix<'a>(&'a self, col: u32, _prefix: &'a [u8]) -> Box<dyn Iterator<Item = KeyValuePair> + 'a> {
+			self.0.iter(col)
+		}
+
+		fn restore(&self, new_db: &str) -> io::Result<()> {
+			self.0.restore(new_db)
+		}
+
+		fn lock_column(&self, col: u32) -> ColumnGuard<'_> {
+			self.0.lock_column(col)
+		}
+
+		fn lock_column_shared(&self, col: u32) -> ColumnGuard<'_> {
+			self.0.lock_column_shared(col)
+		}
+	}
+
+	fn populate<D: KeyValueDB>(db: &D) {
+		let mut transaction = db.transaction();
+		for key in &[b"a1", b"a2", b"b1"] {
+			transaction.put(0, *key, *key);
+		}
+		db.write(transaction).unwrap();
+	}
+
+	#[test]
+	fn accepts_in_memory_db() {
+		let db = SortednessCheckDb::new(create(1));
+		populate(&db);
+		assert_eq!(db.iter(0).count(), 3);
+		assert_eq!(db.iter_from_prefix(0, b"a").count(), 2);
+	}
+
+	#[test]
+	#[should_panic(expected = "key #1 is not greater than the previous one")]
+	fn detects_unordered_keys() {
+		let db = SortednessCheckDb::new(Reversed(create(1)));
+		populate(&db);
+		db.iter(0).for_each(drop);
+	}
+
+	#[test]
+	#[should_panic(expected = "key #2 is outside of the prefix")]
+	fn detects_keys_outside_of_prefix() {
+		let db = SortednessCheckDb::new(Reversed(create(1)));
+		populate(&db);
+		db.iter_from_prefix(0, b"a").for_each(drop);
+	}
+}
@@ -0,0 +1,84 @@
+// Copyright 2015-2019 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Periodic flushing of the write buffer in a background thread.
+
+use std::{
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc, Weak,
+	},
+	thread,
+	time::{Duration, Instant},
+};
+
+use log::warn;
+
+use crate::Database;
+
+/// Handle of the background thread started with `spawn`.
+///
+/// The thread is stopped when the handle is dropped, or when the database is dropped.
+pub struct AutoFlush {
+	stop: Arc<AtomicBool>,
+	thread: Option<thread::JoinHandle<()>>,
+}
+
+/// Flush the write buffer of `db` every `interval`.
+///
+/// The thread only holds a weak reference and doesn't keep the database open.
+pub fn spawn(db: &Arc<Database>, interval: Duration) -> AutoFlush {
+	let db = Arc::downgrade(db);
+	let stop = Arc::new(AtomicBool::new(false));
+	let thread = {
+		let stop = stop.clone();
+		thread::Builder::new()
+			.name("kvdb-auto-flush".into())
+			.spawn(move || run(db, interval, &stop))
+			.expect("the OS can spawn a thread")
+	};
+	AutoFlush { stop, thread: Some(thread) }
+}
+
+fn run(db: Weak<Database>, interval: Duration, stop: &AtomicBool) {
+	let mut next = Instant::now() + interval;
+	while !stop.load(Ordering::SeqCst) {
+		let now = Instant::now();
+		if now < next {
+			thread::park_timeout(next - now);
+			continue;
+		}
+		next = now + interval;
+		match db.upgrade() {
+			Some(db) => {
+				if let Err(err) = db.flush() {
+					warn!("Failed to flush the write buffer: {}", err);
+				}
+			}
+			None => return,
+		}
+	}
+}
+
+impl Drop for AutoFlush {
+	fn drop(&mut self) {
+		self.stop.store(true, Ordering::SeqCst);
+		if let Some(thread) = self.thread.take() {
+			thread.thread().unpark();
+			let _ = thread.join();
+		}
+	}
+}
@@ -0,0 +1,224 @@
+// Copyright 2015-2019 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Forwarding of the RocksDB info log, the `LOG` file in the database directory, to the `log` crate.
+//!
+//! Messages are logged with the `rocksdb` target. Retention of the rotated files
+//! is controlled by `DatabaseConfig::keep_log_file_num`.
+
+use std::{
+	fs::{self, File},
+	io::{BufRead, BufReader},
+	path::{Path, PathBuf},
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc,
+	},
+	thread,
+	time::Duration,
+};
+
+use log::{log, Level};
+
+use crate::Database;
+
+/// Configuration of the forwarding.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InfoLogConfig {
+	/// How often the file is checked for new lines.
+	pub poll_interval: Duration,
+	/// Level of the informational messages, which make up most of the log.
+	/// Warnings and errors keep their level.
+	pub info_level: Level,
+}
+
+impl Default for InfoLogConfig {
+	fn default() -> InfoLogConfig {
+		InfoLogConfig { poll_interval: Duration::from_secs(1), info_level: Level::Debug }
+	}
+}
+
+/// Handle of the background thread started with `spawn`, stopped when the handle is dropped.
+pub struct InfoLog {
+	stop: Arc<AtomicBool>,
+	thread: Option<thread::JoinHandle<()>>,
+}
+
+/// Forward the info log of the database, following it when RocksDB rotates the file.
+pub fn spawn(db: &Database, config: InfoLogConfig) -> InfoLog {
+	let path = Path::new(&db.path).join("LOG");
+	let stop = Arc::new(AtomicBool::new(false));
+	let thread = {
+		let stop = stop.clone();
+		thread::Builder::new()
+			.name("kvdb-info-log".into())
+			.spawn(move || {
+				let mut tail = Tail::new(path);
+				let mut emit = |level, message: &str| log!(target: "rocksdb", level, "{}", message);
+				while !stop.load(Ordering::SeqCst) {
+					tail.poll(config.info_level, &mut emit);
+					thread::park_timeout(config.poll_interval);
+				}
+				tail.poll(config.info_level, &mut emit);
+			})
+			.expect("the OS can spawn a thread")
+	};
+	InfoLog { stop, thread: Some(thread) }
+}
+
+impl Drop for InfoLog {
+	fn drop(&mut self) {
+		self.stop.store(true, Ordering::SeqCst);
+		if let Some(thread) = self.thread.take() {
+			thread.thread().unpark();
+			let _ = thread.join();
+		}
+	}
+}
+
+/// Reads the lines appended to a file, reopening it when it's replaced.
+struct Tail {
+	path: PathBuf,
+	reader: Option<BufReader<File>>,
+	id: Option<FileId>,
+	read: u64,
+	line: String,
+}
+
+impl Tail {
+	fn new(path: PathBuf) -> Tail {
+		Tail { path, reader: None, id: None, read: 0, line: String::new() }
+	}
+
+	fn poll(&mut self, info_level: Level, emit: &mut dyn FnMut(Level, &str)) {
+		self.read_lines(info_level, emit);
+		// the file has been rotated, the rest of the old one is read above
+		if let Ok(metadata) = fs::metadata(&self.path) {
+			let replaced = match (self.id, file_id(&metadata)) {
+				(Some(old), Some(new)) => old != new,
+				_ => metadata.len() < self.read,
+			};
+			if self.reader.is_none() || replaced {
+				if let Ok(file) = File::open(&self.path) {
+					self.reader = Some(BufReader::new(file));
+					self.id = file_id(&metadata);
+					self.read = 0;
+					self.read_lines(info_level, emit);
+				}
+			}
+		}
+	}
+
+	fn read_lines(&mut self, info_level: Level, emit: &mut dyn FnMut(Level, &str)) {
+		let reader = match self.reader {
+			Some(ref mut reader) => reader,
+			None => return,
+		};
+		// a line without a newline is still being written, it's completed by the next read
+		while let Ok(n) = reader.read_line(&mut self.line) {
+			if n == 0 || !self.line.ends_with('\n') {
+				break;
+			}
+			self.read += self.line.len() as u64;
+			let (level, message) = parse_line(self.line.trim_end(), info_level);
+			emit(level, message);
+			self.line.clear();
+		}
+	}
+}
+
+/// Split a line into its level and message, removing the timestamp and thread id.
+///
+/// Lines look like `2019/12/01-10:20:30.123456 7f8e2ce00700 [WARN] [db/column_family.cc:823] message`,
+/// the level is only present for levels other than info.
+fn parse_line(line: &str, info_level: Level) -> (Level, &str) {
+	let mut parts = line.splitn(3, ' ');
+	let message = match (parts.next(), parts.next(), parts.next()) {
+		(Some(timestamp), Some(_thread), Some(message)) if timestamp.len() == 26 && timestamp.contains('-') => message,
+		_ => return (info_level, line),
+	};
+	let levels =
+		[("[DEBUG] ", Level::Trace), ("[WARN] ", Level::Warn), ("[ERROR] ", Level::Error), ("[FATAL] ", Level::Error)];
+	for &(prefix, level) in levels.iter() {
+		if let Some(message) = message.strip_prefix(prefix) {
+			return (level, message);
+		}
+	}
+	(info_level, message.strip_prefix("[HEADER] ").unwrap_or(message))
+}
+
+#[cfg(unix)]
+type FileId = (u64, u64);
+#[cfg(not(unix))]
+type FileId = ();
+
+#[cfg(unix)]
+fn file_id(metadata: &fs::Metadata) -> Option<FileId> {
+	use std::os::unix::fs::MetadataExt;
+	Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &fs::Metadata) -> Option<FileId> {
+	None
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{parse_line, Tail};
+	use log::Level;
+	use std::{fs, io::Write};
+	use tempdir::TempDir;
+
+	#[test]
+	fn parse_levels() {
+		let line = "2019/12/01-10:20:30.123456 7f8e2ce00700 [WARN] [db/column_family.cc:823] Stalling writes";
+		assert_eq!(parse_line(line, Level::Debug), (Level::Warn, "[db/column_family.cc:823] Stalling writes"));
+		let line = "2019/12/01-10:20:30.123456 7f8e2ce00700 [db/db_impl.cc:390] Shutdown complete";
+		assert_eq!(parse_line(line, Level::Info), (Level::Info, "[db/db_impl.cc:390] Shutdown complete"));
+		let line = "2019/12/01-10:20:30.123456 7f8e2ce00700 [ERROR] Corruption";
+		assert_eq!(parse_line(line, Level::Debug), (Level::Error, "Corruption"));
+		assert_eq!(
+			parse_line("  Options.max_open_files: 512", Level::Debug),
+			(Level::Debug, "  Options.max_open_files: 512")
+		);
+	}
+
+	#[test]
+	fn follows_appended_and_rotated_files() {
+		let dir = TempDir::new("info_log").unwrap();
+		let path = dir.path().join("LOG");
+		let mut tail = Tail::new(path.clone());
+		let mut lines = Vec::new();
+		let mut poll = |tail: &mut Tail| {
+			tail.poll(Level::Info, &mut |_, message: &str| lines.push(message.to_owned()));
+		};
+
+		poll(&mut tail);
+		let mut file = fs::File::create(&path).unwrap();
+		write!(file, "first\nsec").unwrap();
+		poll(&mut tail);
+		writeln!(file, "ond").unwrap();
+		poll(&mut tail);
+
+		fs::rename(&path, dir.path().join("LOG.old.1")).unwrap();
+		writeln!(file, "last of old").unwrap();
+		fs::write(&path, "new\n").unwrap();
+		poll(&mut tail);
+
+		assert_eq!(lines, vec!["first", "second", "last of old", "new"]);
+	}
+}
@@ -14,20 +14,40 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
+pub mod auto_flush;
+pub mod info_log;
 mod iter;
-
-use std::{cmp, collections::HashMap, convert::identity, error, fs, io, mem, path::Path, result};
+pub mod slow_ops;
+mod verify;
+
+use std::{
+	cmp,
+	collections::HashMap,
+	convert::identity,
+	error, fs, io, mem,
+	path::Path,
+	result,
+	sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+	time::SystemTime,
+};
 
 use parking_lot::{Mutex, MutexGuard, RwLock};
 use rocksdb::{
-	BlockBasedOptions, ColumnFamily, ColumnFamilyDescriptor, Error, Options, ReadOptions, WriteBatch, WriteOptions, DB,
+	BlockBasedIndexType, BlockBasedOptions, ColumnFamily, ColumnFamilyDescriptor, Error, IteratorMode, Options,
+	ReadOptions, WriteBatch, WriteOptions, DB,
 };
 
 use crate::iter::KeyValuePair;
+pub use crate::verify::{CorruptRange, VerifyReport};
+
+use crate::slow_ops::{SlowOps, SlowOpsConfig, Subject, TimedIter};
 use elastic_array::ElasticArray32;
 use fs_swap::{swap, swap_nonatomic};
 use interleaved_ordered::interleave_ordered;
-use kvdb::{DBOp, DBTransaction, DBValue, KeyValueDB};
+use kvdb::{
+	ColumnGuard, ColumnLocks, DBOp, DBTransaction, DBValue, DbHealth, IterOptions, KeyValueDB, Metadata,
+	SizeLimitError, SizeLimits, UpdateFn,
+};
 use log::{debug, warn};
 
 #[cfg(target_os = "linux")]
@@ -153,6 +173,25 @@ impl CompactionProfile {
 	}
 }
 
+/// Options of the block-based tables used by all columns.
+///
+/// The size of data blocks is part of the `CompactionProfile`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BlockOptions {
+	/// Use a partitioned (two-level) index, so that only the top level of large indexes
+	/// needs to be kept in memory.
+	pub partitioned_index: bool,
+	/// Store index and filter blocks in the block cache, together with the data blocks, instead of
+	/// keeping them in memory for every open table. L0 index and filter blocks are pinned in the cache.
+	pub cache_index_and_filter_blocks: bool,
+}
+
+impl Default for BlockOptions {
+	fn default() -> BlockOptions {
+		BlockOptions { partitioned_index: false, cache_index_and_filter_blocks: true }
+	}
+}
+
 /// Database configuration
 #[derive(Clone)]
 pub struct DatabaseConfig {
@@ -162,6 +201,12 @@ pub struct DatabaseConfig {
 	/// write buffer size for each column including the default one.
 	/// If the memory budget of a column is not specified,
 	/// `DB_DEFAULT_COLUMN_MEMORY_BUDGET_MB` is used for that column.
+	///
+	/// All columns of a `Database` share one block cache, sized after the total budget. It can't be
+	/// shared with other databases: `BlockBasedOptions` of the `rocksdb` bindings has no accessor for
+	/// its cache, and the bindings expose no `Env` or rate limiter objects. Databases opened in the
+	/// same process do share RocksDB's default `Env` and its background threads. Split the budget
+	/// between the databases of a process accordingly.
 	pub memory_budget: HashMap<u32, MiB>,
 	/// Compaction profile.
 	pub compaction: CompactionProfile,
@@ -172,7 +217,27 @@ pub struct DatabaseConfig {
 	/// The number of columns must not be zero.
 	pub columns: u32,
 	/// Specify the maximum number of info/debug log files to be kept.
+	///
+	/// See `info_log` to forward the log to the `log` crate.
 	pub keep_log_file_num: i32,
+	/// Block-based table options.
+	pub block_options: BlockOptions,
+	/// Flush as soon as the keys and values written with `write_buffered` exceed this many bytes.
+	///
+	/// Use `auto_flush::spawn` to also flush periodically.
+	pub max_buffered_bytes: Option<usize>,
+	/// Limits of key and value sizes.
+	///
+	/// `write` and `write_buffered` return a `SizeLimitError` for transactions exceeding the limits,
+	/// without applying or buffering any of their changes.
+	pub size_limits: SizeLimits,
+	/// Verify checksums on every read, and scan the whole database with `Database::verify` when
+	/// opening it. Opening fails with `io::ErrorKind::InvalidData` if any corruption is found.
+	pub paranoid: bool,
+	/// Log `get`, `write`, `flush` calls and iterations slower than a threshold.
+	///
+	/// Disabled by default. See `slow_ops`.
+	pub slow_ops: Option<SlowOpsConfig>,
 }
 
 impl DatabaseConfig {
@@ -221,6 +286,11 @@ impl Default for DatabaseConfig {
 			compaction: CompactionProfile::default(),
 			columns: 1,
 			keep_log_file_num: 1,
+			block_options: BlockOptions::default(),
+			max_buffered_bytes: None,
+			size_limits: SizeLimits::unlimited(),
+			paranoid: false,
+			slow_ops: None,
 		}
 	}
 }
@@ -237,6 +307,10 @@ impl DBAndColumns {
 }
 
 /// Key-Value database.
+///
+/// Data is stored unencrypted. RocksDB's encrypted `Env` can't be plugged in: the `rocksdb`
+/// bindings expose no `Env` and the C API has no way to construct an encrypted one. Encrypt the
+/// values before writing them, or the filesystem holding the database, instead.
 pub struct Database {
 	db: RwLock<Option<DBAndColumns>>,
 	config: DatabaseConfig,
@@ -251,6 +325,19 @@ pub struct Database {
 	// Prevents concurrent flushes.
 	// Value indicates if a flush is in progress.
 	flushing_lock: Mutex<bool>,
+	// Sequence number of the last transaction visible to readers.
+	// Assigned under the `overlay` lock, in the order the transactions become visible.
+	committed_seq: AtomicU64,
+	// Sequence number of the last transaction written to disk.
+	flushed_seq: AtomicU64,
+	// Size of the keys and values added to `overlay` since the last flush.
+	buffered_bytes: AtomicUsize,
+	// Time of the last successful write to disk.
+	last_flush: Mutex<Option<SystemTime>>,
+	// Advisory locks of the columns.
+	column_locks: ColumnLocks,
+	// Counter and log of slow operations.
+	slow_ops: SlowOps,
 }
 
 #[inline]
@@ -265,6 +352,14 @@ fn check_for_corruption<T, P: AsRef<Path>>(path: P, res: result::Result<T, Error
 	res.map_err(other_io_err)
 }
 
+/// The smallest key greater than all the keys starting with `prefix`, if any.
+fn prefix_end(prefix: &[u8]) -> Option<Vec<u8>> {
+	let last = prefix.iter().rposition(|b| *b != 0xff)?;
+	let mut end = prefix[..=last].to_vec();
+	end[last] += 1;
+	Some(end)
+}
+
 fn is_corrupted(err: &Error) -> bool {
 	err.as_ref().starts_with("Corruption:")
 		|| err.as_ref().starts_with("Invalid argument: You have to open all column families")
@@ -278,7 +373,7 @@ fn generate_options(config: &DatabaseConfig) -> Options {
 	opts.create_if_missing(true);
 	opts.set_max_open_files(config.max_open_files);
 	opts.set_bytes_per_sync(1 * MB as u64);
-	opts.set_keep_log_file_num(1);
+	opts.set_keep_log_file_num(config.keep_log_file_num as usize);
 	opts.increase_parallelism(cmp::max(1, num_cpus::get() as i32 / 2));
 
 	opts
@@ -292,11 +387,17 @@ fn generate_block_based_options(config: &DatabaseConfig) -> BlockBasedOptions {
 	// https://github.com/facebook/rocksdb/wiki/Setup-Options-and-Basic-Tuning#block-cache-size
 	let cache_size = config.memory_budget() / 3;
 	block_opts.set_lru_cache(cache_size);
-	// "index and filter blocks will be stored in block cache, together with all other data blocks."
-	// See: https://github.com/facebook/rocksdb/wiki/Memory-usage-in-RocksDB#indexes-and-filter-blocks
-	block_opts.set_cache_index_and_filter_blocks(true);
-	// Don't evict L0 filter/index blocks from the cache
-	block_opts.set_pin_l0_filter_and_index_blocks_in_cache(true);
+	if config.block_options.partitioned_index {
+		// See: https://github.com/facebook/rocksdb/wiki/Partitioned-Index-Filters
+		block_opts.set_index_type(BlockBasedIndexType::TwoLevelIndexSearch);
+	}
+	if config.block_options.cache_index_and_filter_blocks {
+		// "index and filter blocks will be stored in block cache, together with all other data blocks."
+		// See: https://github.com/facebook/rocksdb/wiki/Memory-usage-in-RocksDB#indexes-and-filter-blocks
+		block_opts.set_cache_index_and_filter_blocks(true);
+		// Don't evict L0 filter/index blocks from the cache
+		block_opts.set_pin_l0_filter_and_index_blocks_in_cache(true);
+	}
 	block_opts.set_bloom_filter(10, true);
 
 	block_opts
@@ -304,6 +405,8 @@ fn generate_block_based_options(config: &DatabaseConfig) -> BlockBasedOptions {
 
 impl Database {
 	const CORRUPTION_FILE_NAME: &'static str = "CORRUPTED";
+	/// Key of the metadata header in the default column family, which holds no other data.
+	const METADATA_KEY: &'static [u8] = b"kvdb-metadata";
 
 	/// Open database file. Creates if it does not exist.
 	///
@@ -328,7 +431,7 @@ impl Database {
 
 		let write_opts = WriteOptions::default();
 		let mut read_opts = ReadOptions::default();
-		read_opts.set_verify_checksums(false);
+		read_opts.set_verify_checksums(config.paranoid);
 
 		let cf_descriptors: Vec<_> = (0..config.columns)
 			.map(|i| ColumnFamilyDescriptor::new(&column_names[i as usize], config.column_config(&block_opts, i)))
@@ -340,9 +443,7 @@ impl Database {
 				match DB::open_cf(&opts, path, &[] as &[&str]) {
 					Ok(mut db) => {
 						for (i, name) in column_names.iter().enumerate() {
-							let _ = db
-								.create_cf(name, &config.column_config(&block_opts, i as u32))
-								.map_err(other_io_err)?;
+							db.create_cf(name, &config.column_config(&block_opts, i as u32)).map_err(other_io_err)?;
 						}
 						Ok(db)
 					}
@@ -368,17 +469,87 @@ impl Database {
 			}
 			Err(s) => return Err(other_io_err(s)),
 		};
-		Ok(Database {
+		let db = Database {
 			db: RwLock::new(Some(DBAndColumns { db, column_names })),
 			config: config.clone(),
 			overlay: RwLock::new((0..config.columns).map(|_| HashMap::new()).collect()),
 			flushing: RwLock::new((0..config.columns).map(|_| HashMap::new()).collect()),
 			flushing_lock: Mutex::new(false),
+			committed_seq: AtomicU64::new(0),
+			flushed_seq: AtomicU64::new(0),
+			buffered_bytes: AtomicUsize::new(0),
+			last_flush: Mutex::new(None),
+			column_locks: ColumnLocks::new(),
+			slow_ops: SlowOps::new(config.slow_ops),
 			path: path.to_owned(),
 			read_opts,
 			write_opts,
 			block_opts,
-		})
+		};
+		if config.paranoid {
+			let report = db.verify(None)?;
+			if let Some(range) = report.corrupted.first() {
+				warn!("DB corrupted: {}. Repair will be triggered on next restart", range);
+				let _ = fs::File::create(Path::new(path).join(Database::CORRUPTION_FILE_NAME));
+				return Err(io::Error::new(
+					io::ErrorKind::InvalidData,
+					format!("Database verification failed in {}", range),
+				));
+			}
+		}
+		Ok(db)
+	}
+
+	/// Open database file and check its metadata header, writing it if the database is new.
+	///
+	/// Fails with `kvdb::MetadataError` if the database was created with a different version
+	/// or number of columns, or if it has data but no header. The header of an existing database
+	/// is read before its column families are opened, so that a different number of columns is
+	/// reported as a mismatch too. `metadata.columns` is expected to match `config.columns`.
+	pub fn open_or_create(config: &DatabaseConfig, path: &str, metadata: &Metadata) -> io::Result<Database> {
+		Database::check_existing_metadata(path, metadata)?;
+		kvdb::open_or_create(|| Database::open(config, path), metadata)
+	}
+
+	/// Check the header of the database at `path`, if there is one, opening it with whatever
+	/// column families it has. Failures to open it are left to `open`, which attempts repairs.
+	fn check_existing_metadata(path: &str, expected: &Metadata) -> io::Result<()> {
+		let opts = Options::default();
+		let opened = DB::list_cf(&opts, path).and_then(|names| DB::open_cf(&opts, path, &names).map(|db| (db, names)));
+		let (db, names) = match opened {
+			Ok(opened) => opened,
+			Err(_) => return Ok(()),
+		};
+		match db.get_pinned(Self::METADATA_KEY).map_err(other_io_err)? {
+			Some(raw) => kvdb::check_header(&raw, expected)?,
+			None => {
+				for name in names.iter().filter(|name| name.as_str() != "default") {
+					let cf = db.cf_handle(name).expect("the column family has just been opened; qed");
+					if db.iterator_cf(cf, IteratorMode::Start).map_err(other_io_err)?.next().is_some() {
+						return Err(kvdb::MetadataError::Missing.into());
+					}
+				}
+			}
+		}
+		Ok(())
+	}
+
+	/// Read the raw metadata header.
+	pub fn metadata(&self) -> io::Result<Option<DBValue>> {
+		match *self.db.read() {
+			Some(ref cfs) => {
+				cfs.db.get_pinned(Self::METADATA_KEY).map(|r| r.map(|v| DBValue::from_slice(&v))).map_err(other_io_err)
+			}
+			None => Err(other_io_err("Database is closed")),
+		}
+	}
+
+	/// Store the raw metadata header.
+	pub fn set_metadata(&self, value: &[u8]) -> io::Result<()> {
+		match *self.db.read() {
+			Some(ref cfs) => cfs.db.put_opt(Self::METADATA_KEY, value, &self.write_opts).map_err(other_io_err),
+			None => Err(other_io_err("Database is closed")),
+		}
 	}
 
 	/// Helper to create new transaction for this database.
@@ -387,15 +558,93 @@ impl Database {
 	}
 
 	/// Commit transaction to database.
-	pub fn write_buffered(&self, tr: DBTransaction) {
-		let mut overlay = self.overlay.write();
-		let ops = tr.ops;
-		for op in ops {
-			match op {
-				DBOp::Insert { col, key, value } => overlay[col as usize].insert(key, KeyState::Insert(value)),
-				DBOp::Delete { col, key } => overlay[col as usize].insert(key, KeyState::Delete),
-			};
+	///
+	/// Returns the sequence number of the transaction, see `last_committed`. A transaction
+	/// exceeding `DatabaseConfig::size_limits` is rejected before any of its changes is buffered.
+	pub fn write_buffered(&self, tr: DBTransaction) -> io::Result<u64> {
+		self.config.size_limits.check(&tr)?;
+		let seq = {
+			let mut overlay = self.overlay.write();
+			let ops = tr.ops;
+			let mut bytes = 0;
+			for op in ops {
+				bytes += op.key().len();
+				match op {
+					DBOp::Insert { col, key, value } => {
+						bytes += value.len();
+						overlay[col as usize].insert(key, KeyState::Insert(value))
+					}
+					DBOp::Delete { col, key } => overlay[col as usize].insert(key, KeyState::Delete),
+				};
+			}
+			self.buffered(bytes)
+		};
+		self.auto_flush();
+		Ok(seq)
+	}
+
+	/// Account for a transaction added to the overlay and return its sequence number.
+	/// Must be called under the `overlay` lock.
+	fn buffered(&self, bytes: usize) -> u64 {
+		self.buffered_bytes.fetch_add(bytes, Ordering::SeqCst);
+		self.committed_seq.fetch_add(1, Ordering::SeqCst) + 1
+	}
+
+	/// Return the sequence number of a transaction written straight to disk.
+	/// Must be called under the `overlay` lock, with the locked `overlay`.
+	///
+	/// The transaction counts as flushed unless buffered transactions before it are still waiting.
+	fn committed_directly(&self, overlay: &[HashMap<ElasticArray32<u8>, KeyState>]) -> u64 {
+		let seq = self.committed_seq.fetch_add(1, Ordering::SeqCst) + 1;
+		if overlay.iter().all(HashMap::is_empty) && self.flushing.read().iter().all(HashMap::is_empty) {
+			self.flushed_seq.fetch_max(seq, Ordering::SeqCst);
 		}
+		seq
+	}
+
+	/// Flush if the buffered data exceeds `DatabaseConfig::max_buffered_bytes`.
+	fn auto_flush(&self) {
+		if let Some(max) = self.config.max_buffered_bytes {
+			if self.buffered_bytes.load(Ordering::SeqCst) > max {
+				if let Err(err) = self.flush() {
+					warn!("Failed to flush the write buffer: {}", err);
+				}
+			}
+		}
+	}
+
+	/// Sequence number of the last transaction visible to readers.
+	///
+	/// Transactions are numbered from 1, so 0 means nothing has been written since opening.
+	pub fn last_committed(&self) -> u64 {
+		self.committed_seq.load(Ordering::SeqCst)
+	}
+
+	/// Sequence number of the last transaction that has been flushed to disk,
+	/// together with all transactions before it.
+	pub fn last_flushed(&self) -> u64 {
+		self.flushed_seq.load(Ordering::SeqCst)
+	}
+
+	/// Number of operations slower than `SlowOpsConfig::threshold` since opening,
+	/// including those not logged. Always zero if `DatabaseConfig::slow_ops` is `None`.
+	pub fn slow_operations(&self) -> u64 {
+		self.slow_ops.count()
+	}
+
+	/// Report whether the database is usable and how much data is waiting to be flushed.
+	pub fn health(&self) -> io::Result<DbHealth> {
+		let background_errors = match *self.db.read() {
+			Some(ref cfs) => cfs.db.property_int_value("rocksdb.background-errors").map_err(other_io_err)?.unwrap_or(0),
+			None => return Ok(DbHealth::default()),
+		};
+		Ok(DbHealth {
+			open: true,
+			background_errors,
+			buffered_bytes: self.buffered_bytes.load(Ordering::SeqCst) as u64,
+			pending_transactions: self.last_committed().saturating_sub(self.last_flushed()),
+			last_flush: *self.last_flush.lock(),
+		})
 	}
 
 	/// Commit buffered changes to database. Must be called under `flush_lock`
@@ -403,7 +652,14 @@ impl Database {
 		match *self.db.read() {
 			Some(ref cfs) => {
 				let mut batch = WriteBatch::default();
-				mem::swap(&mut *self.overlay.write(), &mut *self.flushing.write());
+				let pending = {
+					let mut overlay = self.overlay.write();
+					mem::swap(&mut *overlay, &mut *self.flushing.write());
+					self.buffered_bytes.store(0, Ordering::SeqCst);
+					self.committed_seq.load(Ordering::SeqCst)
+				};
+				let ops = self.flushing.read().iter().map(HashMap::len).sum();
+				let _timer = self.slow_ops.time("flush", Subject::Batch { ops });
 				{
 					for (c, column) in self.flushing.read().iter().enumerate() {
 						for (key, state) in column.iter() {
@@ -422,6 +678,15 @@ impl Database {
 					column.clear();
 					column.shrink_to_fit();
 				}
+				// transactions written directly during the flush are on disk too, unless buffered ones came in between
+				let overlay = self.overlay.read();
+				let flushed = if overlay.iter().all(HashMap::is_empty) {
+					self.committed_seq.load(Ordering::SeqCst)
+				} else {
+					pending
+				};
+				self.flushed_seq.fetch_max(flushed, Ordering::SeqCst);
+				*self.last_flush.lock() = Some(SystemTime::now());
 				Ok(())
 			}
 			None => Err(other_io_err("Database is closed")),
@@ -444,14 +709,20 @@ impl Database {
 	}
 
 	/// Commit transaction to database.
-	pub fn write(&self, tr: DBTransaction) -> io::Result<()> {
+	///
+	/// Returns the sequence number of the transaction, assigned once it has been written.
+	pub fn write(&self, tr: DBTransaction) -> io::Result<u64> {
+		self.config.size_limits.check(&tr)?;
+		let _timer = self.slow_ops.time("write", Subject::Batch { ops: tr.ops.len() });
 		match *self.db.read() {
 			Some(ref cfs) => {
 				let mut batch = WriteBatch::default();
+				// held until the sequence number is assigned, so that numbers follow the commit order
+				let mut overlay = self.overlay.write();
 				let ops = tr.ops;
 				for op in ops {
 					// remove any buffered operation for this key
-					self.overlay.write()[op.col() as usize].remove(op.key());
+					overlay[op.col() as usize].remove(op.key());
 
 					let cf = cfs.cf(op.col() as usize);
 
@@ -461,7 +732,9 @@ impl Database {
 					};
 				}
 
-				check_for_corruption(&self.path, cfs.db.write_opt(batch, &self.write_opts))
+				check_for_corruption(&self.path, cfs.db.write_opt(batch, &self.write_opts))?;
+				*self.last_flush.lock() = Some(SystemTime::now());
+				Ok(self.committed_directly(&overlay))
 			}
 			None => Err(other_io_err("Database is closed")),
 		}
@@ -469,27 +742,78 @@ impl Database {
 
 	/// Get value by key.
 	pub fn get(&self, col: u32, key: &[u8]) -> io::Result<Option<DBValue>> {
+		let _timer = self.slow_ops.time("get", Subject::Key { col, len: key.len() });
 		match *self.db.read() {
-			Some(ref cfs) => {
-				let overlay = &self.overlay.read()[col as usize];
-				match overlay.get(key) {
+			Some(ref cfs) => self.get_with_overlay(cfs, &self.overlay.read()[col as usize], col, key),
+			None => Ok(None),
+		}
+	}
+
+	/// Look the key up in the given overlay, then in the values being flushed and finally on disk.
+	fn get_with_overlay(
+		&self,
+		cfs: &DBAndColumns,
+		overlay: &HashMap<ElasticArray32<u8>, KeyState>,
+		col: u32,
+		key: &[u8],
+	) -> io::Result<Option<DBValue>> {
+		match overlay.get(key) {
+			Some(&KeyState::Insert(ref value)) => Ok(Some(value.clone())),
+			Some(&KeyState::Delete) => Ok(None),
+			None => {
+				let flushing = &self.flushing.read()[col as usize];
+				match flushing.get(key) {
 					Some(&KeyState::Insert(ref value)) => Ok(Some(value.clone())),
 					Some(&KeyState::Delete) => Ok(None),
-					None => {
-						let flushing = &self.flushing.read()[col as usize];
-						match flushing.get(key) {
-							Some(&KeyState::Insert(ref value)) => Ok(Some(value.clone())),
-							Some(&KeyState::Delete) => Ok(None),
-							None => cfs
-								.db
-								.get_pinned_cf_opt(cfs.cf(col as usize), key, &self.read_opts)
-								.map(|r| r.map(|v| DBValue::from_slice(&v)))
-								.map_err(other_io_err),
-						}
-					}
+					None => cfs
+						.db
+						.get_pinned_cf_opt(cfs.cf(col as usize), key, &self.read_opts)
+						.map(|r| r.map(|v| DBValue::from_slice(&v)))
+						.map_err(other_io_err),
 				}
 			}
-			None => Ok(None),
+		}
+	}
+
+	/// Atomically replace the value of `key` with the result of `f` applied to the current value.
+	///
+	/// The new value is buffered like with `write_buffered`. `f` is called without holding
+	/// the overlay lock and the update is retried if the value changed in the meantime.
+	pub fn update(&self, col: u32, key: &[u8], f: &UpdateFn<'_>) -> io::Result<()> {
+		while !self.try_update(col, key, f)? {}
+		self.auto_flush();
+		Ok(())
+	}
+
+	/// Apply `f` once, returns `false` if the value changed while `f` was computing the new one.
+	fn try_update(&self, col: u32, key: &[u8], f: &UpdateFn<'_>) -> io::Result<bool> {
+		let current = self.get(col, key)?;
+		let mut ekey = ElasticArray32::new();
+		ekey.append_slice(key);
+		let op = match f(current.as_ref().map(|v| &v[..])) {
+			Some(value) => DBOp::Insert { col, key: ekey, value: DBValue::from_vec(value) },
+			None => DBOp::Delete { col, key: ekey },
+		};
+		self.config.size_limits.check_op(&op)?;
+		match *self.db.read() {
+			Some(ref cfs) => {
+				let mut overlay = self.overlay.write();
+				// compare-and-swap: only apply `f` to the value that is still current
+				if self.get_with_overlay(cfs, &overlay[col as usize], col, key)? != current {
+					return Ok(false);
+				}
+				let bytes = match op {
+					DBOp::Insert { ref key, ref value, .. } => key.len() + value.len(),
+					DBOp::Delete { ref key, .. } => key.len(),
+				};
+				match op {
+					DBOp::Insert { key, value, .. } => overlay[col as usize].insert(key, KeyState::Insert(value)),
+					DBOp::Delete { key, .. } => overlay[col as usize].insert(key, KeyState::Delete),
+				};
+				self.buffered(bytes);
+				Ok(true)
+			}
+			None => Err(other_io_err("Database is closed")),
 		}
 	}
 
@@ -503,6 +827,7 @@ impl Database {
 	/// Will hold a lock until the iterator is dropped
 	/// preventing the database from being closed.
 	pub fn iter<'a>(&'a self, col: u32) -> impl Iterator<Item = KeyValuePair> + 'a {
+		let timer = self.slow_ops.time("iter", Subject::Key { col, len: 0 });
 		let read_lock = self.db.read();
 		let optional = if read_lock.is_some() {
 			let overlay_data = {
@@ -525,13 +850,14 @@ impl Database {
 		} else {
 			None
 		};
-		optional.into_iter().flat_map(identity)
+		TimedIter::new(optional.into_iter().flat_map(identity), timer)
 	}
 
 	/// Get database iterator from prefix for flushed data.
 	/// Will hold a lock until the iterator is dropped
 	/// preventing the database from being closed.
 	fn iter_from_prefix<'a>(&'a self, col: u32, prefix: &'a [u8]) -> impl Iterator<Item = iter::KeyValuePair> + 'a {
+		let timer = self.slow_ops.time("iter_from_prefix", Subject::Key { col, len: prefix.len() });
 		let read_lock = self.db.read();
 		let optional = if read_lock.is_some() {
 			let guarded = iter::ReadGuardedIterator::new_from_prefix(read_lock, col, prefix);
@@ -540,11 +866,32 @@ impl Database {
 			None
 		};
 		// workaround for https://github.com/facebook/rocksdb/issues/2343
-		optional.into_iter().flat_map(identity).filter(move |(k, _)| k.starts_with(prefix))
+		TimedIter::new(optional.into_iter().flat_map(identity).filter(move |(k, _)| k.starts_with(prefix)), timer)
 	}
 
-	/// Close the database
-	fn close(&self) {
+	/// Flush all buffered data and close the database.
+	///
+	/// Unlike dropping the database, errors are reported. The write-ahead log is synced to disk,
+	/// so that the next `open` doesn't have to recover from a partially written log, and the memtable
+	/// of the default column family is flushed to SST files. The bindings can't flush other column
+	/// families, their data is recovered from the synced log on the next `open`.
+	///
+	/// The handle is released before returning. RocksDB waits for running background jobs at that
+	/// point, so the lock on the database directory is free once this returns.
+	pub fn close(self) -> io::Result<()> {
+		self.flush()?;
+		if let Some(cfs) = self.db.write().take() {
+			let mut sync_opts = WriteOptions::default();
+			sync_opts.set_sync(true);
+			// an empty synced write fsyncs everything in the log before it
+			check_for_corruption(&self.path, cfs.db.write_opt(WriteBatch::default(), &sync_opts))?;
+			check_for_corruption(&self.path, cfs.db.flush())?;
+		}
+		Ok(())
+	}
+
+	/// Drop the database handle and the buffered data.
+	fn close_handle(&self) {
 		*self.db.write() = None;
 		self.overlay.write().clear();
 		self.flushing.write().clear();
@@ -552,7 +899,7 @@ impl Database {
 
 	/// Restore the database from a copy at given path.
 	pub fn restore(&self, new_db: &str) -> io::Result<()> {
-		self.close();
+		self.close_handle();
 
 		// swap is guaranteed to be atomic
 		match swap(new_db, &self.path) {
@@ -609,6 +956,72 @@ impl Database {
 		}
 	}
 
+	/// Delete all keys of a column starting with `prefix`, returning how many were deleted.
+	///
+	/// Buffered writes are flushed first. The keys are counted and then removed with a single
+	/// range deletion, unless the prefix has no upper bound (empty or all `0xff`). Keys written
+	/// concurrently with `write` may or may not be deleted.
+	pub fn delete_by_prefix(&self, col: u32, prefix: &[u8]) -> io::Result<u64> {
+		self.flush()?;
+		let _timer = self.slow_ops.time("delete_by_prefix", Subject::Key { col, len: prefix.len() });
+		match *self.db.read() {
+			Some(ref cfs) => {
+				let cf = cfs.cf(col as usize);
+				let end = prefix_end(prefix);
+				let mut batch = WriteBatch::default();
+				let mut count = 0;
+				let keys = cfs.db.prefix_iterator_cf(cf, prefix).map_err(other_io_err)?;
+				for (key, _) in keys.take_while(|(key, _)| key.starts_with(prefix)) {
+					if end.is_none() {
+						batch.delete_cf(cf, &key).map_err(other_io_err)?;
+					}
+					count += 1;
+				}
+				if count == 0 {
+					return Ok(0);
+				}
+				if let Some(end) = end {
+					batch.delete_range_cf(cf, prefix, &end[..]).map_err(other_io_err)?;
+				}
+
+				let overlay = self.overlay.write();
+				check_for_corruption(&self.path, cfs.db.write_opt(batch, &self.write_opts))?;
+				*self.last_flush.lock() = Some(SystemTime::now());
+				self.committed_directly(&overlay);
+				Ok(count)
+			}
+			None => Err(other_io_err("Database is closed")),
+		}
+	}
+
+	/// Remove all the data of a column by dropping and recreating its column family.
+	///
+	/// Unlike deleting the keys one by one this takes constant time and leaves no tombstones
+	/// to compact. Buffered writes to the column are discarded.
+	///
+	/// If the column family can't be recreated after dropping it the database is closed, as the
+	/// column no longer exists.
+	pub fn truncate_column(&self, col: u32) -> io::Result<()> {
+		let mut db = self.db.write();
+		let cfs = db.as_mut().ok_or_else(|| other_io_err("Database is closed"))?;
+		let name = cfs
+			.column_names
+			.get(col as usize)
+			.ok_or_else(|| other_io_err(format!("No such column family: {}", col)))?
+			.clone();
+		let col_config = self.config.column_config(&self.block_opts, col);
+		cfs.db.drop_cf(&name).map_err(other_io_err)?;
+		if let Err(err) = cfs.db.create_cf(&name, &col_config) {
+			warn!("Failed to recreate column family {}, closing the database: {}", name, err);
+			drop(db);
+			self.close_handle();
+			return Err(other_io_err(err));
+		}
+		self.overlay.write()[col as usize].clear();
+		self.flushing.write()[col as usize].clear();
+		Ok(())
+	}
+
 	/// Add a new column family to the DB.
 	pub fn add_column(&self) -> io::Result<()> {
 		match *self.db.write() {
@@ -616,7 +1029,7 @@ impl Database {
 				let col = column_names.len() as u32;
 				let name = format!("col{}", col);
 				let col_config = self.config.column_config(&self.block_opts, col as u32);
-				let _ = db.create_cf(&name, &col_config).map_err(other_io_err)?;
+				db.create_cf(&name, &col_config).map_err(other_io_err)?;
 				column_names.push(name);
 				Ok(())
 			}
@@ -636,23 +1049,41 @@ impl KeyValueDB for Database {
 		Database::get_by_prefix(self, col, prefix)
 	}
 
-	fn write_buffered(&self, transaction: DBTransaction) {
+	fn write_buffered(&self, transaction: DBTransaction) -> io::Result<u64> {
 		Database::write_buffered(self, transaction)
 	}
 
-	fn write(&self, transaction: DBTransaction) -> io::Result<()> {
+	fn write(&self, transaction: DBTransaction) -> io::Result<u64> {
 		Database::write(self, transaction)
 	}
 
+	fn last_committed(&self) -> u64 {
+		Database::last_committed(self)
+	}
+
 	fn flush(&self) -> io::Result<()> {
 		Database::flush(self)
 	}
 
+	fn delete_by_prefix(&self, col: u32, prefix: &[u8]) -> io::Result<u64> {
+		Database::delete_by_prefix(self, col, prefix)
+	}
+
+	fn update(&self, col: u32, key: &[u8], f: &UpdateFn<'_>) -> io::Result<()> {
+		Database::update(self, col, key, f)
+	}
+
 	fn iter<'a>(&'a self, col: u32) -> Box<dyn Iterator<Item = KeyValuePair> + 'a> {
 		let unboxed = Database::iter(self, col);
 		Box::new(unboxed.into_iter())
 	}
 
+	// the buffered values are copied and RocksDB iterators read from an implicit snapshot,
+	// so iterators are always snapshots
+	fn iter_with_options<'a>(&'a self, col: u32, _options: IterOptions) -> Box<dyn Iterator<Item = KeyValuePair> + 'a> {
+		KeyValueDB::iter(self, col)
+	}
+
 	fn iter_from_prefix<'a>(&'a self, col: u32, prefix: &'a [u8]) -> Box<dyn Iterator<Item = KeyValuePair> + 'a> {
 		let unboxed = Database::iter_from_prefix(self, col, prefix);
 		Box::new(unboxed.into_iter())
@@ -661,6 +1092,26 @@ impl KeyValueDB for Database {
 	fn restore(&self, new_db: &str) -> io::Result<()> {
 		Database::restore(self, new_db)
 	}
+
+	fn lock_column(&self, col: u32) -> ColumnGuard<'_> {
+		self.column_locks.lock(col)
+	}
+
+	fn lock_column_shared(&self, col: u32) -> ColumnGuard<'_> {
+		self.column_locks.lock_shared(col)
+	}
+
+	fn health(&self) -> io::Result<DbHealth> {
+		Database::health(self)
+	}
+
+	fn metadata(&self) -> io::Result<Option<DBValue>> {
+		Database::metadata(self)
+	}
+
+	fn set_metadata(&self, value: &[u8]) -> io::Result<()> {
+		Database::set_metadata(self, value)
+	}
 }
 
 impl Drop for Database {
@@ -733,7 +1184,7 @@ mod tests {
 		let mut transaction = db.transaction();
 		transaction.put(0, key1.as_bytes(), b"horse");
 		transaction.delete(0, key3.as_bytes());
-		db.write_buffered(transaction);
+		db.write_buffered(transaction).unwrap();
 		assert!(db.get(0, key3.as_bytes()).unwrap().is_none());
 		assert_eq!(&*db.get(0, key1.as_bytes()).unwrap().unwrap(), b"horse");
 
@@ -829,6 +1280,115 @@ mod tests {
 		}
 	}
 
+	#[test]
+	fn health() {
+		let tempdir = TempDir::new("health").unwrap();
+		let db = Database::open(&DatabaseConfig::default(), tempdir.path().to_str().unwrap()).unwrap();
+		let health = db.health().unwrap();
+		assert!(health.is_healthy());
+		assert_eq!(health.last_flush, None);
+
+		let mut batch = db.transaction();
+		batch.put(0, b"key", b"value");
+		db.write_buffered(batch).unwrap();
+		let health = db.health().unwrap();
+		assert_eq!((health.buffered_bytes, health.pending_transactions), (8, 1));
+
+		// a direct write doesn't flush the buffered one before it
+		let mut batch = db.transaction();
+		batch.put(0, b"other", b"value");
+		db.write(batch).unwrap();
+		assert_eq!(db.health().unwrap().pending_transactions, 2);
+
+		db.flush().unwrap();
+		let health = db.health().unwrap();
+		assert_eq!((health.buffered_bytes, health.pending_transactions), (0, 0));
+		assert!(health.last_flush.is_some());
+
+		let mut batch = db.transaction();
+		batch.put(0, b"key", b"other value");
+		db.write(batch).unwrap();
+		assert_eq!(db.health().unwrap().pending_transactions, 0);
+		assert_eq!(db.last_flushed(), db.last_committed());
+
+		db.close_handle();
+		assert!(!db.health().unwrap().is_healthy());
+	}
+
+	#[test]
+	fn slow_operations() {
+		let tempdir = TempDir::new("slow_operations").unwrap();
+		let slow_ops = SlowOpsConfig { threshold: std::time::Duration::from_secs(0), log_one_in: 2 };
+		let config = DatabaseConfig { slow_ops: Some(slow_ops), ..DatabaseConfig::default() };
+		let db = Database::open(&config, tempdir.path().to_str().unwrap()).unwrap();
+
+		let mut batch = db.transaction();
+		batch.put(0, b"key", b"value");
+		db.write(batch).unwrap();
+		db.get(0, b"key").unwrap();
+		assert_eq!(db.iter(0).count(), 1);
+		assert_eq!(db.slow_operations(), 3);
+
+		// iterations are timed until the iterator is dropped
+		let mut iter = db.iter(0);
+		assert!(iter.next().is_some());
+		assert_eq!(db.slow_operations(), 3);
+		drop(iter);
+		assert_eq!(db.slow_operations(), 4);
+
+		let tempdir = TempDir::new("slow_operations_disabled").unwrap();
+		let db = Database::open(&DatabaseConfig::default(), tempdir.path().to_str().unwrap()).unwrap();
+		db.get(0, b"key").unwrap();
+		assert_eq!(db.slow_operations(), 0);
+	}
+
+	#[test]
+	fn delete_by_prefix() {
+		let tempdir = TempDir::new("delete_by_prefix").unwrap();
+		let db = Database::open(&DatabaseConfig::default(), tempdir.path().to_str().unwrap()).unwrap();
+		let keys: [&[u8]; 6] = [&[1, 0], &[1, 1], &[1, 0xff], &[2], &[0xff], &[0xff, 1]];
+		let mut batch = db.transaction();
+		for key in &keys[..5] {
+			batch.put(0, key, b"value");
+		}
+		db.write(batch).unwrap();
+		let mut batch = db.transaction();
+		batch.put(0, keys[5], b"buffered");
+		db.write_buffered(batch).unwrap();
+
+		assert_eq!(db.delete_by_prefix(0, &[1]).unwrap(), 3);
+		assert_eq!(db.delete_by_prefix(0, &[1]).unwrap(), 0);
+		assert!(db.get(0, &[1, 0xff]).unwrap().is_none());
+		assert_eq!(db.delete_by_prefix(0, &[0xff]).unwrap(), 2);
+		assert_eq!(db.iter(0).map(|(key, _)| key.into_vec()).collect::<Vec<_>>(), vec![vec![2]]);
+		assert_eq!(db.delete_by_prefix(0, &[]).unwrap(), 1);
+		assert_eq!(db.iter(0).count(), 0);
+	}
+
+	#[test]
+	fn truncate_column() {
+		let tempdir = TempDir::new("truncate_column").unwrap();
+		let db = Database::open(&DatabaseConfig::with_columns(2), tempdir.path().to_str().unwrap()).unwrap();
+		let mut batch = db.transaction();
+		batch.put(0, b"key1", b"value1");
+		batch.put(1, b"key2", b"value2");
+		db.write(batch).unwrap();
+		let mut batch = db.transaction();
+		batch.put(0, b"key3", b"value3");
+		db.write_buffered(batch).unwrap();
+
+		db.truncate_column(0).unwrap();
+		assert_eq!(db.iter(0).count(), 0);
+		assert!(db.get(0, b"key3").unwrap().is_none());
+		assert_eq!(&*db.get(1, b"key2").unwrap().unwrap(), b"value2");
+
+		let mut batch = db.transaction();
+		batch.put(0, b"key4", b"value4");
+		db.write(batch).unwrap();
+		assert_eq!(db.iter(0).count(), 1);
+		assert!(db.truncate_column(2).is_err());
+	}
+
 	#[test]
 	fn test_iter_by_prefix() {
 		let tempdir = TempDir::new("").unwrap();
@@ -878,6 +1438,33 @@ mod tests {
 		assert_eq!(&*contents[0].0, key1);
 	}
 
+	#[test]
+	fn size_limits() {
+		let tempdir = TempDir::new("").unwrap();
+		let mut config = DatabaseConfig::with_columns(1);
+		config.size_limits = SizeLimits { max_key_len: Some(4), max_value_len: None };
+		let db = Database::open(&config, tempdir.path().to_str().unwrap()).unwrap();
+
+		let mut batch = db.transaction();
+		batch.put(0, b"long key", b"bar");
+		let err = db.write(batch).unwrap_err();
+		let err = err.into_inner().unwrap().downcast::<SizeLimitError>().unwrap();
+		assert_eq!(*err, SizeLimitError::KeyTooLong { col: 0, len: 8, limit: 4 });
+
+		let mut batch = db.transaction();
+		batch.put(0, b"foo", b"bar");
+		assert_eq!(db.write_buffered(batch).unwrap(), 1);
+		let mut batch = db.transaction();
+		batch.put(0, b"long key", b"bar");
+		let err = db.write_buffered(batch).unwrap_err();
+		assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+		assert!(db.get(0, b"long key").unwrap().is_none());
+		assert_eq!(db.last_committed(), 1);
+		assert!(db.flush().is_ok());
+		assert_eq!(&*db.get(0, b"foo").unwrap().unwrap(), b"bar");
+		assert_eq!(db.last_flushed(), 1);
+	}
+
 	#[test]
 	fn write_clears_buffered_ops() {
 		let tempdir = TempDir::new("").unwrap();
@@ -886,7 +1473,7 @@ mod tests {
 
 		let mut batch = db.transaction();
 		batch.put(0, b"foo", b"bar");
-		db.write_buffered(batch);
+		db.write_buffered(batch).unwrap();
 
 		let mut batch = db.transaction();
 		batch.put(0, b"foo", b"baz");
@@ -895,6 +1482,239 @@ mod tests {
 		assert_eq!(db.get(0, b"foo").unwrap().unwrap().as_ref(), b"baz");
 	}
 
+	#[test]
+	fn close_persists_and_releases_lock() {
+		let tempdir = TempDir::new("").unwrap();
+		let path = tempdir.path().to_str().unwrap();
+		let config = DatabaseConfig::with_columns(1);
+
+		let db = Database::open(&config, path).unwrap();
+		let mut batch = db.transaction();
+		batch.put(0, b"foo", b"bar");
+		db.write_buffered(batch).unwrap();
+		db.close().unwrap();
+
+		// reopening while the handle is still held would fail on the lock file
+		let db = Database::open(&config, path).unwrap();
+		assert_eq!(&*db.get(0, b"foo").unwrap().unwrap(), b"bar");
+	}
+
+	// Set in the child process of `multi_column_write_survives_crash`.
+	const CRASH_TEST_PATH: &str = "KVDB_ROCKSDB_CRASH_TEST_PATH";
+
+	#[test]
+	fn multi_column_write_survives_crash() {
+		let config = DatabaseConfig::with_columns(2);
+
+		// in the child process: write until aborted
+		if let Ok(path) = std::env::var(CRASH_TEST_PATH) {
+			let db = Database::open(&config, &path).unwrap();
+			let _writer = std::thread::spawn(move || {
+				for i in 0u32.. {
+					let mut batch = db.transaction();
+					batch.put(1, &i.to_be_bytes(), &[1; 1024]);
+					batch.put(0, &i.to_be_bytes(), &[0; 1024]);
+					db.write(batch).unwrap();
+				}
+			});
+			std::thread::sleep(std::time::Duration::from_millis(200));
+			std::process::abort();
+		}
+
+		let tempdir = TempDir::new("").unwrap();
+		let status = std::process::Command::new(std::env::current_exe().unwrap())
+			.args(["--exact", "tests::multi_column_write_survives_crash", "--test-threads", "1"])
+			.env(CRASH_TEST_PATH, tempdir.path())
+			.stdout(std::process::Stdio::null())
+			.stderr(std::process::Stdio::null())
+			.status()
+			.unwrap();
+		assert!(!status.success());
+
+		// every transaction is either fully recovered or not at all
+		let db = Database::open(&config, tempdir.path().to_str().unwrap()).unwrap();
+		let keys = |col| db.iter(col).map(|(key, _)| key).collect::<Vec<_>>();
+		let written = keys(0);
+		assert!(!written.is_empty());
+		assert_eq!(written, keys(1));
+	}
+
+	#[test]
+	fn verify_healthy_database() {
+		let tempdir = TempDir::new("").unwrap();
+		let path = tempdir.path().to_str().unwrap();
+		let mut config = DatabaseConfig::with_columns(2);
+
+		let db = Database::open(&config, path).unwrap();
+		let mut batch = db.transaction();
+		batch.put(0, b"foo", b"bar");
+		batch.put(1, b"baz", b"quux");
+		batch.put(1, b"bazz", b"quux");
+		db.write(batch).unwrap();
+
+		let report = db.verify(None).unwrap();
+		assert!(report.is_ok());
+		assert_eq!(report.keys, 3);
+		assert_eq!(report.bytes, 21);
+		assert_eq!(db.verify(Some(1)).unwrap().keys, 2);
+		assert!(db.verify(Some(2)).is_err());
+		db.close().unwrap();
+
+		config.paranoid = true;
+		let db = Database::open(&config, path).unwrap();
+		assert_eq!(&*db.get(1, b"baz").unwrap().unwrap(), b"quux");
+	}
+
+	#[test]
+	fn snapshot_iter_ignores_later_writes() {
+		let tempdir = TempDir::new("").unwrap();
+		let db = Database::open(&DatabaseConfig::default(), tempdir.path().to_str().unwrap()).unwrap();
+		let mut batch = db.transaction();
+		batch.put(0, b"key1", b"old");
+		db.write(batch).unwrap();
+
+		let mut iter = db.iter_with_options(0, IterOptions { snapshot: true });
+		let mut batch = db.transaction();
+		batch.put(0, b"key1", b"new");
+		batch.put(0, b"key2", b"new");
+		db.write(batch).unwrap();
+
+		assert_eq!(iter.next(), Some((b"key1".to_vec().into_boxed_slice(), b"old".to_vec().into_boxed_slice())));
+		assert_eq!(iter.next(), None);
+	}
+
+	#[test]
+	fn flushes_when_buffer_is_full() {
+		let tempdir = TempDir::new("").unwrap();
+		let mut config = DatabaseConfig::with_columns(1);
+		config.max_buffered_bytes = Some(10);
+		let db = Database::open(&config, tempdir.path().to_str().unwrap()).unwrap();
+
+		let mut batch = db.transaction();
+		batch.put(0, b"foo", b"bar");
+		db.write_buffered(batch).unwrap();
+		assert_eq!(db.last_flushed(), 0);
+
+		let mut batch = db.transaction();
+		batch.put(0, b"baz", b"qux");
+		db.write_buffered(batch).unwrap();
+		assert_eq!(db.last_flushed(), 2);
+		assert!(db.overlay.read()[0].is_empty());
+	}
+
+	#[test]
+	fn sequence_numbers() {
+		let tempdir = TempDir::new("").unwrap();
+		let config = DatabaseConfig::with_columns(1);
+		let db = Database::open(&config, tempdir.path().to_str().unwrap()).unwrap();
+		assert_eq!(db.last_committed(), 0);
+
+		let mut batch = db.transaction();
+		batch.put(0, b"foo", b"bar");
+		assert_eq!(db.write_buffered(batch).unwrap(), 1);
+		let mut batch = db.transaction();
+		batch.put(0, b"baz", b"qux");
+		assert_eq!(db.write(batch).unwrap(), 2);
+		db.update(0, b"foo", &|_| None).unwrap();
+		assert_eq!(db.last_committed(), 3);
+		assert_eq!(db.last_flushed(), 0);
+
+		db.flush().unwrap();
+		assert_eq!(db.last_flushed(), 3);
+	}
+
+	#[test]
+	fn flushes_periodically() {
+		let tempdir = TempDir::new("").unwrap();
+		let config = DatabaseConfig::with_columns(1);
+		let db = std::sync::Arc::new(Database::open(&config, tempdir.path().to_str().unwrap()).unwrap());
+		let _auto_flush = auto_flush::spawn(&db, std::time::Duration::from_millis(10));
+
+		let mut batch = db.transaction();
+		batch.put(0, b"foo", b"bar");
+		db.write_buffered(batch).unwrap();
+
+		for _ in 0..500 {
+			if db.last_flushed() == 1 {
+				return;
+			}
+			std::thread::sleep(std::time::Duration::from_millis(10));
+		}
+		panic!("the write buffer hasn't been flushed");
+	}
+
+	#[test]
+	fn update() {
+		let tempdir = TempDir::new("").unwrap();
+		let config = DatabaseConfig::with_columns(1);
+		let db = Database::open(&config, tempdir.path().to_str().unwrap()).unwrap();
+
+		let mut batch = db.transaction();
+		batch.put(0, b"counter", &[1]);
+		db.write(batch).unwrap();
+
+		let increment = |v: Option<&[u8]>| Some(vec![v.map_or(0, |v| v[0]) + 1]);
+		db.update(0, b"counter", &increment).unwrap();
+		assert_eq!(&*db.get(0, b"counter").unwrap().unwrap(), &[2]);
+		db.flush().unwrap();
+		db.update(0, b"counter", &increment).unwrap();
+		assert_eq!(&*db.get(0, b"counter").unwrap().unwrap(), &[3]);
+
+		db.update(0, b"counter", &|_| None).unwrap();
+		assert!(db.get(0, b"counter").unwrap().is_none());
+	}
+
+	#[test]
+	fn open_or_create_checks_metadata() {
+		let tempdir = TempDir::new("").unwrap();
+		let path = tempdir.path().to_str().unwrap();
+		let config = DatabaseConfig::with_columns(2);
+		let metadata = Metadata::new(1, 2);
+
+		{
+			let db = Database::open_or_create(&config, path, &metadata).unwrap();
+			assert_eq!(Metadata::decode(&db.metadata().unwrap().unwrap()), Some(metadata));
+			assert_eq!(db.iter(0).count(), 0);
+		}
+
+		assert!(Database::open_or_create(&config, path, &metadata).is_ok());
+
+		let err = Database::open_or_create(&config, path, &Metadata::new(2, 2)).err().unwrap();
+		let err = err.into_inner().unwrap().downcast::<kvdb::MetadataError>().unwrap();
+		assert_eq!(*err, kvdb::MetadataError::Mismatch { expected: Metadata::new(2, 2), found: metadata });
+
+		// a different number of columns is caught before opening the column families
+		let err = Database::open_or_create(&DatabaseConfig::with_columns(3), path, &Metadata::new(1, 3)).err().unwrap();
+		let err = err.into_inner().unwrap().downcast::<kvdb::MetadataError>().unwrap();
+		assert_eq!(*err, kvdb::MetadataError::Mismatch { expected: Metadata::new(1, 3), found: metadata });
+	}
+
+	#[test]
+	fn open_or_create_rejects_legacy_database() {
+		let tempdir = TempDir::new("").unwrap();
+		let path = tempdir.path().to_str().unwrap();
+		let config = DatabaseConfig::with_columns(2);
+
+		// created without a header and written to
+		{
+			let db = Database::open(&config, path).unwrap();
+			let mut batch = db.transaction();
+			batch.put(1, b"key", b"value");
+			db.write(batch).unwrap();
+		}
+
+		let err = Database::open_or_create(&config, path, &Metadata::new(1, 2)).err().unwrap();
+		let err = err.into_inner().unwrap().downcast::<kvdb::MetadataError>().unwrap();
+		assert_eq!(*err, kvdb::MetadataError::Missing);
+
+		// created without a header but empty
+		let tempdir = TempDir::new("").unwrap();
+		let path = tempdir.path().to_str().unwrap();
+		drop(Database::open(&config, path).unwrap());
+		let db = Database::open_or_create(&config, path, &Metadata::new(1, 2)).unwrap();
+		assert_eq!(Metadata::decode(&db.metadata().unwrap().unwrap()), Some(Metadata::new(1, 2)));
+	}
+
 	#[test]
 	fn default_memory_budget() {
 		let c = DatabaseConfig::default();
@@ -919,6 +1739,25 @@ mod tests {
 		assert_eq!(c.memory_budget(), 45 * MB, "total budget is the sum of the column budget");
 	}
 
+	#[test]
+	fn block_options() {
+		let mut cfg = DatabaseConfig::with_columns(1);
+		cfg.compaction.block_size = 16 * KB;
+		cfg.block_options = BlockOptions { partitioned_index: true, cache_index_and_filter_blocks: false };
+
+		let db_path = TempDir::new("block_options").expect("the OS can create tmp dirs");
+		let _db = Database::open(&cfg, db_path.path().to_str().unwrap()).expect("can open a db");
+		let mut settings = String::new();
+		std::fs::File::open(db_path.path().join("LOG"))
+			.expect("rocksdb creates a LOG file")
+			.read_to_string(&mut settings)
+			.unwrap();
+
+		assert!(settings.contains(" block_size: 16384"));
+		assert!(settings.contains("index_type: 2"));
+		assert!(!settings.contains("cache_index_and_filter_blocks: 1"));
+	}
+
 	#[test]
 	fn rocksdb_settings() {
 		const NUM_COLS: usize = 2;
@@ -0,0 +1,144 @@
+// Copyright 2015-2019 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Logging of slow reads, writes and iterations.
+//!
+//! Operations taking longer than `SlowOpsConfig::threshold` are logged as warnings with the
+//! `kvdb_rocksdb::slow` target. Iterations are timed until the iterator is dropped. Timed operations
+//! also open a `trace_time` scope, so that they show up in the trees of the enclosing `trace_time!`
+//! scopes.
+
+use std::{
+	sync::atomic::{AtomicU64, Ordering},
+	time::{Duration, Instant},
+};
+
+use log::warn;
+use trace_time::PerfTimer;
+
+/// Configuration of the slow operation log, see `DatabaseConfig::slow_ops`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SlowOpsConfig {
+	/// Operations taking at least this long are slow.
+	pub threshold: Duration,
+	/// Log only one in this many slow operations, all of them are counted.
+	pub log_one_in: u64,
+}
+
+impl Default for SlowOpsConfig {
+	fn default() -> SlowOpsConfig {
+		SlowOpsConfig { threshold: Duration::from_millis(500), log_one_in: 1 }
+	}
+}
+
+/// What a timed operation was about.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Subject {
+	/// A key, or a prefix, of a column.
+	Key { col: u32, len: usize },
+	/// A batch of operations.
+	Batch { ops: usize },
+}
+
+/// Counts slow operations and logs a sample of them.
+#[derive(Default)]
+pub(crate) struct SlowOps {
+	config: Option<SlowOpsConfig>,
+	count: AtomicU64,
+}
+
+impl SlowOps {
+	pub(crate) fn new(config: Option<SlowOpsConfig>) -> Self {
+		SlowOps { config, count: AtomicU64::new(0) }
+	}
+
+	/// Number of slow operations so far.
+	pub(crate) fn count(&self) -> u64 {
+		self.count.load(Ordering::Relaxed)
+	}
+
+	/// Time an operation until the returned timer is dropped. Nothing is measured if disabled.
+	pub(crate) fn time(&self, op: &'static str, subject: Subject) -> Option<OpTimer<'_>> {
+		let config = self.config?;
+		Some(OpTimer { slow_ops: self, config, op, subject, start: Instant::now(), _scope: PerfTimer::new(op) })
+	}
+}
+
+pub(crate) struct OpTimer<'a> {
+	slow_ops: &'a SlowOps,
+	config: SlowOpsConfig,
+	op: &'static str,
+	subject: Subject,
+	start: Instant,
+	_scope: PerfTimer,
+}
+
+/// Iterator timed until it is dropped, so that consuming it counts as part of the operation.
+pub(crate) struct TimedIter<'a, I> {
+	// dropped before the timer
+	inner: I,
+	_timer: Option<OpTimer<'a>>,
+}
+
+impl<'a, I> TimedIter<'a, I> {
+	pub(crate) fn new(inner: I, timer: Option<OpTimer<'a>>) -> Self {
+		TimedIter { inner, _timer: timer }
+	}
+}
+
+impl<'a, I: Iterator> Iterator for TimedIter<'a, I> {
+	type Item = I::Item;
+
+	fn next(&mut self) -> Option<I::Item> {
+		self.inner.next()
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		self.inner.size_hint()
+	}
+}
+
+impl<'a> Drop for OpTimer<'a> {
+	fn drop(&mut self) {
+		let elapsed = self.start.elapsed();
+		if elapsed < self.config.threshold {
+			return;
+		}
+		let n = self.slow_ops.count.fetch_add(1, Ordering::Relaxed);
+		if n % self.config.log_one_in.max(1) != 0 {
+			return;
+		}
+		match self.subject {
+			Subject::Key { col, len } => warn!(
+				target: "kvdb_rocksdb::slow",
+				"Slow {} took {}ms: column {}, key length {} ({} slow operations so far)",
+				self.op,
+				elapsed.as_millis(),
+				col,
+				len,
+				n + 1,
+			),
+			Subject::Batch { ops } => warn!(
+				target: "kvdb_rocksdb::slow",
+				"Slow {} took {}ms: {} operations ({} slow operations so far)",
+				self.op,
+				elapsed.as_millis(),
+				ops,
+				n + 1,
+			),
+		}
+	}
+}
@@ -0,0 +1,108 @@
+// Copyright 2015-2019 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Integrity scan of the flushed data.
+
+use std::{fmt, io};
+
+use kvdb::display::hex;
+use log::warn;
+use rocksdb::ReadOptions;
+
+use crate::{other_io_err, Database};
+
+/// Result of `Database::verify`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+	/// Number of keys read with their checksums verified.
+	pub keys: u64,
+	/// Total size of the keys and values read.
+	pub bytes: u64,
+	/// Corrupted parts of the scanned columns.
+	pub corrupted: Vec<CorruptRange>,
+}
+
+impl VerifyReport {
+	/// Whether no corruption was found.
+	pub fn is_ok(&self) -> bool {
+		self.corrupted.is_empty()
+	}
+}
+
+/// A part of a column which can't be read.
+///
+/// The scan of a column stops at the first error, so the range covers everything after the
+/// last readable key, even though some of it might be intact.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorruptRange {
+	/// The column.
+	pub col: u32,
+	/// The last key read before the error, `None` if the column couldn't be read from the start.
+	pub after: Option<Box<[u8]>>,
+	/// The error returned by RocksDB.
+	pub error: String,
+}
+
+impl fmt::Display for CorruptRange {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "column {} ", self.col)?;
+		match self.after {
+			Some(ref key) => write!(f, "after key {}", hex(key))?,
+			None => write!(f, "from the start")?,
+		}
+		write!(f, ": {}", self.error)
+	}
+}
+
+impl Database {
+	/// Read all flushed data of the column `col_filter`, or of all columns if `None`, verifying
+	/// the checksums of every block.
+	///
+	/// Corruption is reported in the returned `VerifyReport`, errors are only returned if the
+	/// database is closed or the column doesn't exist. The blocks read go through the block
+	/// cache, the `rocksdb` bindings can't turn off filling it for a read.
+	pub fn verify(&self, col_filter: Option<u32>) -> io::Result<VerifyReport> {
+		let db = self.db.read();
+		let cfs = db.as_ref().ok_or_else(|| other_io_err("Database is closed"))?;
+		let num_columns = cfs.column_names.len() as u32;
+		let columns = match col_filter {
+			Some(col) if col >= num_columns => return Err(other_io_err(format!("No such column family: {}", col))),
+			Some(col) => col..col + 1,
+			None => 0..num_columns,
+		};
+		let mut read_opts = ReadOptions::default();
+		read_opts.set_verify_checksums(true);
+
+		let mut report = VerifyReport::default();
+		for col in columns {
+			let mut iter = cfs.db.raw_iterator_cf_opt(cfs.cf(col as usize), &read_opts).map_err(other_io_err)?;
+			let mut last = None;
+			iter.seek_to_first();
+			while let (Some(key), Some(value)) = (iter.key(), iter.value()) {
+				report.keys += 1;
+				report.bytes += (key.len() + value.len()) as u64;
+				last = Some(key.to_vec().into_boxed_slice());
+				iter.next();
+			}
+			if let Err(e) = iter.status() {
+				let range = CorruptRange { col, after: last, error: e.into_string() };
+				warn!("DB verification failed in {}", range);
+				report.corrupted.push(range);
+			}
+		}
+		Ok(report)
+	}
+}
@@ -24,7 +24,7 @@
 mod error;
 mod indexed_db;
 
-use kvdb::{DBTransaction, DBValue};
+use kvdb::{ColumnGuard, DBTransaction, DBValue, IterOptions};
 use kvdb_memorydb::{self as in_memory, InMemory};
 use send_wrapper::SendWrapper;
 use std::io;
@@ -76,7 +76,7 @@ impl Database {
 				txn.put_vec(column, key.as_ref(), value);
 			}
 			// write each column into memory
-			in_memory.write_buffered(txn);
+			in_memory.write_buffered(txn).expect("in-memory database has no size limits; qed");
 		}
 		Ok(Database { name: name_clone, version, columns, in_memory, indexed_db: inner })
 	}
@@ -107,9 +107,13 @@ impl KeyValueDB for Database {
 		self.in_memory.get_by_prefix(col, prefix)
 	}
 
-	fn write_buffered(&self, transaction: DBTransaction) {
+	fn write_buffered(&self, transaction: DBTransaction) -> io::Result<u64> {
 		let _ = indexed_db::idb_commit_transaction(&*self.indexed_db, &transaction, self.columns);
-		self.in_memory.write_buffered(transaction);
+		self.in_memory.write_buffered(transaction)
+	}
+
+	fn last_committed(&self) -> u64 {
+		self.in_memory.last_committed()
 	}
 
 	fn flush(&self) -> io::Result<()> {
@@ -121,6 +125,15 @@ impl KeyValueDB for Database {
 		self.in_memory.iter(col)
 	}
 
+	// NOTE: clones the whole db
+	fn iter_with_options<'a>(
+		&'a self,
+		col: u32,
+		options: IterOptions,
+	) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
+		self.in_memory.iter_with_options(col, options)
+	}
+
 	// NOTE: clones the whole db
 	fn iter_from_prefix<'a>(
 		&'a self,
@@ -134,4 +147,12 @@ impl KeyValueDB for Database {
 	fn restore(&self, _new_db: &str) -> std::io::Result<()> {
 		Err(io::Error::new(io::ErrorKind::Other, "Not supported yet"))
 	}
+
+	fn lock_column(&self, col: u32) -> ColumnGuard<'_> {
+		self.in_memory.lock_column(col)
+	}
+
+	fn lock_column_shared(&self, col: u32) -> ColumnGuard<'_> {
+		self.in_memory.lock_column_shared(col)
+	}
 }
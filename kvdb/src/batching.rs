@@ -0,0 +1,518 @@
+// Copyright 2015-2019 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Coalescing of many small transactions into fewer, larger commits.
+
+use std::{
+	collections::HashMap,
+	io, mem,
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc, Mutex, RwLock, Weak,
+	},
+	thread,
+	time::{Duration, Instant},
+};
+
+use crate::{ColumnGuard, DBOp, DBTransaction, DBValue, DbHealth, IterOptions, KeyValueDB, UpdateFn};
+
+/// Configuration of a `BatchingDb`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BatchingConfig {
+	/// Queued transactions are committed at least this often.
+	pub interval: Duration,
+	/// Queued transactions are committed as soon as their keys and values amount to this many bytes.
+	pub max_bytes: usize,
+}
+
+impl Default for BatchingConfig {
+	fn default() -> BatchingConfig {
+		BatchingConfig { interval: Duration::from_millis(100), max_bytes: 4 * 1024 * 1024 }
+	}
+}
+
+// Changes by column and key, `None` for deletions.
+type Changes = HashMap<u32, HashMap<Vec<u8>, Option<DBValue>>>;
+
+struct Queued {
+	seq: u64,
+	transaction: DBTransaction,
+	// Whether a `write` waits for the outcome of the transaction.
+	waiting: bool,
+}
+
+#[derive(Default)]
+struct Queue {
+	changes: Changes,
+	// The queued transactions, committed one by one if committing their changes at once fails.
+	transactions: Vec<Queued>,
+	bytes: usize,
+	// Sequence number of the last queued transaction.
+	seq: u64,
+}
+
+impl Queue {
+	/// Queue a transaction and return its sequence number.
+	fn push(&mut self, transaction: DBTransaction, waiting: bool) -> u64 {
+		for op in &transaction.ops {
+			self.bytes += op.key().len();
+			let change = match op {
+				DBOp::Insert { value, .. } => {
+					self.bytes += value.len();
+					Some(value.clone())
+				}
+				DBOp::Delete { .. } => None,
+			};
+			self.changes.entry(op.col()).or_default().insert(op.key().to_vec(), change);
+		}
+		self.seq += 1;
+		self.transactions.push(Queued { seq: self.seq, transaction, waiting });
+		self.seq
+	}
+}
+
+struct Inner<D> {
+	db: D,
+	config: BatchingConfig,
+	queue: RwLock<Queue>,
+	// Changes being committed, still visible to readers until they are in `db`.
+	committing: RwLock<Changes>,
+	// Serializes commits, holds the sequence number of the last committed transaction.
+	committed_seq: Mutex<u64>,
+	// First failure of a transaction queued by `write_buffered` or `update`, reported by the next `flush_now`.
+	failed: Mutex<Option<io::Error>>,
+	// Failures of transactions queued by `write`, by sequence number, taken by their writers.
+	rejected: Mutex<HashMap<u64, io::Error>>,
+}
+
+/// A `KeyValueDB` wrapper queueing transactions and committing them to the inner database in
+/// batches, either every `BatchingConfig::interval` or once `BatchingConfig::max_bytes` are queued.
+///
+/// Queued changes are seen by `get`, `get_by_prefix` and `update`, but not by iterators, which
+/// only see committed data. `write` returns once the transaction is committed, together with those
+/// of all other writers queued so far, so that concurrent writers share a single commit of the
+/// inner database.
+///
+/// A writer queueing a transaction over `max_bytes` commits the queue itself, waiting for any
+/// commit in progress: writers are slowed down to the pace of the inner database.
+///
+/// If committing the queued transactions at once fails, they are committed one by one and those
+/// failing are dropped, so that a transaction the inner database rejects doesn't hold back the
+/// others. An error returned by `write` is the failure of its own transaction, which has not been
+/// applied and won't be retried. Failures of transactions queued by `write_buffered` or `update`
+/// are reported by the next `flush_now`, the first one if there are several.
+pub struct BatchingDb<D: KeyValueDB + 'static> {
+	inner: Arc<Inner<D>>,
+	stop: Arc<AtomicBool>,
+	thread: Option<thread::JoinHandle<()>>,
+}
+
+impl<D: KeyValueDB + 'static> BatchingDb<D> {
+	/// Wrap `db`, starting a thread committing queued transactions every `config.interval`.
+	pub fn new(db: D, config: BatchingConfig) -> Self {
+		let inner = Arc::new(Inner {
+			db,
+			config,
+			queue: RwLock::new(Queue::default()),
+			committing: RwLock::new(HashMap::new()),
+			committed_seq: Mutex::new(0),
+			failed: Mutex::new(None),
+			rejected: Mutex::new(HashMap::new()),
+		});
+		let stop = Arc::new(AtomicBool::new(false));
+		let thread = {
+			let inner = Arc::downgrade(&inner);
+			let stop = stop.clone();
+			thread::Builder::new()
+				.name("kvdb-batching".into())
+				.spawn(move || run(inner, config.interval, &stop))
+				.expect("the OS can spawn a thread")
+		};
+		BatchingDb { inner, stop, thread: Some(thread) }
+	}
+
+	/// The wrapped database.
+	pub fn inner(&self) -> &D {
+		&self.inner.db
+	}
+
+	/// Commit all queued transactions now.
+	pub fn flush_now(&self) -> io::Result<()> {
+		self.inner.commit(u64::MAX);
+		match self.inner.failed.lock().expect("poisoned").take() {
+			Some(err) => Err(err),
+			None => Ok(()),
+		}
+	}
+
+	/// Sequence number of the last transaction committed to the inner database, or dropped,
+	/// together with all transactions before it.
+	pub fn last_flushed(&self) -> u64 {
+		*self.inner.committed_seq.lock().expect("poisoned")
+	}
+
+	/// Number of bytes of keys and values queued.
+	pub fn queued_bytes(&self) -> usize {
+		self.inner.queue.read().expect("poisoned").bytes
+	}
+}
+
+impl<D: KeyValueDB> Inner<D> {
+	fn get(&self, queue: &Queue, col: u32, key: &[u8]) -> io::Result<Option<DBValue>> {
+		if let Some(change) = queue.changes.get(&col).and_then(|changes| changes.get(key)) {
+			return Ok(change.clone());
+		}
+		if let Some(change) = self.committing.read().expect("poisoned").get(&col).and_then(|changes| changes.get(key)) {
+			return Ok(change.clone());
+		}
+		self.db.get(col, key)
+	}
+
+	/// Commit the queue, unless the transaction `seq` has already been committed.
+	fn commit(&self, seq: u64) {
+		let mut committed_seq = self.committed_seq.lock().expect("poisoned");
+		if *committed_seq >= seq {
+			return;
+		}
+
+		let (pending, transactions) = {
+			let mut queue = self.queue.write().expect("poisoned");
+			let mut committing = self.committing.write().expect("poisoned");
+			mem::swap(&mut queue.changes, &mut *committing);
+			queue.bytes = 0;
+			(queue.seq, mem::take(&mut queue.transactions))
+		};
+
+		let mut batch = DBTransaction::new();
+		for (col, changes) in self.committing.read().expect("poisoned").iter() {
+			for (key, change) in changes {
+				match change {
+					Some(value) => batch.put(*col, key, value),
+					None => batch.delete(*col, key),
+				}
+			}
+		}
+		if !batch.ops.is_empty() && self.db.write(batch).is_err() {
+			// find the failing transactions, the others are committed. Readers wait until the changes
+			// of the failing ones are gone.
+			let mut committing = self.committing.write().expect("poisoned");
+			for Queued { seq, transaction, waiting } in transactions {
+				if let Err(err) = self.db.write(transaction) {
+					if waiting {
+						self.rejected.lock().expect("poisoned").insert(seq, err);
+					} else {
+						self.failed.lock().expect("poisoned").get_or_insert(err);
+					}
+				}
+			}
+			committing.clear();
+		}
+
+		*committed_seq = pending;
+		self.committing.write().expect("poisoned").clear();
+	}
+}
+
+fn run<D: KeyValueDB>(inner: Weak<Inner<D>>, interval: Duration, stop: &AtomicBool) {
+	let mut next = Instant::now() + interval;
+	while !stop.load(Ordering::SeqCst) {
+		let now = Instant::now();
+		if now < next {
+			thread::park_timeout(next - now);
+			continue;
+		}
+		next = now + interval;
+		match inner.upgrade() {
+			Some(inner) => inner.commit(u64::MAX),
+			None => return,
+		}
+	}
+}
+
+impl<D: KeyValueDB + 'static> Drop for BatchingDb<D> {
+	fn drop(&mut self) {
+		self.stop.store(true, Ordering::SeqCst);
+		if let Some(thread) = self.thread.take() {
+			thread.thread().unpark();
+			let _ = thread.join();
+		}
+		self.inner.commit(u64::MAX);
+	}
+}
+
+impl<D: KeyValueDB + 'static> KeyValueDB for BatchingDb<D> {
+	fn get(&self, col: u32, key: &[u8]) -> io::Result<Option<DBValue>> {
+		let queue = self.inner.queue.read().expect("poisoned");
+		self.inner.get(&queue, col, key)
+	}
+
+	fn get_by_prefix(&self, col: u32, prefix: &[u8]) -> Option<Box<[u8]>> {
+		let queue = self.inner.queue.read().expect("poisoned");
+		let committing = self.inner.committing.read().expect("poisoned");
+		let pending = [&queue.changes, &*committing];
+		let change = |key: &[u8]| pending.iter().find_map(|changes| changes.get(&col)?.get(key));
+		// the smallest queued key, otherwise the first committed key not changed since
+		let queued = pending
+			.iter()
+			.filter_map(|changes| changes.get(&col))
+			.flat_map(|changes| changes.keys())
+			.filter(|key| key.starts_with(prefix) && matches!(change(key), Some(Some(_))))
+			.min();
+		match queued {
+			Some(key) => change(key).cloned().flatten().map(|value| value.to_vec().into_boxed_slice()),
+			None => self
+				.inner
+				.db
+				.iter_from_prefix(col, prefix)
+				.find(|(key, _)| change(key).is_none())
+				.map(|(_, value)| value),
+		}
+	}
+
+	fn write_buffered(&self, transaction: DBTransaction) -> io::Result<u64> {
+		let (seq, full) = {
+			let mut queue = self.inner.queue.write().expect("poisoned");
+			let seq = queue.push(transaction, false);
+			(seq, queue.bytes >= self.inner.config.max_bytes)
+		};
+		if full {
+			self.inner.commit(u64::MAX);
+		}
+		Ok(seq)
+	}
+
+	fn write(&self, transaction: DBTransaction) -> io::Result<u64> {
+		let seq = self.inner.queue.write().expect("poisoned").push(transaction, true);
+		self.inner.commit(seq);
+		match self.inner.rejected.lock().expect("poisoned").remove(&seq) {
+			Some(err) => Err(err),
+			None => Ok(seq),
+		}
+	}
+
+	fn last_committed(&self) -> u64 {
+		self.inner.queue.read().expect("poisoned").seq
+	}
+
+	fn flush(&self) -> io::Result<()> {
+		self.flush_now()?;
+		self.inner.db.flush()
+	}
+
+	fn update(&self, col: u32, key: &[u8], f: &UpdateFn<'_>) -> io::Result<()> {
+		let mut queue = self.inner.queue.write().expect("poisoned");
+		let value = self.inner.get(&queue, col, key)?;
+		let mut transaction = DBTransaction::with_capacity(1);
+		match f(value.as_ref().map(|value| &value[..])) {
+			Some(value) => transaction.put_vec(col, key, value),
+			None => transaction.delete(col, key),
+		}
+		queue.push(transaction, false);
+		Ok(())
+	}
+
+	fn iter<'a>(&'a self, col: u32) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
+		self.inner.db.iter(col)
+	}
+
+	fn iter_with_options<'a>(
+		&'a self,
+		col: u32,
+		options: IterOptions,
+	) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
+		self.inner.db.iter_with_options(col, options)
+	}
+
+	fn iter_from_prefix<'a>(
+		&'a self,
+		col: u32,
+		prefix: &'a [u8],
+	) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
+		self.inner.db.iter_from_prefix(col, prefix)
+	}
+
+	fn restore(&self, new_db: &str) -> io::Result<()> {
+		self.flush_now()?;
+		self.inner.db.restore(new_db)
+	}
+
+	fn lock_column(&self, col: u32) -> ColumnGuard<'_> {
+		self.inner.db.lock_column(col)
+	}
+
+	fn lock_column_shared(&self, col: u32) -> ColumnGuard<'_> {
+		self.inner.db.lock_column_shared(col)
+	}
+
+	fn health(&self) -> io::Result<DbHealth> {
+		let mut health = self.inner.db.health()?;
+		health.buffered_bytes += self.queued_bytes() as u64;
+		health.pending_transactions += self.last_committed().saturating_sub(self.last_flushed());
+		Ok(health)
+	}
+
+	fn metadata(&self) -> io::Result<Option<DBValue>> {
+		self.inner.db.metadata()
+	}
+
+	fn set_metadata(&self, value: &[u8]) -> io::Result<()> {
+		self.inner.db.set_metadata(value)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{BatchingConfig, BatchingDb};
+	use crate::test_db::{create, create_with_size_limits};
+	use crate::{KeyValueDB, SizeLimits};
+	use std::{io, sync::Arc, thread, time::Duration};
+
+	#[test]
+	fn batching_db_coalesces_writes() {
+		let config = BatchingConfig { interval: Duration::from_secs(3600), max_bytes: 16 };
+		let db = BatchingDb::new(create(2), config);
+
+		// queued writes are read back, but not committed yet
+		let mut transaction = db.transaction();
+		transaction.put(0, b"key1", b"cat");
+		transaction.put(1, b"key1", b"dog");
+		assert_eq!(db.write_buffered(transaction).unwrap(), 1);
+		db.update(0, b"key2", &|_| Some(b"lion".to_vec())).unwrap();
+		assert_eq!(&*db.get(0, b"key1").unwrap().unwrap(), b"cat");
+		assert_eq!(&*db.get(0, b"key2").unwrap().unwrap(), b"lion");
+		assert!(db.inner().get(0, b"key1").unwrap().is_none());
+		assert_eq!(db.inner().last_committed(), 0);
+		assert_eq!(db.last_committed(), 2);
+		assert_eq!(db.last_flushed(), 0);
+		assert_eq!(db.health().unwrap().pending_transactions, 2);
+
+		// both transactions are committed at once
+		db.flush_now().unwrap();
+		assert_eq!(db.inner().last_committed(), 1);
+		assert_eq!(db.last_flushed(), 2);
+		assert_eq!(&*db.inner().get(1, b"key1").unwrap().unwrap(), b"dog");
+
+		// crossing `max_bytes` commits right away
+		let mut transaction = db.transaction();
+		transaction.delete(0, b"key1");
+		transaction.put(0, b"key3", b"0123456789");
+		db.write_buffered(transaction).unwrap();
+		assert_eq!(db.queued_bytes(), 0);
+		assert!(db.inner().get(0, b"key1").unwrap().is_none());
+		assert_eq!(db.inner().last_committed(), 2);
+	}
+
+	#[test]
+	fn batching_db_isolates_failing_transactions() {
+		let config = BatchingConfig { interval: Duration::from_secs(3600), max_bytes: 1024 };
+		let limits = SizeLimits { max_key_len: None, max_value_len: Some(4) };
+		let db = BatchingDb::new(create_with_size_limits(1, limits), config);
+
+		// a rejected buffered transaction doesn't hold back the others
+		let mut transaction = db.transaction();
+		transaction.put(0, b"key1", b"cat");
+		db.write_buffered(transaction).unwrap();
+		let mut transaction = db.transaction();
+		transaction.put(0, b"key2", b"horse");
+		db.write_buffered(transaction).unwrap();
+		assert_eq!(db.flush_now().unwrap_err().kind(), io::ErrorKind::InvalidInput);
+		assert_eq!(&*db.inner().get(0, b"key1").unwrap().unwrap(), b"cat");
+		assert!(db.inner().get(0, b"key2").unwrap().is_none());
+		assert_eq!(db.last_flushed(), 2);
+		db.flush_now().unwrap();
+
+		// `write` returns the error of its own transaction only
+		let mut transaction = db.transaction();
+		transaction.put(0, b"key3", b"dog");
+		db.write_buffered(transaction).unwrap();
+		let mut transaction = db.transaction();
+		transaction.put(0, b"key4", b"mouse");
+		assert!(db.write(transaction).is_err());
+		let mut transaction = db.transaction();
+		transaction.put(0, b"key5", b"bee");
+		assert_eq!(db.write(transaction).unwrap(), 5);
+		assert_eq!(&*db.inner().get(0, b"key3").unwrap().unwrap(), b"dog");
+		assert!(db.inner().get(0, b"key4").unwrap().is_none());
+		db.flush_now().unwrap();
+	}
+
+	#[test]
+	fn batching_db_get_by_prefix_sees_queued_changes() {
+		let config = BatchingConfig { interval: Duration::from_secs(3600), max_bytes: 1024 };
+		let db = BatchingDb::new(create(1), config);
+		let mut transaction = db.transaction();
+		transaction.put(0, b"a/1", b"committed");
+		transaction.put(0, b"b/1", b"committed");
+		db.write(transaction).unwrap();
+
+		let mut transaction = db.transaction();
+		transaction.delete(0, b"a/1");
+		transaction.put(0, b"a/2", b"queued");
+		transaction.put(0, b"c/1", b"queued");
+		db.write_buffered(transaction).unwrap();
+		assert_eq!(&*db.get_by_prefix(0, b"a/").unwrap(), b"queued");
+		assert_eq!(&*db.get_by_prefix(0, b"b/").unwrap(), b"committed");
+		assert_eq!(&*db.get_by_prefix(0, b"c/").unwrap(), b"queued");
+
+		let mut transaction = db.transaction();
+		transaction.delete(0, b"a/2");
+		db.write_buffered(transaction).unwrap();
+		assert!(db.get_by_prefix(0, b"a/").is_none());
+		db.flush_now().unwrap();
+		assert!(db.get_by_prefix(0, b"a/").is_none());
+		assert_eq!(&*db.get_by_prefix(0, b"c/").unwrap(), b"queued");
+	}
+
+	#[test]
+	fn batching_db_commits_periodically() {
+		let config = BatchingConfig { interval: Duration::from_millis(10), max_bytes: 1024 };
+		let db = BatchingDb::new(create(1), config);
+
+		let mut transaction = db.transaction();
+		transaction.put(0, b"key", b"cat");
+		db.write_buffered(transaction).unwrap();
+		for _ in 0..100 {
+			if db.last_flushed() == 1 {
+				break;
+			}
+			thread::sleep(Duration::from_millis(10));
+		}
+		assert_eq!(&*db.inner().get(0, b"key").unwrap().unwrap(), b"cat");
+	}
+
+	#[test]
+	fn batching_db_shares_commits_between_writers() {
+		let config = BatchingConfig { interval: Duration::from_secs(3600), max_bytes: 1024 * 1024 };
+		let db = Arc::new(BatchingDb::new(create(1), config));
+		let writers = (0..8u32)
+			.map(|w| {
+				let db = db.clone();
+				thread::spawn(move || {
+					for i in 0..50u32 {
+						let mut transaction = db.transaction();
+						transaction.put(0, &(w * 1000 + i).to_be_bytes(), b"value");
+						db.write(transaction).unwrap();
+					}
+				})
+			})
+			.collect::<Vec<_>>();
+		for writer in writers {
+			writer.join().unwrap();
+		}
+		assert_eq!(db.inner().iter(0).count(), 400);
+		assert!(db.inner().last_committed() <= 400);
+	}
+}
@@ -0,0 +1,216 @@
+// Copyright 2015-2019 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Hex formatting of keys, values, operations and transactions for log messages and errors.
+//!
+//! ```
+//! use kvdb::{display::hex, DBTransaction};
+//!
+//! let mut tx = DBTransaction::new();
+//! tx.put(0, b"key", &[0xab; 40]);
+//! tx.delete(1, b"key");
+//! assert_eq!(hex(b"key").to_string(), "0x6b6579");
+//! assert_eq!(
+//! 	tx.display_brief().to_string(),
+//! 	"[insert column 0 key 0x6b6579 value 0xabababababababab...(40 bytes), delete column 1 key 0x6b6579]",
+//! );
+//! ```
+
+use std::fmt;
+
+use crate::{DBOp, DBTransaction};
+
+/// Number of bytes of a key or value shown by brief formats.
+pub const BRIEF_MAX_BYTES: usize = 8;
+
+/// Number of operations of a transaction shown by `DBTransaction::display_brief`.
+pub const BRIEF_MAX_OPS: usize = 4;
+
+/// Bytes formatted as `0x` prefixed hex, cut after a maximal number of bytes.
+///
+/// Cut bytes are followed by `...` and their full length, e.g. `0xabab...(40 bytes)`.
+#[derive(Clone, Copy)]
+pub struct HexBytes<'a> {
+	bytes: &'a [u8],
+	max_len: usize,
+}
+
+/// Format all of `bytes` as hex.
+pub fn hex(bytes: &[u8]) -> HexBytes<'_> {
+	HexBytes { bytes, max_len: usize::MAX }
+}
+
+/// Format the first `BRIEF_MAX_BYTES` of `bytes` as hex.
+pub fn hex_brief(bytes: &[u8]) -> HexBytes<'_> {
+	hex(bytes).max_len(BRIEF_MAX_BYTES)
+}
+
+impl<'a> HexBytes<'a> {
+	/// Cut after `max_len` bytes.
+	pub fn max_len(self, max_len: usize) -> Self {
+		HexBytes { max_len, ..self }
+	}
+}
+
+impl<'a> fmt::Display for HexBytes<'a> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "0x")?;
+		for byte in self.bytes.iter().take(self.max_len) {
+			write!(f, "{:02x}", byte)?;
+		}
+		if self.bytes.len() > self.max_len {
+			write!(f, "...({} bytes)", self.bytes.len())?;
+		}
+		Ok(())
+	}
+}
+
+impl<'a> fmt::Debug for HexBytes<'a> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		fmt::Display::fmt(self, f)
+	}
+}
+
+/// A `DBOp` formatted as `insert column 0 key 0x.. value 0x..` or `delete column 0 key 0x..`,
+/// see `DBOp::display` and `DBOp::display_brief`.
+#[derive(Clone, Copy)]
+pub struct OpDisplay<'a> {
+	op: &'a DBOp,
+	max_len: usize,
+}
+
+impl<'a> fmt::Display for OpDisplay<'a> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self.op {
+			DBOp::Insert { col, ref key, ref value } => write!(
+				f,
+				"insert column {} key {} value {}",
+				col,
+				hex(key).max_len(self.max_len),
+				hex(value).max_len(self.max_len)
+			),
+			DBOp::Delete { col, ref key } => write!(f, "delete column {} key {}", col, hex(key).max_len(self.max_len)),
+		}
+	}
+}
+
+impl<'a> fmt::Debug for OpDisplay<'a> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		fmt::Display::fmt(self, f)
+	}
+}
+
+/// A `DBTransaction` formatted as the list of its operations, see `DBTransaction::display` and
+/// `DBTransaction::display_brief`.
+///
+/// Cut operations are replaced by `...` and the total number of operations.
+#[derive(Clone, Copy)]
+pub struct TransactionDisplay<'a> {
+	transaction: &'a DBTransaction,
+	max_ops: usize,
+	max_len: usize,
+}
+
+impl<'a> fmt::Display for TransactionDisplay<'a> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "[")?;
+		for (i, op) in self.transaction.ops.iter().take(self.max_ops).enumerate() {
+			if i > 0 {
+				write!(f, ", ")?;
+			}
+			write!(f, "{}", OpDisplay { op, max_len: self.max_len })?;
+		}
+		if self.transaction.ops.len() > self.max_ops {
+			write!(f, ", ...({} ops)", self.transaction.ops.len())?;
+		}
+		write!(f, "]")
+	}
+}
+
+impl<'a> fmt::Debug for TransactionDisplay<'a> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		fmt::Display::fmt(self, f)
+	}
+}
+
+impl DBOp {
+	/// Format the operation with its full key and value.
+	pub fn display(&self) -> OpDisplay<'_> {
+		OpDisplay { op: self, max_len: usize::MAX }
+	}
+
+	/// Format the operation with its key and value cut after `BRIEF_MAX_BYTES`.
+	pub fn display_brief(&self) -> OpDisplay<'_> {
+		OpDisplay { op: self, max_len: BRIEF_MAX_BYTES }
+	}
+}
+
+impl fmt::Debug for DBOp {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		fmt::Display::fmt(&self.display(), f)
+	}
+}
+
+impl DBTransaction {
+	/// Format all operations of the transaction with their full keys and values.
+	pub fn display(&self) -> TransactionDisplay<'_> {
+		TransactionDisplay { transaction: self, max_ops: usize::MAX, max_len: usize::MAX }
+	}
+
+	/// Format the first `BRIEF_MAX_OPS` operations of the transaction, with their keys and values
+	/// cut after `BRIEF_MAX_BYTES`, for log messages.
+	pub fn display_brief(&self) -> TransactionDisplay<'_> {
+		TransactionDisplay { transaction: self, max_ops: BRIEF_MAX_OPS, max_len: BRIEF_MAX_BYTES }
+	}
+}
+
+impl fmt::Debug for DBTransaction {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		fmt::Display::fmt(&self.display(), f)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn hex_is_cut_after_max_len() {
+		assert_eq!(hex(&[]).to_string(), "0x");
+		assert_eq!(hex(&[0, 1, 0xff]).to_string(), "0x0001ff");
+		assert_eq!(hex(&[0, 1, 0xff]).max_len(2).to_string(), "0x0001...(3 bytes)");
+		assert_eq!(format!("{:?}", hex_brief(&[7; 8])), "0x0707070707070707");
+		assert_eq!(hex_brief(&[7; 9]).to_string(), "0x0707070707070707...(9 bytes)");
+	}
+
+	#[test]
+	fn transactions_are_cut_after_max_ops() {
+		let mut tx = DBTransaction::new();
+		assert_eq!(tx.display_brief().to_string(), "[]");
+		for i in 0..6u8 {
+			tx.put(0, &[i], &[i; 10]);
+		}
+		assert_eq!(
+			tx.ops[5].display_brief().to_string(),
+			"insert column 0 key 0x05 value 0x0505050505050505...(10 bytes)"
+		);
+		assert_eq!(format!("{:?}", tx.ops[5]), "insert column 0 key 0x05 value 0x05050505050505050505");
+		let brief = tx.display_brief().to_string();
+		assert!(brief.starts_with("[insert column 0 key 0x00 value 0x0000000000000000...(10 bytes), insert"));
+		assert!(brief.ends_with("insert column 0 key 0x03 value 0x0303030303030303...(10 bytes), ...(6 ops)]"));
+		assert_eq!(format!("{:?}", tx).matches("insert").count(), 6);
+	}
+}
@@ -0,0 +1,405 @@
+// Copyright 2015-2019 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Streaming export and import of the contents of a database.
+//!
+//! The stream starts with the magic `kvdbexpt` and the format version as a big-endian `u32`,
+//! followed by records starting with a tag byte:
+//!
+//! - `TAG_COLUMN`, then the column index as a `u32`. The chunks up to the next column header
+//!   belong to this column.
+//! - `TAG_CHUNK`, then the number of entries, the payload length and the CRC-32 of the payload
+//!   as `u32`s, then the payload. The payload is a sorted run of entries, each one being the key
+//!   length as a `u32`, the key, the value length as a `u32` and the value. Keys are strictly
+//!   ascending within a column, across chunks.
+//! - `TAG_END`, terminating the stream.
+//!
+//! Every chunk is imported in a single transaction, so an interrupted import can be resumed by
+//! exporting again after the last imported key, see `ExportProgress`.
+
+use std::{
+	error, fmt,
+	io::{self, Read},
+};
+
+use crate::{IterOptions, KeyValueDB};
+
+const MAGIC: &[u8; 8] = b"kvdbexpt";
+/// Version of the export format written by `export_to`.
+pub const EXPORT_VERSION: u32 = 1;
+
+const TAG_END: u8 = 0;
+const TAG_COLUMN: u8 = 1;
+const TAG_CHUNK: u8 = 2;
+
+/// Options of `export_to`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportOptions {
+	/// The columns to export, in this order.
+	pub columns: Vec<u32>,
+	/// Size of the entries after which a chunk is closed. A chunk holds at least one entry.
+	pub chunk_size: usize,
+	/// Export only the entries after this position, to resume an interrupted import.
+	pub resume_after: Option<ExportPosition>,
+}
+
+impl ExportOptions {
+	/// Export the given columns entirely, in chunks of 4 MiB.
+	pub fn new(columns: Vec<u32>) -> Self {
+		ExportOptions { columns, chunk_size: 4 * 1024 * 1024, resume_after: None }
+	}
+}
+
+/// The last entry of a chunk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportPosition {
+	/// The column.
+	pub col: u32,
+	/// The key.
+	pub key: Box<[u8]>,
+}
+
+/// Progress of an export or an import.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportProgress {
+	/// Number of entries exported or imported.
+	pub keys: u64,
+	/// Number of chunks exported or imported.
+	pub chunks: u64,
+	/// The last entry of the last complete chunk.
+	pub last: Option<ExportPosition>,
+}
+
+/// Error of an export stream which can't be imported.
+///
+/// Surfaced as the inner error of an `io::Error` of kind `InvalidData`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExportError {
+	/// The stream doesn't start with the expected magic.
+	NotAnExport,
+	/// The stream was written with an unsupported version of the format.
+	UnsupportedVersion(u32),
+	/// The checksum of a chunk doesn't match its payload.
+	ChecksumMismatch {
+		/// The column of the chunk.
+		col: u32,
+	},
+	/// A chunk is malformed, precedes any column header, or isn't sorted.
+	Malformed(&'static str),
+}
+
+impl fmt::Display for ExportError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			ExportError::NotAnExport => write!(f, "Not a database export"),
+			ExportError::UnsupportedVersion(v) => write!(f, "Unsupported database export version {}", v),
+			ExportError::ChecksumMismatch { col } => write!(f, "Checksum mismatch in a chunk of column {}", col),
+			ExportError::Malformed(reason) => write!(f, "Malformed database export: {}", reason),
+		}
+	}
+}
+
+impl error::Error for ExportError {}
+
+impl From<ExportError> for io::Error {
+	fn from(err: ExportError) -> io::Error {
+		io::Error::new(io::ErrorKind::InvalidData, err)
+	}
+}
+
+/// Write the flushed contents of the columns to `writer`.
+///
+/// Every column is read with a snapshot iterator, see `IterOptions`, so backends with native
+/// snapshots export a consistent view of each column without holding it in memory.
+pub fn export_to<D, W>(db: &D, mut writer: W, options: &ExportOptions) -> io::Result<ExportProgress>
+where
+	D: KeyValueDB + ?Sized,
+	W: io::Write,
+{
+	writer.write_all(MAGIC)?;
+	writer.write_all(&EXPORT_VERSION.to_be_bytes())?;
+
+	let mut progress = ExportProgress::default();
+	let mut columns = &options.columns[..];
+	if let Some(ref resume) = options.resume_after {
+		let start = columns
+			.iter()
+			.position(|col| *col == resume.col)
+			.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Resumed column is not exported"))?;
+		columns = &columns[start..];
+	}
+	for &col in columns {
+		writer.write_all(&[TAG_COLUMN])?;
+		writer.write_all(&col.to_be_bytes())?;
+
+		let skip_until = options.resume_after.as_ref().filter(|resume| resume.col == col).map(|resume| &resume.key);
+		let iter = db
+			.iter_with_options(col, IterOptions { snapshot: true })
+			.skip_while(|(key, _)| matches!(skip_until, Some(last) if key <= last));
+		let mut chunk = Vec::new();
+		let mut entries = 0u32;
+		let mut last = None;
+		for (key, value) in iter {
+			put_bytes(&mut chunk, &key)?;
+			put_bytes(&mut chunk, &value)?;
+			entries += 1;
+			progress.keys += 1;
+			last = Some(key);
+			if chunk.len() >= options.chunk_size {
+				write_chunk(&mut writer, &mut chunk, &mut entries)?;
+				progress.chunk_written(col, &mut last);
+			}
+		}
+		if entries > 0 {
+			write_chunk(&mut writer, &mut chunk, &mut entries)?;
+			progress.chunk_written(col, &mut last);
+		}
+	}
+	writer.write_all(&[TAG_END])?;
+	writer.flush()?;
+	Ok(progress)
+}
+
+/// Import an export stream written by `export_to` into `db`.
+///
+/// Every chunk is verified and written with `KeyValueDB::write`. `progress` is updated after
+/// every chunk, so that after an error it tells which entries have been imported.
+pub fn import_from<D, R>(db: &D, mut reader: R, progress: &mut ExportProgress) -> io::Result<()>
+where
+	D: KeyValueDB + ?Sized,
+	R: io::Read,
+{
+	let mut magic = [0u8; 8];
+	reader.read_exact(&mut magic)?;
+	if &magic != MAGIC {
+		return Err(ExportError::NotAnExport.into());
+	}
+	let version = read_u32(&mut reader)?;
+	if version != EXPORT_VERSION {
+		return Err(ExportError::UnsupportedVersion(version).into());
+	}
+
+	let mut col = None;
+	let mut previous: Option<Box<[u8]>> = None;
+	loop {
+		let mut tag = [0u8; 1];
+		reader.read_exact(&mut tag)?;
+		match tag[0] {
+			TAG_END => return Ok(()),
+			TAG_COLUMN => {
+				col = Some(read_u32(&mut reader)?);
+				previous = None;
+			}
+			TAG_CHUNK => {
+				let col = col.ok_or(ExportError::Malformed("chunk before any column header"))?;
+				let entries = read_u32(&mut reader)?;
+				let len = read_u32(&mut reader)?;
+				let checksum = read_u32(&mut reader)?;
+				let mut payload = Vec::new();
+				(&mut reader).take(len as u64).read_to_end(&mut payload)?;
+				if payload.len() != len as usize {
+					return Err(io::ErrorKind::UnexpectedEof.into());
+				}
+				if crc32(&payload) != checksum {
+					return Err(ExportError::ChecksumMismatch { col }.into());
+				}
+
+				let mut transaction = db.transaction();
+				let mut rest = &payload[..];
+				for _ in 0..entries {
+					let key = take_bytes(&mut rest)?;
+					let value = take_bytes(&mut rest)?;
+					if matches!(previous, Some(ref previous) if key <= &previous[..]) {
+						return Err(ExportError::Malformed("keys are not sorted").into());
+					}
+					transaction.put(col, key, value);
+					previous = Some(key.into());
+				}
+				if !rest.is_empty() {
+					return Err(ExportError::Malformed("trailing bytes in a chunk").into());
+				}
+				db.write(transaction)?;
+				progress.keys += entries as u64;
+				progress.chunks += 1;
+				progress.last = previous.clone().map(|key| ExportPosition { col, key });
+			}
+			_ => return Err(ExportError::Malformed("unknown record").into()),
+		}
+	}
+}
+
+impl ExportProgress {
+	fn chunk_written(&mut self, col: u32, last: &mut Option<Box<[u8]>>) {
+		self.chunks += 1;
+		if let Some(key) = last.take() {
+			self.last = Some(ExportPosition { col, key });
+		}
+	}
+}
+
+fn write_chunk<W: io::Write>(writer: &mut W, chunk: &mut Vec<u8>, entries: &mut u32) -> io::Result<()> {
+	writer.write_all(&[TAG_CHUNK])?;
+	writer.write_all(&entries.to_be_bytes())?;
+	writer.write_all(&len_u32(chunk.len())?.to_be_bytes())?;
+	writer.write_all(&crc32(chunk).to_be_bytes())?;
+	writer.write_all(chunk)?;
+	chunk.clear();
+	*entries = 0;
+	Ok(())
+}
+
+fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) -> io::Result<()> {
+	out.extend_from_slice(&len_u32(bytes.len())?.to_be_bytes());
+	out.extend_from_slice(bytes);
+	Ok(())
+}
+
+fn take_bytes<'a>(rest: &mut &'a [u8]) -> Result<&'a [u8], ExportError> {
+	let truncated = ExportError::Malformed("truncated entry");
+	if rest.len() < 4 {
+		return Err(truncated);
+	}
+	let mut len = [0u8; 4];
+	len.copy_from_slice(&rest[..4]);
+	let len = u32::from_be_bytes(len) as usize;
+	if rest.len() - 4 < len {
+		return Err(truncated);
+	}
+	let bytes = &rest[4..4 + len];
+	*rest = &rest[4 + len..];
+	Ok(bytes)
+}
+
+fn len_u32(len: usize) -> io::Result<u32> {
+	if len > u32::MAX as usize {
+		return Err(io::Error::new(io::ErrorKind::InvalidInput, "Entry or chunk too large to export"));
+	}
+	Ok(len as u32)
+}
+
+fn read_u32<R: io::Read>(reader: &mut R) -> io::Result<u32> {
+	let mut bytes = [0u8; 4];
+	reader.read_exact(&mut bytes)?;
+	Ok(u32::from_be_bytes(bytes))
+}
+
+/// CRC-32 (IEEE) of `bytes`.
+fn crc32(bytes: &[u8]) -> u32 {
+	let mut crc = !0u32;
+	for &byte in bytes {
+		crc ^= byte as u32;
+		for _ in 0..8 {
+			crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+		}
+	}
+	!crc
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{crc32, export_to, import_from, take_bytes, ExportError, ExportOptions, ExportProgress};
+	use crate::test_db::{create, TestDb};
+	use crate::KeyValueDB;
+	use std::io;
+
+	#[test]
+	fn crc32_check_value() {
+		assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+		assert_eq!(crc32(b""), 0);
+	}
+
+	#[test]
+	fn truncated_entries_are_rejected() {
+		let mut rest = &[0u8, 0, 0, 2, 1, 2, 0, 0, 0, 5, 1][..];
+		assert_eq!(take_bytes(&mut rest).unwrap(), &[1, 2]);
+		assert!(take_bytes(&mut rest).is_err());
+		assert!(take_bytes(&mut &[0u8, 0][..]).is_err());
+	}
+
+	fn export_test_db() -> TestDb {
+		let db = create(3);
+		let mut transaction = db.transaction();
+		for i in 0u8..10 {
+			transaction.put(0, &[i], &[i; 10]);
+			transaction.put(2, &[i, i], &[]);
+		}
+		db.write(transaction).unwrap();
+		db
+	}
+
+	#[test]
+	fn export_import_roundtrip() {
+		let db = export_test_db();
+		let mut options = ExportOptions::new(vec![0, 1, 2]);
+		options.chunk_size = 32;
+		let mut stream = Vec::new();
+		let exported = export_to(&db, &mut stream, &options).unwrap();
+		assert_eq!(exported.keys, 20);
+		assert!(exported.chunks > 2);
+
+		let copy = create(3);
+		let mut imported = ExportProgress::default();
+		import_from(&copy, &stream[..], &mut imported).unwrap();
+		assert_eq!(imported, exported);
+		for col in 0..3 {
+			assert_eq!(copy.iter(col).collect::<Vec<_>>(), db.iter(col).collect::<Vec<_>>());
+		}
+	}
+
+	#[test]
+	fn interrupted_import_resumes() {
+		let db = export_test_db();
+		let mut options = ExportOptions::new(vec![0, 2]);
+		options.chunk_size = 32;
+		let mut stream = Vec::new();
+		export_to(&db, &mut stream, &options).unwrap();
+
+		let copy = create(3);
+		let mut progress = ExportProgress::default();
+		let err = import_from(&copy, &stream[..stream.len() / 2], &mut progress).unwrap_err();
+		assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+		let last = progress.last.clone().unwrap();
+		assert_eq!(copy.iter(last.col).last().unwrap().0, last.key);
+
+		options.resume_after = Some(last);
+		let mut stream = Vec::new();
+		export_to(&db, &mut stream, &options).unwrap();
+		import_from(&copy, &stream[..], &mut progress).unwrap();
+		assert_eq!(progress.keys, 20);
+		for col in 0..3 {
+			assert_eq!(copy.iter(col).collect::<Vec<_>>(), db.iter(col).collect::<Vec<_>>());
+		}
+	}
+
+	#[test]
+	fn corrupted_export_is_rejected() {
+		let db = export_test_db();
+		let mut stream = Vec::new();
+		export_to(&db, &mut stream, &ExportOptions::new(vec![0])).unwrap();
+		// flip a bit of the last value
+		let len = stream.len();
+		stream[len - 2] ^= 1;
+
+		let copy = create(1);
+		let err = import_from(&copy, &stream[..], &mut ExportProgress::default()).unwrap_err();
+		let err = err.into_inner().unwrap().downcast::<ExportError>().unwrap();
+		assert_eq!(*err, ExportError::ChecksumMismatch { col: 0 });
+		assert_eq!(copy.iter(0).count(), 0);
+
+		let err = import_from(&copy, &b"not an export"[..], &mut ExportProgress::default()).unwrap_err();
+		assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+	}
+}
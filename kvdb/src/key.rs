@@ -0,0 +1,124 @@
+// Copyright 2015-2019 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Order-preserving encoding of keys.
+
+use std::convert::TryInto;
+
+/// Encode a number so that the byte-wise order of keys matches the numeric order.
+pub fn encode_u64_be(n: u64) -> [u8; 8] {
+	n.to_be_bytes()
+}
+
+/// Decode a number encoded with `encode_u64_be` from the first 8 bytes of `key`.
+pub fn decode_u64_be(key: &[u8]) -> Option<u64> {
+	key.get(..8).map(|bytes| u64::from_be_bytes(bytes.try_into().expect("the slice has 8 bytes; qed")))
+}
+
+/// A value encoded into a key preserving its order.
+///
+/// Keys are iterated in byte-wise order, so numbers are encoded in big-endian. Tuples concatenate
+/// the encodings of their fields, ordering by the first field, then by the next ones. Since all
+/// implementations have a fixed length, composite keys can be prefix-scanned by their first fields,
+/// e.g. `(block_number, hash.to_fixed_bytes())` keys by block number.
+pub trait KeyEncode {
+	/// Append the encoding of `self` to `key`.
+	fn encode_key_to(&self, key: &mut Vec<u8>);
+
+	/// Encode `self` into a new key.
+	fn encode_key(&self) -> Vec<u8> {
+		let mut key = Vec::new();
+		self.encode_key_to(&mut key);
+		key
+	}
+}
+
+macro_rules! impl_key_encode_for_uint {
+	($($t:ty),*) => {
+		$(impl KeyEncode for $t {
+			fn encode_key_to(&self, key: &mut Vec<u8>) {
+				key.extend_from_slice(&self.to_be_bytes());
+			}
+		})*
+	};
+}
+
+impl_key_encode_for_uint!(u8, u16, u32, u64, u128);
+
+macro_rules! impl_key_encode_for_array {
+	($($n:expr),*) => {
+		$(impl KeyEncode for [u8; $n] {
+			fn encode_key_to(&self, key: &mut Vec<u8>) {
+				key.extend_from_slice(self);
+			}
+		})*
+	};
+}
+
+impl_key_encode_for_array!(4, 8, 16, 20, 32, 64);
+
+impl<A: KeyEncode, B: KeyEncode> KeyEncode for (A, B) {
+	fn encode_key_to(&self, key: &mut Vec<u8>) {
+		self.0.encode_key_to(key);
+		self.1.encode_key_to(key);
+	}
+}
+
+impl<A: KeyEncode, B: KeyEncode, C: KeyEncode> KeyEncode for (A, B, C) {
+	fn encode_key_to(&self, key: &mut Vec<u8>) {
+		self.0.encode_key_to(key);
+		self.1.encode_key_to(key);
+		self.2.encode_key_to(key);
+	}
+}
+
+impl<T: KeyEncode + ?Sized> KeyEncode for &T {
+	fn encode_key_to(&self, key: &mut Vec<u8>) {
+		(**self).encode_key_to(key)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{decode_u64_be, encode_u64_be, KeyEncode};
+
+	#[test]
+	fn numbers_keep_their_order() {
+		let numbers = [0u64, 1, 255, 256, 65_535, 1 << 40, u64::MAX];
+		let keys: Vec<_> = numbers.iter().map(|n| encode_u64_be(*n)).collect();
+		assert!(keys.windows(2).all(|w| w[0] < w[1]));
+		assert!(numbers.iter().zip(keys.iter()).all(|(n, k)| decode_u64_be(k) == Some(*n)));
+		assert_eq!(decode_u64_be(&[1, 2, 3]), None);
+		assert_eq!(256u16.encode_key(), vec![1, 0]);
+	}
+
+	#[test]
+	fn composite_keys_order_by_fields() {
+		let hash = |b: u8| [b; 32];
+		let mut keys = [
+			(2u64, hash(0)).encode_key(),
+			(1u64, hash(0xff)).encode_key(),
+			(256u64, hash(1)).encode_key(),
+			(1u64, hash(2)).encode_key(),
+		];
+		keys.sort();
+		let numbers: Vec<_> = keys.iter().map(|k| decode_u64_be(k).unwrap()).collect();
+		assert_eq!(numbers, vec![1, 1, 2, 256]);
+		assert_eq!(keys[0][8..], hash(2));
+		assert_eq!(keys[0].len(), 40);
+		assert_eq!((1u8, 2u32, &[3u8; 4]).encode_key(), vec![1, 0, 0, 0, 2, 3, 3, 3, 3]);
+	}
+}
@@ -16,11 +16,31 @@
 
 //! Key-Value store abstraction with `RocksDB` backend.
 
-use bytes::Bytes;
 use elastic_array::{ElasticArray128, ElasticArray32};
+use rand::{Rng, RngCore};
 use std::io;
 use std::path::Path;
 use std::sync::Arc;
+use std::time::SystemTime;
+
+mod batching;
+pub mod display;
+mod export;
+mod key;
+mod limits;
+mod lock;
+mod metadata;
+mod scoped;
+#[cfg(test)]
+mod test_db;
+
+pub use batching::{BatchingConfig, BatchingDb};
+pub use export::{export_to, import_from, ExportError, ExportOptions, ExportPosition, ExportProgress, EXPORT_VERSION};
+pub use key::{decode_u64_be, encode_u64_be, KeyEncode};
+pub use limits::{SizeLimitError, SizeLimits};
+pub use lock::{ColumnGuard, ColumnLocks};
+pub use metadata::{check_header, check_metadata, open_or_create, Metadata, MetadataError};
+pub use scoped::ScopedDb;
 
 /// Required length of prefixes.
 pub const PREFIX_LEN: usize = 12;
@@ -28,6 +48,38 @@ pub const PREFIX_LEN: usize = 12;
 /// Database value.
 pub type DBValue = ElasticArray128<u8>;
 
+/// Computes the new value of a key from its current one, see `KeyValueDB::update`.
+pub type UpdateFn<'a> = dyn Fn(Option<&[u8]>) -> Option<Vec<u8>> + 'a;
+
+/// Options of `KeyValueDB::iter_with_options`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IterOptions {
+	/// Observe the column as it was when the iterator was created, unaffected by later writes.
+	pub snapshot: bool,
+}
+
+/// State of a database, see `KeyValueDB::health`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DbHealth {
+	/// Whether the database is open.
+	pub open: bool,
+	/// Number of errors of the background flushes and compactions. Writes fail after such an error.
+	pub background_errors: u64,
+	/// Size of the keys and values written with `write_buffered` and not flushed yet.
+	pub buffered_bytes: u64,
+	/// Number of transactions committed and not flushed yet.
+	pub pending_transactions: u64,
+	/// Time of the last successful write to disk, `None` if there was none since opening.
+	pub last_flush: Option<SystemTime>,
+}
+
+impl DbHealth {
+	/// Whether the database can still be used.
+	pub fn is_healthy(&self) -> bool {
+		self.open && self.background_errors == 0
+	}
+}
+
 /// Write transaction. Batches a sequence of put/delete operations for efficiency.
 #[derive(Default, Clone, PartialEq)]
 pub struct DBTransaction {
@@ -79,7 +131,7 @@ impl DBTransaction {
 	}
 
 	/// Insert a key-value pair in the transaction. Any existing value will be overwritten upon write.
-	pub fn put_vec(&mut self, col: u32, key: &[u8], value: Bytes) {
+	pub fn put_vec(&mut self, col: u32, key: &[u8], value: Vec<u8>) {
 		let mut ekey = ElasticArray32::new();
 		ekey.append_slice(key);
 		self.ops.push(DBOp::Insert { col, key: ekey, value: DBValue::from_vec(value) });
@@ -124,29 +176,160 @@ pub trait KeyValueDB: Sync + Send {
 	fn get_by_prefix(&self, col: u32, prefix: &[u8]) -> Option<Box<[u8]>>;
 
 	/// Write a transaction of changes to the buffer.
-	fn write_buffered(&self, transaction: DBTransaction);
+	///
+	/// As for `write`, the transaction is applied atomically, across all columns it touches.
+	///
+	/// Returns the sequence number of the transaction, see `last_committed`. A transaction the
+	/// database doesn't accept, e.g. one exceeding its `SizeLimits`, is rejected with an error
+	/// before any of its changes is buffered.
+	fn write_buffered(&self, transaction: DBTransaction) -> io::Result<u64>;
 
 	/// Write a transaction of changes to the backing store.
-	fn write(&self, transaction: DBTransaction) -> io::Result<()> {
-		self.write_buffered(transaction);
-		self.flush()
+	///
+	/// The transaction is atomic, even if it touches several columns: either all of its changes
+	/// are applied or none of them, including after a crash for durable backends. Callers such as
+	/// pruning rely on this to keep the columns consistent with each other.
+	///
+	/// Returns the sequence number of the transaction, see `last_committed`.
+	fn write(&self, transaction: DBTransaction) -> io::Result<u64> {
+		let seq = self.write_buffered(transaction)?;
+		self.flush()?;
+		Ok(seq)
 	}
 
+	/// Sequence number of the last transaction visible to readers.
+	///
+	/// Every written transaction gets the next number, starting from 1, so that 0 means nothing has
+	/// been written since the database was opened. Numbers are not persisted across restarts.
+	fn last_committed(&self) -> u64;
+
 	/// Flush all buffered data.
 	fn flush(&self) -> io::Result<()>;
 
-	/// Iterate over flushed data for a given column.
+	/// Atomically replace the value of `key` with the result of `f` applied to the current value.
+	///
+	/// Returning `None` from `f` deletes the key. Backends override this to make concurrent
+	/// updates of the same key safe; `f` may be called more than once. The default implementation
+	/// is a plain read followed by a write and gives no such guarantee.
+	fn update(&self, col: u32, key: &[u8], f: &UpdateFn<'_>) -> io::Result<()> {
+		let current = self.get(col, key)?;
+		let mut transaction = self.transaction();
+		match f(current.as_ref().map(|v| &v[..])) {
+			Some(value) => transaction.put_vec(col, key, value),
+			None => transaction.delete(col, key),
+		}
+		self.write(transaction).map(|_| ())
+	}
+
+	/// Delete all keys of a column starting with `prefix`, returning how many were deleted.
+	///
+	/// Buffered writes are flushed first. The default implementation deletes the keys yielded by
+	/// `iter_from_prefix` in a single transaction. Backends override it with cheaper primitives.
+	fn delete_by_prefix(&self, col: u32, prefix: &[u8]) -> io::Result<u64> {
+		self.flush()?;
+		let mut transaction = self.transaction();
+		for (key, _) in self.iter_from_prefix(col, prefix) {
+			transaction.delete(col, &key);
+		}
+		let count = transaction.ops.len() as u64;
+		if count > 0 {
+			self.write(transaction)?;
+		}
+		Ok(count)
+	}
+
+	/// Iterate over flushed data for a given column, in strictly ascending key order.
+	///
+	/// Whether writes made while iterating are observed depends on the backend, use
+	/// `iter_with_options` to rule it out.
 	fn iter<'a>(&'a self, col: u32) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a>;
 
+	/// Iterate over flushed data for a given column like `iter`, with guarantees given by `options`.
+	///
+	/// The default implementation reads the whole column up front to take a snapshot.
+	/// Backends with cheaper snapshots override it.
+	fn iter_with_options<'a>(
+		&'a self,
+		col: u32,
+		options: IterOptions,
+	) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
+		if options.snapshot {
+			Box::new(self.iter(col).collect::<Vec<_>>().into_iter())
+		} else {
+			self.iter(col)
+		}
+	}
+
 	/// Iterate over flushed data for a given column, starting from a given prefix.
+	///
+	/// Yields only keys starting with the prefix, in strictly ascending key order.
 	fn iter_from_prefix<'a>(
 		&'a self,
 		col: u32,
 		prefix: &'a [u8],
 	) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a>;
 
+	/// Pick up to `n` distinct keys of the flushed data of a column uniformly at random,
+	/// in no particular order.
+	///
+	/// The default implementation reservoir-samples `iter`, so that only `n` keys are held in memory.
+	fn sample_keys(&self, col: u32, n: usize, rng: &mut dyn RngCore) -> Vec<Box<[u8]>> {
+		// `n` may well exceed the number of keys, don't trust it for the allocation
+		let mut sample = Vec::with_capacity(n.min(1024));
+		for (i, (key, _)) in self.iter(col).enumerate() {
+			if i < n {
+				sample.push(key);
+			} else {
+				let j = rng.gen_range(0, i + 1);
+				if j < n {
+					sample[j] = key;
+				}
+			}
+		}
+		sample
+	}
+
 	/// Attempt to replace this database with a new one located at the given path.
 	fn restore(&self, new_db: &str) -> io::Result<()>;
+
+	/// Lock a column exclusively, e.g. for a multi-step migration of its contents.
+	///
+	/// The lock is advisory: it doesn't prevent reads and writes, it only blocks until other
+	/// holders of a lock on the same column release it. See `ColumnLocks`.
+	fn lock_column(&self, col: u32) -> ColumnGuard<'_>;
+
+	/// Lock a column shared with other shared locks, excluding exclusive locks.
+	fn lock_column_shared(&self, col: u32) -> ColumnGuard<'_>;
+
+	/// Report whether the database is usable and how much data is waiting to be flushed.
+	///
+	/// This must be cheap enough to be polled, e.g. by a liveness probe. By default the database
+	/// is reported open with nothing pending, which is right for databases not buffering writes.
+	fn health(&self) -> io::Result<DbHealth> {
+		Ok(DbHealth { open: true, ..DbHealth::default() })
+	}
+
+	/// Read the raw metadata header, stored apart from the columns.
+	///
+	/// Returns `None` if no header has been written yet.
+	fn metadata(&self) -> io::Result<Option<DBValue>> {
+		Err(io::Error::new(io::ErrorKind::Other, "Metadata is not supported by this database"))
+	}
+
+	/// Store the raw metadata header. See `check_metadata`.
+	fn set_metadata(&self, _value: &[u8]) -> io::Result<()> {
+		Err(io::Error::new(io::ErrorKind::Other, "Metadata is not supported by this database"))
+	}
+
+	/// View of the keys of column `col` starting with `prefix`, as column `0` of a `KeyValueDB`.
+	///
+	/// Use `ScopedDb::new` for an `Arc<dyn KeyValueDB>`.
+	fn scoped(self: Arc<Self>, col: u32, prefix: Vec<u8>) -> ScopedDb
+	where
+		Self: Sized + 'static,
+	{
+		ScopedDb::new(self, col, prefix)
+	}
 }
 
 /// Generic key-value database handler. This trait contains one function `open`.
@@ -0,0 +1,138 @@
+// Copyright 2015-2019 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Limits of key and value sizes.
+
+use std::{error, fmt, io};
+
+use crate::{display::hex, DBOp, DBTransaction};
+
+/// Maximum sizes of keys and values accepted by a database.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SizeLimits {
+	/// Maximum key length in bytes, unlimited if `None`.
+	pub max_key_len: Option<usize>,
+	/// Maximum value length in bytes, unlimited if `None`.
+	pub max_value_len: Option<usize>,
+}
+
+impl SizeLimits {
+	/// No limits.
+	pub fn unlimited() -> Self {
+		Self::default()
+	}
+
+	/// Check all operations of the transaction, returning the first violation.
+	pub fn check(&self, transaction: &DBTransaction) -> Result<(), SizeLimitError> {
+		transaction.ops.iter().try_for_each(|op| self.check_op(op))
+	}
+
+	/// Check a single operation.
+	pub fn check_op(&self, op: &DBOp) -> Result<(), SizeLimitError> {
+		if let Some(limit) = self.max_key_len {
+			if op.key().len() > limit {
+				return Err(SizeLimitError::KeyTooLong { col: op.col(), len: op.key().len(), limit });
+			}
+		}
+		if let (Some(limit), DBOp::Insert { col, key, value }) = (self.max_value_len, op) {
+			if value.len() > limit {
+				return Err(SizeLimitError::ValueTooLong { col: *col, key: key.to_vec(), len: value.len(), limit });
+			}
+		}
+		Ok(())
+	}
+}
+
+/// A transaction exceeding the `SizeLimits` of a database.
+///
+/// Surfaced as the inner error of an `io::Error` of kind `InvalidInput`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SizeLimitError {
+	/// A key is longer than allowed.
+	KeyTooLong {
+		/// Column of the key.
+		col: u32,
+		/// Length of the key.
+		len: usize,
+		/// Maximum key length.
+		limit: usize,
+	},
+	/// A value is longer than allowed.
+	ValueTooLong {
+		/// Column of the value.
+		col: u32,
+		/// Key of the value.
+		key: Vec<u8>,
+		/// Length of the value.
+		len: usize,
+		/// Maximum value length.
+		limit: usize,
+	},
+}
+
+impl fmt::Display for SizeLimitError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			SizeLimitError::KeyTooLong { col, len, limit } => {
+				write!(f, "Key of {} bytes in column {} exceeds the limit of {} bytes", len, col, limit)
+			}
+			SizeLimitError::ValueTooLong { col, ref key, len, limit } => {
+				write!(
+					f,
+					"Value of {} bytes for key {} in column {} exceeds the limit of {} bytes",
+					len,
+					hex(key),
+					col,
+					limit
+				)
+			}
+		}
+	}
+}
+
+impl error::Error for SizeLimitError {}
+
+impl From<SizeLimitError> for io::Error {
+	fn from(err: SizeLimitError) -> io::Error {
+		io::Error::new(io::ErrorKind::InvalidInput, err)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{SizeLimitError, SizeLimits};
+	use crate::DBTransaction;
+
+	#[test]
+	fn check_limits() {
+		let limits = SizeLimits { max_key_len: Some(4), max_value_len: Some(8) };
+		let mut transaction = DBTransaction::new();
+		transaction.put(0, b"key", b"value");
+		transaction.delete(1, b"four");
+		assert_eq!(limits.check(&transaction), Ok(()));
+		assert_eq!(SizeLimits::unlimited().check(&transaction), Ok(()));
+
+		transaction.put(2, b"key", b"long value");
+		let err = limits.check(&transaction).unwrap_err();
+		assert_eq!(err, SizeLimitError::ValueTooLong { col: 2, key: b"key".to_vec(), len: 10, limit: 8 });
+		assert_eq!(err.to_string(), "Value of 10 bytes for key 0x6b6579 in column 2 exceeds the limit of 8 bytes");
+
+		let mut transaction = DBTransaction::new();
+		transaction.delete(0, b"long key");
+		let err = limits.check(&transaction).unwrap_err();
+		assert_eq!(err, SizeLimitError::KeyTooLong { col: 0, len: 8, limit: 4 });
+	}
+}
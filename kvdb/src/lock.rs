@@ -0,0 +1,150 @@
+// Copyright 2015-2019 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Advisory per-column read-write locks.
+
+use std::collections::HashMap;
+use std::sync::{Condvar, Mutex, MutexGuard};
+
+/// Lock state of a column.
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+	Shared(usize),
+	Exclusive,
+}
+
+/// Advisory read-write locks of the columns of a database.
+///
+/// The locks don't affect reads and writes of the database, they only exclude other holders of
+/// a lock on the same column. Backends keep one instance to implement `KeyValueDB::lock_column`.
+#[derive(Default)]
+pub struct ColumnLocks {
+	columns: Mutex<HashMap<u32, State>>,
+	released: Condvar,
+}
+
+impl ColumnLocks {
+	/// Create unlocked column locks.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Lock the column exclusively, blocking until all other locks of the column are released.
+	pub fn lock(&self, col: u32) -> ColumnGuard<'_> {
+		let mut columns = self.state();
+		while columns.contains_key(&col) {
+			columns = self.released.wait(columns).unwrap_or_else(|e| e.into_inner());
+		}
+		columns.insert(col, State::Exclusive);
+		ColumnGuard { locks: self, col }
+	}
+
+	/// Lock the column shared with other shared locks, blocking while it's locked exclusively.
+	pub fn lock_shared(&self, col: u32) -> ColumnGuard<'_> {
+		let mut columns = self.state();
+		loop {
+			match columns.get(&col).cloned() {
+				Some(State::Exclusive) => columns = self.released.wait(columns).unwrap_or_else(|e| e.into_inner()),
+				Some(State::Shared(n)) => {
+					columns.insert(col, State::Shared(n + 1));
+					break;
+				}
+				None => {
+					columns.insert(col, State::Shared(1));
+					break;
+				}
+			}
+		}
+		ColumnGuard { locks: self, col }
+	}
+
+	fn state(&self) -> MutexGuard<'_, HashMap<u32, State>> {
+		// the map is consistent after every operation, so a poisoned lock is fine to reuse
+		self.columns.lock().unwrap_or_else(|e| e.into_inner())
+	}
+
+	fn release(&self, col: u32) {
+		let mut columns = self.state();
+		match columns.get(&col).cloned() {
+			Some(State::Shared(n)) if n > 1 => {
+				columns.insert(col, State::Shared(n - 1));
+			}
+			_ => {
+				columns.remove(&col);
+				self.released.notify_all();
+			}
+		}
+	}
+}
+
+/// A lock of a column, released on drop.
+#[must_use = "the column is unlocked immediately if the guard is not used"]
+pub struct ColumnGuard<'a> {
+	locks: &'a ColumnLocks,
+	col: u32,
+}
+
+impl<'a> ColumnGuard<'a> {
+	/// The locked column.
+	pub fn column(&self) -> u32 {
+		self.col
+	}
+}
+
+impl<'a> Drop for ColumnGuard<'a> {
+	fn drop(&mut self) {
+		self.locks.release(self.col);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::ColumnLocks;
+	use std::sync::{mpsc, Arc};
+	use std::thread;
+	use std::time::Duration;
+
+	#[test]
+	fn exclusive_lock_blocks_other_locks_of_the_column() {
+		let locks = Arc::new(ColumnLocks::new());
+		let guard = locks.lock(0);
+		let _other_column = locks.lock(1);
+
+		let (tx, rx) = mpsc::channel();
+		let handle = {
+			let locks = locks.clone();
+			thread::spawn(move || {
+				let _guard = locks.lock_shared(0);
+				tx.send(()).unwrap();
+			})
+		};
+		assert!(rx.recv_timeout(Duration::from_millis(50)).is_err());
+		drop(guard);
+		assert!(rx.recv_timeout(Duration::from_secs(5)).is_ok());
+		handle.join().unwrap();
+	}
+
+	#[test]
+	fn shared_locks_coexist() {
+		let locks = ColumnLocks::new();
+		let first = locks.lock_shared(0);
+		let second = locks.lock_shared(0);
+		assert_eq!(second.column(), 0);
+		drop(first);
+		drop(second);
+		let _exclusive = locks.lock(0);
+	}
+}
@@ -0,0 +1,201 @@
+// Copyright 2015-2019 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Version and column layout header stored by a database at creation.
+
+use std::{error, fmt, io};
+
+use crate::KeyValueDB;
+
+const MAGIC: &[u8; 4] = b"kvdb";
+const ENCODED_LEN: usize = 12;
+
+/// Version and column layout of a database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Metadata {
+	/// Version of the data format, chosen by the client.
+	pub version: u32,
+	/// Number of columns.
+	pub columns: u32,
+}
+
+impl Metadata {
+	/// Create metadata for the given version and number of columns.
+	pub fn new(version: u32, columns: u32) -> Self {
+		Metadata { version, columns }
+	}
+
+	/// Encode the header as stored by the backends.
+	pub fn encode(&self) -> [u8; ENCODED_LEN] {
+		let mut out = [0u8; ENCODED_LEN];
+		out[..4].copy_from_slice(MAGIC);
+		out[4..8].copy_from_slice(&self.version.to_be_bytes());
+		out[8..].copy_from_slice(&self.columns.to_be_bytes());
+		out
+	}
+
+	/// Decode a stored header. Returns `None` if the bytes are not a valid header.
+	pub fn decode(bytes: &[u8]) -> Option<Self> {
+		if bytes.len() != ENCODED_LEN || &bytes[..4] != MAGIC {
+			return None;
+		}
+		let mut version = [0u8; 4];
+		let mut columns = [0u8; 4];
+		version.copy_from_slice(&bytes[4..8]);
+		columns.copy_from_slice(&bytes[8..]);
+		Some(Metadata { version: u32::from_be_bytes(version), columns: u32::from_be_bytes(columns) })
+	}
+}
+
+impl fmt::Display for Metadata {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "version {}, {} columns", self.version, self.columns)
+	}
+}
+
+/// Error returned when the stored header doesn't match the expected one.
+///
+/// Surfaced as the inner error of an `io::Error` of kind `InvalidData`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetadataError {
+	/// The database was created with a different version or column layout.
+	Mismatch {
+		/// Metadata the caller expected.
+		expected: Metadata,
+		/// Metadata found in the database.
+		found: Metadata,
+	},
+	/// The stored header can't be decoded.
+	Corrupted,
+	/// The database holds data but no header, it was created before headers were written.
+	Missing,
+}
+
+impl fmt::Display for MetadataError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			MetadataError::Mismatch { ref expected, ref found } => {
+				write!(f, "Database metadata mismatch: expected {}, found {}", expected, found)
+			}
+			MetadataError::Corrupted => write!(f, "Database metadata header is corrupted"),
+			MetadataError::Missing => write!(f, "Database has data but no metadata header"),
+		}
+	}
+}
+
+impl error::Error for MetadataError {}
+
+impl From<MetadataError> for io::Error {
+	fn from(err: MetadataError) -> io::Error {
+		io::Error::new(io::ErrorKind::InvalidData, err)
+	}
+}
+
+/// Check the header of a freshly opened database.
+///
+/// Writes `expected` if the database has no header and no data yet, i.e. it has just been created.
+/// Fails with `MetadataError::Missing` if it has data but no header, and with
+/// `MetadataError::Mismatch` carrying the stored layout if that differs from `expected`.
+///
+/// The database is expected to have `expected.columns` columns.
+pub fn check_metadata<D: KeyValueDB + ?Sized>(db: &D, expected: &Metadata) -> io::Result<()> {
+	match db.metadata()? {
+		None if (0..expected.columns).all(|col| db.iter(col).next().is_none()) => db.set_metadata(&expected.encode()),
+		None => Err(MetadataError::Missing.into()),
+		Some(raw) => check_header(&raw, expected).map_err(Into::into),
+	}
+}
+
+/// Check a stored header against `expected`.
+pub fn check_header(raw: &[u8], expected: &Metadata) -> Result<(), MetadataError> {
+	match Metadata::decode(raw) {
+		Some(ref found) if found == expected => Ok(()),
+		Some(found) => Err(MetadataError::Mismatch { expected: *expected, found }),
+		None => Err(MetadataError::Corrupted),
+	}
+}
+
+/// Open a database with `open` and check its header against `expected`.
+///
+/// See `check_metadata`.
+pub fn open_or_create<D, F>(open: F, expected: &Metadata) -> io::Result<D>
+where
+	D: KeyValueDB,
+	F: FnOnce() -> io::Result<D>,
+{
+	let db = open()?;
+	check_metadata(&db, expected)?;
+	Ok(db)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{check_metadata, open_or_create, Metadata, MetadataError};
+	use crate::test_db::create;
+	use crate::KeyValueDB;
+	use std::io;
+
+	#[test]
+	fn encode_decode_roundtrip() {
+		let metadata = Metadata::new(3, 7);
+		assert_eq!(Metadata::decode(&metadata.encode()), Some(metadata));
+		assert_eq!(Metadata::decode(b"kvdb"), None);
+		assert_eq!(Metadata::decode(b"nope\0\0\0\x03\0\0\0\x07"), None);
+	}
+
+	#[test]
+	fn metadata_written_on_create() {
+		let expected = Metadata::new(1, 2);
+		let db = open_or_create(|| Ok(create(2)), &expected).unwrap();
+		assert_eq!(Metadata::decode(&db.metadata().unwrap().unwrap()), Some(expected));
+		assert!(check_metadata(&db, &expected).is_ok());
+	}
+
+	#[test]
+	fn metadata_mismatch_reports_found_layout() {
+		let found = Metadata::new(1, 2);
+		let expected = Metadata::new(2, 3);
+		let db = create(1);
+		db.set_metadata(&found.encode()).unwrap();
+
+		let err = check_metadata(&db, &expected).unwrap_err();
+		assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+		let err = err.into_inner().unwrap().downcast::<MetadataError>().unwrap();
+		assert_eq!(*err, MetadataError::Mismatch { expected, found });
+	}
+
+	#[test]
+	fn corrupted_metadata_is_rejected() {
+		let db = create(1);
+		db.set_metadata(b"garbage").unwrap();
+		let err = check_metadata(&db, &Metadata::new(1, 1)).unwrap_err();
+		let err = err.into_inner().unwrap().downcast::<MetadataError>().unwrap();
+		assert_eq!(*err, MetadataError::Corrupted);
+	}
+
+	#[test]
+	fn legacy_database_without_metadata_is_rejected() {
+		let db = create(2);
+		let mut batch = db.transaction();
+		batch.put(1, b"key", b"value");
+		db.write(batch).unwrap();
+
+		let err = check_metadata(&db, &Metadata::new(1, 2)).unwrap_err();
+		let err = err.into_inner().unwrap().downcast::<MetadataError>().unwrap();
+		assert_eq!(*err, MetadataError::Missing);
+		assert_eq!(db.metadata().unwrap(), None);
+	}
+}
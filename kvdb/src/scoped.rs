@@ -0,0 +1,248 @@
+// Copyright 2015-2019 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A view of the keys of a column sharing a prefix.
+
+use std::io;
+use std::sync::Arc;
+
+use elastic_array::ElasticArray32;
+
+use crate::{ColumnGuard, DBOp, DBTransaction, DBValue, DbHealth, IterOptions, KeyValueDB, UpdateFn};
+
+/// A view of the keys of one column of a database starting with a prefix, see `KeyValueDB::scoped`.
+///
+/// The view has a single column, `0`. The prefix is prepended to the keys of all reads and
+/// writes and stripped from the keys yielded by iterators, so several stores can share a column
+/// without seeing each other's keys, provided none of their prefixes is a prefix of another.
+///
+/// Reads of and writes to other columns fail as for a missing column, a transaction touching
+/// another column is rejected as a whole. Column locks lock the whole underlying column.
+pub struct ScopedDb {
+	db: Arc<dyn KeyValueDB>,
+	col: u32,
+	prefix: Vec<u8>,
+}
+
+impl ScopedDb {
+	/// Create a view of the keys of column `col` of `db` starting with `prefix`.
+	pub fn new(db: Arc<dyn KeyValueDB>, col: u32, prefix: Vec<u8>) -> Self {
+		ScopedDb { db, col, prefix }
+	}
+
+	/// The underlying column.
+	pub fn column(&self) -> u32 {
+		self.col
+	}
+
+	/// The prefix of the keys of the view.
+	pub fn prefix(&self) -> &[u8] {
+		&self.prefix
+	}
+
+	fn key(&self, key: &[u8]) -> Vec<u8> {
+		let mut prefixed = Vec::with_capacity(self.prefix.len() + key.len());
+		prefixed.extend_from_slice(&self.prefix);
+		prefixed.extend_from_slice(key);
+		prefixed
+	}
+
+	fn check_column(col: u32) -> io::Result<()> {
+		match col {
+			0 => Ok(()),
+			_ => Err(io::Error::new(io::ErrorKind::Other, format!("No such column family: {:?}", col))),
+		}
+	}
+
+	fn transaction_of(&self, transaction: DBTransaction) -> io::Result<DBTransaction> {
+		let mut scoped = DBTransaction::with_capacity(transaction.ops.len());
+		for op in transaction.ops {
+			Self::check_column(op.col())?;
+			let mut key = ElasticArray32::new();
+			key.append_slice(&self.prefix);
+			key.append_slice(op.key());
+			scoped.ops.push(match op {
+				DBOp::Insert { value, .. } => DBOp::Insert { col: self.col, key, value },
+				DBOp::Delete { .. } => DBOp::Delete { col: self.col, key },
+			});
+		}
+		Ok(scoped)
+	}
+
+	fn strip<'b>(
+		&self,
+		iter: Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'b>,
+	) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'b> {
+		let len = self.prefix.len();
+		Box::new(iter.map(move |(key, value)| (key[len..].into(), value)))
+	}
+}
+
+impl KeyValueDB for ScopedDb {
+	fn get(&self, col: u32, key: &[u8]) -> io::Result<Option<DBValue>> {
+		Self::check_column(col)?;
+		self.db.get(self.col, &self.key(key))
+	}
+
+	fn get_by_prefix(&self, col: u32, prefix: &[u8]) -> Option<Box<[u8]>> {
+		Self::check_column(col).ok()?;
+		self.db.get_by_prefix(self.col, &self.key(prefix))
+	}
+
+	fn write_buffered(&self, transaction: DBTransaction) -> io::Result<u64> {
+		self.db.write_buffered(self.transaction_of(transaction)?)
+	}
+
+	fn write(&self, transaction: DBTransaction) -> io::Result<u64> {
+		self.db.write(self.transaction_of(transaction)?)
+	}
+
+	fn last_committed(&self) -> u64 {
+		self.db.last_committed()
+	}
+
+	fn flush(&self) -> io::Result<()> {
+		self.db.flush()
+	}
+
+	fn update(&self, col: u32, key: &[u8], f: &UpdateFn<'_>) -> io::Result<()> {
+		Self::check_column(col)?;
+		self.db.update(self.col, &self.key(key), f)
+	}
+
+	fn delete_by_prefix(&self, col: u32, prefix: &[u8]) -> io::Result<u64> {
+		Self::check_column(col)?;
+		self.db.delete_by_prefix(self.col, &self.key(prefix))
+	}
+
+	fn iter<'a>(&'a self, col: u32) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
+		match col {
+			0 => self.strip(self.db.iter_from_prefix(self.col, &self.prefix)),
+			_ => Box::new(None.into_iter()),
+		}
+	}
+
+	// without a snapshot `iter_from_prefix` seeks to the prefix, the snapshot is filtered instead
+	fn iter_with_options<'a>(
+		&'a self,
+		col: u32,
+		options: IterOptions,
+	) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
+		if col != 0 || !options.snapshot {
+			return self.iter(col);
+		}
+		let prefix = &self.prefix[..];
+		let iter = self
+			.db
+			.iter_with_options(self.col, options)
+			.skip_while(move |(key, _)| &key[..] < prefix)
+			.take_while(move |(key, _)| key.starts_with(prefix));
+		self.strip(Box::new(iter))
+	}
+
+	fn iter_from_prefix<'a>(
+		&'a self,
+		col: u32,
+		prefix: &'a [u8],
+	) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
+		Box::new(self.iter(col).filter(move |(key, _)| key.starts_with(prefix)))
+	}
+
+	fn restore(&self, _new_db: &str) -> io::Result<()> {
+		Err(io::Error::new(io::ErrorKind::Other, "Attempted to restore a scoped database view"))
+	}
+
+	fn lock_column(&self, _col: u32) -> ColumnGuard<'_> {
+		self.db.lock_column(self.col)
+	}
+
+	fn lock_column_shared(&self, _col: u32) -> ColumnGuard<'_> {
+		self.db.lock_column_shared(self.col)
+	}
+
+	fn health(&self) -> io::Result<DbHealth> {
+		self.db.health()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::ScopedDb;
+	use crate::test_db::create;
+	use crate::{IterOptions, KeyValueDB};
+	use std::sync::Arc;
+
+	#[test]
+	fn scoped_views_share_a_column() {
+		let db = Arc::new(create(2));
+		let a = db.clone().scoped(1, b"a/".to_vec());
+		let shared: Arc<dyn KeyValueDB> = Arc::new(create(1));
+		let b = ScopedDb::new(shared.clone(), 0, b"b/".to_vec());
+
+		for view in &[&a, &b] {
+			// a transaction touching another column is rejected as a whole
+			let mut transaction = view.transaction();
+			transaction.put(0, b"key1", b"rejected");
+			transaction.put(1, b"other", b"");
+			assert!(view.write(transaction).is_err());
+
+			let mut transaction = view.transaction();
+			transaction.put(0, b"key1", view.prefix());
+			transaction.put(0, b"key2", view.prefix());
+			view.write(transaction).unwrap();
+		}
+		let mut transaction = db.transaction();
+		transaction.put(1, b"a", b"outside");
+		transaction.put(1, b"b/key1", b"outside");
+		db.write(transaction).unwrap();
+
+		assert_eq!(&*a.get(0, b"key1").unwrap().unwrap(), b"a/");
+		assert_eq!(&*db.get(1, b"a/key1").unwrap().unwrap(), b"a/");
+		assert_eq!(&*shared.get(0, b"b/key2").unwrap().unwrap(), b"b/");
+		assert!(a.get(0, b"missing").unwrap().is_none());
+		assert!(a.get(1, b"key1").is_err());
+		assert_eq!(db.iter(1).count(), 4);
+
+		let keys = |iter: Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + '_>| {
+			iter.map(|(key, _)| key.into_vec()).collect::<Vec<_>>()
+		};
+		assert_eq!(keys(a.iter(0)), vec![b"key1".to_vec(), b"key2".to_vec()]);
+		assert_eq!(keys(a.iter_with_options(0, IterOptions { snapshot: true })), keys(a.iter(0)));
+		assert_eq!(keys(a.iter_from_prefix(0, b"key2")), vec![b"key2".to_vec()]);
+		assert_eq!(a.iter(1).count(), 0);
+		assert_eq!(&*a.get_by_prefix(0, b"key").unwrap(), b"a/");
+
+		let mut transaction = a.transaction();
+		transaction.delete(0, b"key1");
+		a.write(transaction).unwrap();
+		a.update(0, b"key2", &|_| Some(b"updated".to_vec())).unwrap();
+		assert_eq!(keys(a.iter(0)), vec![b"key2".to_vec()]);
+		assert_eq!(&*db.get(1, b"a/key2").unwrap().unwrap(), b"updated");
+		assert_eq!(&*db.get(1, b"b/key1").unwrap().unwrap(), b"outside");
+	}
+
+	#[test]
+	fn delete_by_prefix_stays_in_scope() {
+		let db = Arc::new(create(1));
+		let mut transaction = db.transaction();
+		transaction.put(0, b"a/1", b"");
+		transaction.put(0, b"a/2", b"");
+		transaction.put(0, b"b/1", b"");
+		db.write(transaction).unwrap();
+		assert_eq!(db.clone().scoped(0, b"a/".to_vec()).delete_by_prefix(0, b"").unwrap(), 2);
+		assert_eq!(db.iter(0).count(), 1);
+	}
+}
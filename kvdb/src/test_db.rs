@@ -0,0 +1,142 @@
+// Copyright 2015-2019 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A minimal in-memory `KeyValueDB` for the tests of this crate.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+
+use crate::{ColumnGuard, ColumnLocks, DBOp, DBTransaction, DBValue, KeyValueDB, SizeLimits};
+
+type Column = BTreeMap<Box<[u8]>, DBValue>;
+
+pub struct TestDb {
+	columns: RwLock<Vec<Column>>,
+	metadata: RwLock<Option<DBValue>>,
+	last_committed: AtomicU64,
+	column_locks: ColumnLocks,
+	size_limits: SizeLimits,
+}
+
+/// A database with `num_cols` empty columns.
+pub fn create(num_cols: u32) -> TestDb {
+	create_with_size_limits(num_cols, SizeLimits::unlimited())
+}
+
+/// A database with `num_cols` empty columns, rejecting transactions exceeding `size_limits`.
+pub fn create_with_size_limits(num_cols: u32, size_limits: SizeLimits) -> TestDb {
+	TestDb {
+		columns: RwLock::new((0..num_cols).map(|_| Column::new()).collect()),
+		metadata: RwLock::new(None),
+		last_committed: AtomicU64::new(0),
+		column_locks: ColumnLocks::new(),
+		size_limits,
+	}
+}
+
+fn no_such_column(col: u32) -> io::Error {
+	io::Error::new(io::ErrorKind::Other, format!("No such column family: {:?}", col))
+}
+
+impl TestDb {
+	fn pairs<'a>(&self, col: u32, prefix: &[u8]) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
+		let columns = self.columns.read().expect("poisoned");
+		let pairs = match columns.get(col as usize) {
+			Some(map) => map
+				.iter()
+				.filter(|(key, _)| key.starts_with(prefix))
+				.map(|(key, value)| (key.clone(), value.to_vec().into_boxed_slice()))
+				.collect(),
+			None => Vec::new(),
+		};
+		Box::new(pairs.into_iter())
+	}
+}
+
+impl KeyValueDB for TestDb {
+	fn get(&self, col: u32, key: &[u8]) -> io::Result<Option<DBValue>> {
+		let columns = self.columns.read().expect("poisoned");
+		let map = columns.get(col as usize).ok_or_else(|| no_such_column(col))?;
+		Ok(map.get(key).cloned())
+	}
+
+	fn get_by_prefix(&self, col: u32, prefix: &[u8]) -> Option<Box<[u8]>> {
+		self.pairs(col, prefix).next().map(|(_, value)| value)
+	}
+
+	fn write_buffered(&self, transaction: DBTransaction) -> io::Result<u64> {
+		self.size_limits.check(&transaction)?;
+		let mut columns = self.columns.write().expect("poisoned");
+		let num_cols = columns.len() as u32;
+		if let Some(op) = transaction.ops.iter().find(|op| op.col() >= num_cols) {
+			return Err(no_such_column(op.col()));
+		}
+		for op in transaction.ops {
+			match op {
+				DBOp::Insert { col, key, value } => {
+					columns[col as usize].insert(key.into_vec().into_boxed_slice(), value);
+				}
+				DBOp::Delete { col, key } => {
+					columns[col as usize].remove(&*key);
+				}
+			}
+		}
+		Ok(self.last_committed.fetch_add(1, Ordering::SeqCst) + 1)
+	}
+
+	fn last_committed(&self) -> u64 {
+		self.last_committed.load(Ordering::SeqCst)
+	}
+
+	fn flush(&self) -> io::Result<()> {
+		Ok(())
+	}
+
+	fn iter<'a>(&'a self, col: u32) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
+		self.pairs(col, &[])
+	}
+
+	fn iter_from_prefix<'a>(
+		&'a self,
+		col: u32,
+		prefix: &'a [u8],
+	) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
+		self.pairs(col, prefix)
+	}
+
+	fn restore(&self, _new_db: &str) -> io::Result<()> {
+		Err(io::Error::new(io::ErrorKind::Other, "Attempted to restore a test database"))
+	}
+
+	fn lock_column(&self, col: u32) -> ColumnGuard<'_> {
+		self.column_locks.lock(col)
+	}
+
+	fn lock_column_shared(&self, col: u32) -> ColumnGuard<'_> {
+		self.column_locks.lock_shared(col)
+	}
+
+	fn metadata(&self) -> io::Result<Option<DBValue>> {
+		Ok(self.metadata.read().expect("poisoned").clone())
+	}
+
+	fn set_metadata(&self, value: &[u8]) -> io::Result<()> {
+		*self.metadata.write().expect("poisoned") = Some(DBValue::from_slice(value));
+		Ok(())
+	}
+}
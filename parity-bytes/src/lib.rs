@@ -17,7 +17,7 @@
 //! General bytes-related utilities.
 //!
 //! Includes a pretty-printer for bytes, in the form of `ToPretty` and `PrettySlice`
-//! as
+//! as well as `Bytes`, a reference-counted byte buffer with cheap slicing.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
@@ -25,8 +25,12 @@
 extern crate alloc;
 
 #[cfg(not(feature = "std"))]
-use alloc::{format, string::String, vec::Vec};
-use core::{cmp::min, fmt, ops};
+use alloc::{format, string::String, sync::Arc, vec::Vec};
+use core::iter::FromIterator;
+use core::ops::{Bound, RangeBounds};
+use core::{borrow::Borrow, cmp, cmp::min, fmt, hash, ops};
+#[cfg(feature = "std")]
+use std::sync::Arc;
 
 /// Slice pretty print helper
 pub struct PrettySlice<'a>(&'a [u8]);
@@ -73,7 +77,7 @@ impl<T: AsRef<[u8]>> ToPretty for T {
 /// A byte collection reference that can either be a slice or a vector
 pub enum BytesRef<'a> {
 	/// This is a reference to a vector
-	Flexible(&'a mut Bytes),
+	Flexible(&'a mut Vec<u8>),
 	/// This is a reference to a slice
 	Fixed(&'a mut [u8]),
 }
@@ -122,14 +126,227 @@ impl<'a> ops::DerefMut for BytesRef<'a> {
 	}
 }
 
-/// Vector of bytes.
-pub type Bytes = Vec<u8>;
+/// Immutable, reference-counted bytes.
+///
+/// Clones and slices share the underlying allocation, so passing the same payload
+/// around or taking parts of it doesn't copy any data.
+///
+/// With the `bytes` feature, bytes converted from `bytes::Bytes` keep sharing its buffer, and
+/// converting them back doesn't copy either. Bytes created from a `Vec<u8>` are converted to
+/// `bytes::Bytes` without copying as long as they are the only view of the whole vector.
+///
+/// ```rust
+/// use parity_bytes::Bytes;
+///
+/// let packet = Bytes::from(vec![0xc2, 0x01, 0x02]);
+/// let payload = packet.slice(1..);
+/// assert_eq!(payload, [0x01, 0x02][..]);
+/// assert_eq!(payload.slice(1..), [0x02][..]);
+/// ```
+#[derive(Clone, Default)]
+pub struct Bytes {
+	data: Storage,
+	start: usize,
+	end: usize,
+}
+
+#[derive(Clone)]
+enum Storage {
+	Vec(Arc<Vec<u8>>),
+	#[cfg(feature = "bytes")]
+	Bytes(bytes::Bytes),
+}
+
+impl Default for Storage {
+	fn default() -> Self {
+		Storage::Vec(Arc::default())
+	}
+}
+
+impl Storage {
+	fn as_slice(&self) -> &[u8] {
+		match self {
+			Storage::Vec(data) => data,
+			#[cfg(feature = "bytes")]
+			Storage::Bytes(data) => data,
+		}
+	}
+}
+
+impl Bytes {
+	/// Creates empty bytes.
+	pub fn new() -> Self {
+		Bytes::default()
+	}
+
+	/// Returns the number of bytes.
+	pub fn len(&self) -> usize {
+		self.end - self.start
+	}
+
+	/// Returns true if there are no bytes.
+	pub fn is_empty(&self) -> bool {
+		self.start == self.end
+	}
+
+	/// Returns the bytes as a slice.
+	pub fn as_slice(&self) -> &[u8] {
+		&self.data.as_slice()[self.start..self.end]
+	}
+
+	/// Returns a view of given range of the bytes, sharing the allocation.
+	///
+	/// Panics if the range is out of bounds, like slice indexing does.
+	pub fn slice<R: RangeBounds<usize>>(&self, range: R) -> Bytes {
+		let start = match range.start_bound() {
+			Bound::Included(&start) => start,
+			Bound::Excluded(&start) => start + 1,
+			Bound::Unbounded => 0,
+		};
+		let end = match range.end_bound() {
+			Bound::Included(&end) => end + 1,
+			Bound::Excluded(&end) => end,
+			Bound::Unbounded => self.len(),
+		};
+		assert!(start <= end, "slice index starts at {} but ends at {}", start, end);
+		assert!(end <= self.len(), "range end index {} out of range for bytes of length {}", end, self.len());
+
+		Bytes { data: self.data.clone(), start: self.start + start, end: self.start + end }
+	}
+
+	/// Converts into a vector. Doesn't copy if this is the only reference to the whole allocation.
+	pub fn into_vec(self) -> Vec<u8> {
+		match self.data {
+			Storage::Vec(data) if self.start == 0 && self.end == data.len() => match Arc::try_unwrap(data) {
+				Ok(data) => data,
+				Err(data) => data.to_vec(),
+			},
+			_ => self.as_slice().to_vec(),
+		}
+	}
+}
+
+impl ops::Deref for Bytes {
+	type Target = [u8];
+
+	fn deref(&self) -> &[u8] {
+		self.as_slice()
+	}
+}
+
+impl AsRef<[u8]> for Bytes {
+	fn as_ref(&self) -> &[u8] {
+		self.as_slice()
+	}
+}
+
+impl Borrow<[u8]> for Bytes {
+	fn borrow(&self) -> &[u8] {
+		self.as_slice()
+	}
+}
+
+impl fmt::Debug for Bytes {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		fmt::Debug::fmt(self.as_slice(), f)
+	}
+}
+
+impl PartialEq for Bytes {
+	fn eq(&self, other: &Self) -> bool {
+		self.as_slice() == other.as_slice()
+	}
+}
+
+impl Eq for Bytes {}
+
+impl PartialEq<[u8]> for Bytes {
+	fn eq(&self, other: &[u8]) -> bool {
+		self.as_slice() == other
+	}
+}
+
+impl PartialEq<Vec<u8>> for Bytes {
+	fn eq(&self, other: &Vec<u8>) -> bool {
+		self.as_slice() == &other[..]
+	}
+}
+
+impl PartialOrd for Bytes {
+	fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for Bytes {
+	fn cmp(&self, other: &Self) -> cmp::Ordering {
+		self.as_slice().cmp(other.as_slice())
+	}
+}
+
+impl hash::Hash for Bytes {
+	fn hash<H: hash::Hasher>(&self, state: &mut H) {
+		self.as_slice().hash(state)
+	}
+}
+
+impl From<Vec<u8>> for Bytes {
+	fn from(data: Vec<u8>) -> Self {
+		let end = data.len();
+		Bytes { data: Storage::Vec(Arc::new(data)), start: 0, end }
+	}
+}
+
+impl<'a> From<&'a [u8]> for Bytes {
+	fn from(data: &'a [u8]) -> Self {
+		Bytes::from(data.to_vec())
+	}
+}
+
+impl From<Bytes> for Vec<u8> {
+	fn from(bytes: Bytes) -> Self {
+		bytes.into_vec()
+	}
+}
+
+impl FromIterator<u8> for Bytes {
+	fn from_iter<I: IntoIterator<Item = u8>>(iter: I) -> Self {
+		Bytes::from(iter.into_iter().collect::<Vec<_>>())
+	}
+}
+
+impl<'a> IntoIterator for &'a Bytes {
+	type Item = &'a u8;
+	type IntoIter = core::slice::Iter<'a, u8>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.as_slice().iter()
+	}
+}
+
+#[cfg(feature = "bytes")]
+impl From<bytes::Bytes> for Bytes {
+	fn from(bytes: bytes::Bytes) -> Self {
+		let end = bytes.len();
+		Bytes { data: Storage::Bytes(bytes), start: 0, end }
+	}
+}
+
+#[cfg(feature = "bytes")]
+impl From<Bytes> for bytes::Bytes {
+	fn from(bytes: Bytes) -> Self {
+		match bytes.data {
+			Storage::Bytes(ref data) => data.slice(bytes.start..bytes.end),
+			Storage::Vec(_) => bytes::Bytes::from(bytes.into_vec()),
+		}
+	}
+}
 
 #[cfg(test)]
 mod tests {
-	use super::BytesRef;
+	use super::{Bytes, BytesRef};
 	#[cfg(not(feature = "std"))]
-	use alloc::vec;
+	use alloc::{vec, vec::Vec};
 
 	#[test]
 	fn should_write_bytes_to_fixed_bytesref() {
@@ -182,4 +399,63 @@ mod tests {
 		assert_eq!(&data3, &[0, 0, 0, 0, 0, 1, 1, 1]);
 		assert_eq!(res3, 5);
 	}
+
+	#[test]
+	fn should_slice_bytes_without_copying() {
+		let bytes = Bytes::from(vec![0, 1, 2, 3, 4]);
+		let slice = bytes.slice(1..4);
+		assert_eq!(slice, [1, 2, 3][..]);
+		assert_eq!(slice.slice(..=1), [1, 2][..]);
+		assert_eq!(slice.slice(3..), Bytes::new());
+		assert_eq!(slice.as_ptr(), bytes[1..].as_ptr());
+		assert_eq!(slice.into_vec(), vec![1, 2, 3]);
+	}
+
+	#[test]
+	#[should_panic]
+	fn should_panic_when_slicing_out_of_range() {
+		Bytes::from(vec![0, 1]).slice(1..3);
+	}
+
+	#[test]
+	fn should_convert_back_to_vec_without_copying_if_unique() {
+		let data = vec![1, 2, 3];
+		let ptr = data.as_ptr();
+		let bytes = Bytes::from(data);
+		let clone = bytes.clone();
+		let copied = Vec::from(clone);
+		assert_ne!(copied.as_ptr(), ptr);
+		let unwrapped = Vec::from(bytes);
+		assert_eq!(unwrapped.as_ptr(), ptr);
+	}
+
+	#[test]
+	fn should_compare_bytes_by_content() {
+		let bytes = Bytes::from(vec![0, 1, 2, 1, 2]);
+		assert_eq!(bytes.slice(1..3), bytes.slice(3..));
+		assert!(bytes.slice(..2) < bytes.slice(1..3));
+		assert_eq!(bytes.iter().cloned().collect::<Bytes>(), bytes);
+	}
+
+	#[cfg(feature = "bytes")]
+	#[test]
+	fn should_convert_from_and_to_bytes_crate() {
+		let bytes = Bytes::from(vec![1, 2, 3]).slice(1..);
+		let converted = bytes::Bytes::from(bytes.clone());
+		assert_eq!(&converted[..], &[2, 3]);
+		assert_eq!(Bytes::from(converted), bytes);
+
+		// the buffer of `bytes::Bytes` is shared both ways
+		let original = bytes::Bytes::from(vec![1, 2, 3, 4]);
+		let shared = Bytes::from(original.clone()).slice(1..3);
+		assert_eq!(shared.as_ptr(), original[1..].as_ptr());
+		let back = bytes::Bytes::from(shared);
+		assert_eq!(&back[..], &[2, 3]);
+		assert_eq!(back.as_ptr(), original[1..].as_ptr());
+
+		// a vector is handed over when nothing else refers to it
+		let vec = vec![5, 6];
+		let ptr = vec.as_ptr();
+		assert_eq!(bytes::Bytes::from(Bytes::from(vec)).as_ptr(), ptr);
+	}
 }
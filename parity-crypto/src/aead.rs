@@ -0,0 +1,185 @@
+// Copyright 2015-2019 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Authenticated encryption with associated data (AES-256-GCM).
+//!
+//! Unlike the CTR constructions in `aes`, the cipher text is authenticated, so there is no
+//! separate MAC to compute and check. A nonce must never be reused with the same key,
+//! `NonceSequence` hands out unique nonces for a key.
+
+use aes_gcm::aead::{generic_array::GenericArray, Aead, NewAead, Payload};
+use rand::{rngs::OsRng, RngCore};
+
+use crate::error::SymmError;
+
+/// Length of the AES-256-GCM key.
+pub const KEY_LENGTH: usize = 32;
+/// Length of the AES-256-GCM nonce.
+pub const NONCE_LENGTH: usize = 12;
+/// Length of the authentication tag appended to the cipher text.
+pub const TAG_LENGTH: usize = 16;
+
+/// AES-256-GCM cipher.
+pub struct Aes256Gcm(aes_gcm::Aes256Gcm);
+
+impl Aes256Gcm {
+	/// New cipher using given key
+	pub fn new(key: &[u8; KEY_LENGTH]) -> Self {
+		Aes256Gcm(aes_gcm::Aes256Gcm::new(*GenericArray::from_slice(key)))
+	}
+
+	/// Encrypt and authenticate `plain` and authenticate `auth_data`.
+	/// Returns the cipher text followed by the authentication tag.
+	pub fn encrypt(&self, nonce: &[u8; NONCE_LENGTH], plain: &[u8], auth_data: &[u8]) -> Result<Vec<u8>, SymmError> {
+		let payload = Payload { msg: plain, aad: auth_data };
+		Ok(self.0.encrypt(GenericArray::from_slice(nonce), payload)?)
+	}
+
+	/// Check the authentication tag and decrypt the cipher text produced by `encrypt`.
+	/// Fails if the cipher text, the authenticated data or the nonce doesn't match.
+	pub fn decrypt(&self, nonce: &[u8; NONCE_LENGTH], cipher: &[u8], auth_data: &[u8]) -> Result<Vec<u8>, SymmError> {
+		let payload = Payload { msg: cipher, aad: auth_data };
+		Ok(self.0.decrypt(GenericArray::from_slice(nonce), payload)?)
+	}
+
+	/// Encrypt with the next nonce of the sequence.
+	/// Returns the nonce followed by the cipher text and the authentication tag.
+	pub fn seal(&self, nonces: &mut NonceSequence, plain: &[u8], auth_data: &[u8]) -> Result<Vec<u8>, SymmError> {
+		let nonce = nonces.next_nonce()?;
+		let mut sealed = Vec::with_capacity(NONCE_LENGTH + plain.len() + TAG_LENGTH);
+		sealed.extend_from_slice(&nonce);
+		sealed.extend_from_slice(&self.encrypt(&nonce, plain, auth_data)?);
+		Ok(sealed)
+	}
+
+	/// Decrypt a message produced by `seal`.
+	pub fn open(&self, sealed: &[u8], auth_data: &[u8]) -> Result<Vec<u8>, SymmError> {
+		if sealed.len() < NONCE_LENGTH {
+			return Err(SymmError::authentication());
+		}
+		let mut nonce = [0u8; NONCE_LENGTH];
+		nonce.copy_from_slice(&sealed[..NONCE_LENGTH]);
+		self.decrypt(&nonce, &sealed[NONCE_LENGTH..], auth_data)
+	}
+}
+
+/// Unique nonces for a single key: a fixed 4 bytes prefix followed by a 64 bits big-endian counter.
+///
+/// Use a different prefix for each party encrypting with the same key.
+pub struct NonceSequence {
+	prefix: [u8; 4],
+	counter: u64,
+}
+
+impl NonceSequence {
+	/// New sequence with a random prefix
+	pub fn new() -> Self {
+		let mut prefix = [0u8; 4];
+		OsRng.fill_bytes(&mut prefix);
+		NonceSequence::with_prefix(prefix)
+	}
+
+	/// New sequence with given prefix
+	pub fn with_prefix(prefix: [u8; 4]) -> Self {
+		NonceSequence { prefix, counter: 0 }
+	}
+
+	/// Returns the next nonce, or an error once the counter is exhausted.
+	pub fn next_nonce(&mut self) -> Result<[u8; NONCE_LENGTH], SymmError> {
+		if self.counter == u64::max_value() {
+			return Err(SymmError::nonce_exhausted());
+		}
+		let mut nonce = [0u8; NONCE_LENGTH];
+		nonce[..4].copy_from_slice(&self.prefix);
+		nonce[4..].copy_from_slice(&self.counter.to_be_bytes());
+		self.counter += 1;
+		Ok(nonce)
+	}
+}
+
+impl Default for NonceSequence {
+	fn default() -> Self {
+		NonceSequence::new()
+	}
+}
+
+/// Random nonce, for keys used for a few messages only.
+///
+/// Random nonces may collide after about 2^32 messages with the same key.
+pub fn random_nonce() -> [u8; NONCE_LENGTH] {
+	let mut nonce = [0u8; NONCE_LENGTH];
+	OsRng.fill_bytes(&mut nonce);
+	nonce
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use hex_literal::hex;
+
+	const KEY: [u8; 32] = hex!("feffe9928665731c6d6a8f9467308308feffe9928665731c6d6a8f9467308308");
+	const NONCE: [u8; 12] = hex!("cafebabefacedbaddecaf888");
+	const AUTH_DATA: [u8; 20] = hex!("feedfacedeadbeeffeedfacedeadbeefabaddad2");
+
+	#[test]
+	fn should_match_gcm_spec_test_vector() {
+		// test case 16 of the GCM specification
+		let plain = hex!(
+			"d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a721c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39"
+		);
+		let cipher = hex!(
+			"522dc1f099567d07f47f37a32a84427d643a8cdcbfe5c0c97598a2bd2555d1aa8cb08e48590dbb3da7b08b1056828838c5f61e6393ba7a0abcc9f66276fc6ece0f4e1768cddf8853bb2d551b"
+		);
+
+		let aes = Aes256Gcm::new(&KEY);
+		assert_eq!(aes.encrypt(&NONCE, &plain, &AUTH_DATA).unwrap(), &cipher[..]);
+		assert_eq!(aes.decrypt(&NONCE, &cipher, &AUTH_DATA).unwrap(), &plain[..]);
+	}
+
+	#[test]
+	fn should_reject_tampered_messages() {
+		let aes = Aes256Gcm::new(&KEY);
+		let mut cipher = aes.encrypt(&NONCE, b"secret", &AUTH_DATA).unwrap();
+
+		assert!(aes.decrypt(&NONCE, &cipher, b"other data").is_err());
+		assert!(aes.decrypt(&random_nonce(), &cipher, &AUTH_DATA).is_err());
+		assert!(aes.decrypt(&NONCE, &cipher[..TAG_LENGTH - 1], &AUTH_DATA).is_err());
+		cipher[0] ^= 1;
+		assert!(aes.decrypt(&NONCE, &cipher, &AUTH_DATA).is_err());
+	}
+
+	#[test]
+	fn should_seal_and_open_with_unique_nonces() {
+		let aes = Aes256Gcm::new(&KEY);
+		let mut nonces = NonceSequence::with_prefix([1, 2, 3, 4]);
+		let first = aes.seal(&mut nonces, b"message", b"").unwrap();
+		let second = aes.seal(&mut nonces, b"message", b"").unwrap();
+
+		assert_eq!(&first[..NONCE_LENGTH], &[1, 2, 3, 4, 0, 0, 0, 0, 0, 0, 0, 0]);
+		assert_eq!(&second[..NONCE_LENGTH], &[1, 2, 3, 4, 0, 0, 0, 0, 0, 0, 0, 1]);
+		assert_ne!(first[NONCE_LENGTH..], second[NONCE_LENGTH..]);
+		assert_eq!(aes.open(&first, b"").unwrap(), b"message");
+		assert_eq!(aes.open(&second, b"").unwrap(), b"message");
+		assert!(aes.open(&second[..NONCE_LENGTH - 1], b"").is_err());
+	}
+
+	#[test]
+	fn should_fail_when_nonces_are_exhausted() {
+		let mut nonces = NonceSequence { prefix: [0; 4], counter: u64::max_value() - 1 };
+		assert!(nonces.next_nonce().is_ok());
+		assert!(nonces.next_nonce().is_err());
+	}
+}
@@ -20,6 +20,8 @@ use std::{error::Error as StdError, fmt, result};
 pub enum Error {
 	Scrypt(ScryptError),
 	Symm(SymmError),
+	#[cfg(feature = "argon2")]
+	Argon2(argon2::Error),
 }
 
 #[derive(Debug)]
@@ -40,6 +42,18 @@ enum PrivSymmErr {
 	BlockMode(block_modes::BlockModeError),
 	KeyStream(aes_ctr::stream_cipher::LoopError),
 	InvalidKeyLength(block_modes::InvalidKeyIvLength),
+	Authentication,
+	NonceExhausted,
+}
+
+impl SymmError {
+	pub(crate) fn authentication() -> Self {
+		SymmError(PrivSymmErr::Authentication)
+	}
+
+	pub(crate) fn nonce_exhausted() -> Self {
+		SymmError(PrivSymmErr::NonceExhausted)
+	}
 }
 
 impl StdError for Error {
@@ -47,6 +61,8 @@ impl StdError for Error {
 		match self {
 			Error::Scrypt(scrypt_err) => Some(scrypt_err),
 			Error::Symm(symm_err) => Some(symm_err),
+			#[cfg(feature = "argon2")]
+			Error::Argon2(argon2_err) => Some(argon2_err),
 		}
 	}
 }
@@ -76,6 +92,8 @@ impl fmt::Display for Error {
 		match self {
 			Error::Scrypt(err) => write!(f, "scrypt error: {}", err),
 			Error::Symm(err) => write!(f, "symm error: {}", err),
+			#[cfg(feature = "argon2")]
+			Error::Argon2(err) => write!(f, "argon2 error: {}", err),
 		}
 	}
 }
@@ -97,6 +115,8 @@ impl fmt::Display for SymmError {
 			SymmError(PrivSymmErr::BlockMode(err)) => write!(f, "block cipher error: {}", err),
 			SymmError(PrivSymmErr::KeyStream(err)) => write!(f, "ctr key stream ended: {}", err),
 			SymmError(PrivSymmErr::InvalidKeyLength(err)) => write!(f, "block cipher key length: {}", err),
+			SymmError(PrivSymmErr::Authentication) => write!(f, "authenticated decryption failed"),
+			SymmError(PrivSymmErr::NonceExhausted) => write!(f, "no more unique nonces"),
 		}
 	}
 }
@@ -125,6 +145,12 @@ impl From<aes_ctr::stream_cipher::LoopError> for SymmError {
 	}
 }
 
+impl From<aes_gcm::aead::Error> for SymmError {
+	fn from(_e: aes_gcm::aead::Error) -> SymmError {
+		SymmError::authentication()
+	}
+}
+
 impl From<scrypt::errors::InvalidParams> for ScryptError {
 	fn from(e: scrypt::errors::InvalidParams) -> ScryptError {
 		ScryptError::ScryptParam(e)
@@ -148,3 +174,10 @@ impl From<SymmError> for Error {
 		Error::Symm(e)
 	}
 }
+
+#[cfg(feature = "argon2")]
+impl From<argon2::Error> for Error {
+	fn from(e: argon2::Error) -> Error {
+		Error::Argon2(e)
+	}
+}
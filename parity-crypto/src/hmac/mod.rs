@@ -22,9 +22,9 @@ use digest::generic_array::{
 	GenericArray,
 };
 use hmac::{Hmac, Mac as _};
-use zeroize::Zeroize;
 
 use crate::digest::{Sha256, Sha512};
+use crate::secret::Secret;
 
 /// HMAC signature.
 #[derive(Debug)]
@@ -50,43 +50,22 @@ impl<T> Deref for Signature<T> {
 /// HMAC signing key.
 pub struct SigKey<T>(KeyInner, PhantomData<T>);
 
-#[derive(PartialEq)]
-// Using `Box[u8]` guarantees no reallocation can happen
-struct DisposableBox(Box<[u8]>);
-
-impl std::fmt::Debug for DisposableBox {
-	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		write!(f, "{:?}", &self.0.as_ref())
-	}
-}
-
-impl DisposableBox {
-	fn from_slice(data: &[u8]) -> Self {
-		Self(data.to_vec().into_boxed_slice())
-	}
-}
-
-impl Drop for DisposableBox {
-	fn drop(&mut self) {
-		self.0.zeroize()
-	}
-}
-
 #[derive(Debug, PartialEq)]
 enum KeyInner {
-	Sha256(DisposableBox),
-	Sha512(DisposableBox),
+	// Using `Box<[u8]>` guarantees no reallocation can happen
+	Sha256(Secret<Box<[u8]>>),
+	Sha512(Secret<Box<[u8]>>),
 }
 
 impl SigKey<Sha256> {
 	pub fn sha256(key: &[u8]) -> SigKey<Sha256> {
-		SigKey(KeyInner::Sha256(DisposableBox::from_slice(key)), PhantomData)
+		SigKey(KeyInner::Sha256(Secret::from(key)), PhantomData)
 	}
 }
 
 impl SigKey<Sha512> {
 	pub fn sha512(key: &[u8]) -> SigKey<Sha512> {
-		SigKey(KeyInner::Sha512(DisposableBox::from_slice(key)), PhantomData)
+		SigKey(KeyInner::Sha512(Secret::from(key)), PhantomData)
 	}
 }
 
@@ -109,11 +88,15 @@ impl<T> Signer<T> {
 	pub fn with(key: &SigKey<T>) -> Signer<T> {
 		match &key.0 {
 			KeyInner::Sha256(key_bytes) => Signer(
-				SignerInner::Sha256(Hmac::<sha2::Sha256>::new_varkey(&key_bytes.0).expect("always returns Ok; qed")),
+				SignerInner::Sha256(
+					Hmac::<sha2::Sha256>::new_varkey(key_bytes.expose()).expect("always returns Ok; qed"),
+				),
 				PhantomData,
 			),
 			KeyInner::Sha512(key_bytes) => Signer(
-				SignerInner::Sha512(Hmac::<sha2::Sha512>::new_varkey(&key_bytes.0).expect("always returns Ok; qed")),
+				SignerInner::Sha512(
+					Hmac::<sha2::Sha512>::new_varkey(key_bytes.expose()).expect("always returns Ok; qed"),
+				),
 				PhantomData,
 			),
 		}
@@ -139,13 +122,13 @@ pub struct VerifyKey<T>(KeyInner, PhantomData<T>);
 
 impl VerifyKey<Sha256> {
 	pub fn sha256(key: &[u8]) -> VerifyKey<Sha256> {
-		VerifyKey(KeyInner::Sha256(DisposableBox::from_slice(key)), PhantomData)
+		VerifyKey(KeyInner::Sha256(Secret::from(key)), PhantomData)
 	}
 }
 
 impl VerifyKey<Sha512> {
 	pub fn sha512(key: &[u8]) -> VerifyKey<Sha512> {
-		VerifyKey(KeyInner::Sha512(DisposableBox::from_slice(key)), PhantomData)
+		VerifyKey(KeyInner::Sha512(Secret::from(key)), PhantomData)
 	}
 }
 
@@ -153,12 +136,12 @@ impl VerifyKey<Sha512> {
 pub fn verify<T>(key: &VerifyKey<T>, data: &[u8], sig: &[u8]) -> bool {
 	match &key.0 {
 		KeyInner::Sha256(key_bytes) => {
-			let mut ctx = Hmac::<sha2::Sha256>::new_varkey(&key_bytes.0).expect("always returns Ok; qed");
+			let mut ctx = Hmac::<sha2::Sha256>::new_varkey(key_bytes.expose()).expect("always returns Ok; qed");
 			ctx.input(data);
 			ctx.verify(sig).is_ok()
 		}
 		KeyInner::Sha512(key_bytes) => {
-			let mut ctx = Hmac::<sha2::Sha512>::new_varkey(&key_bytes.0).expect("always returns Ok; qed");
+			let mut ctx = Hmac::<sha2::Sha512>::new_varkey(key_bytes.expose()).expect("always returns Ok; qed");
 			ctx.input(data);
 			ctx.verify(sig).is_ok()
 		}
@@ -0,0 +1,171 @@
+// Copyright 2015-2019 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Password based key derivation functions with tunable parameters.
+//!
+//! Keystore formats can be written against the `Kdf` trait and pick the function
+//! and its parameters from the stored metadata.
+
+use crate::error::{Error, ScryptError};
+
+/// A password based key derivation function.
+pub trait Kdf {
+	/// Derive a key from `password` and `salt`, filling the whole `out` buffer.
+	fn derive(&self, password: &[u8], salt: &[u8], out: &mut [u8]) -> Result<(), Error>;
+}
+
+/// PBKDF2 with HMAC-SHA256.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pbkdf2 {
+	/// Number of iterations.
+	pub iterations: u32,
+}
+
+impl Kdf for Pbkdf2 {
+	fn derive(&self, password: &[u8], salt: &[u8], out: &mut [u8]) -> Result<(), Error> {
+		pbkdf2::pbkdf2::<hmac::Hmac<sha2::Sha256>>(password, salt, self.iterations as usize, out);
+		Ok(())
+	}
+}
+
+/// scrypt, as used by the web3 secret storage v3 format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scrypt {
+	/// CPU/memory cost, rounded down to a power of two.
+	pub n: u32,
+	/// Block size.
+	pub r: u32,
+	/// Parallelization.
+	pub p: u32,
+}
+
+impl Default for Scrypt {
+	/// Parameters used by geth and parity for new keys.
+	fn default() -> Self {
+		Scrypt { n: 262_144, r: 8, p: 1 }
+	}
+}
+
+impl Kdf for Scrypt {
+	fn derive(&self, password: &[u8], salt: &[u8], out: &mut [u8]) -> Result<(), Error> {
+		// sanity checks
+		if self.n < 2 {
+			return Err(ScryptError::InvalidN.into());
+		}
+		let log_n = (32 - self.n.leading_zeros() - 1) as u8;
+		if log_n as u32 >= self.r * 16 {
+			return Err(ScryptError::InvalidN.into());
+		}
+
+		if self.p as u64 > ((u32::max_value() as u64 - 1) * 32) / (128 * (self.r as u64)) {
+			return Err(ScryptError::InvalidP.into());
+		}
+
+		let params = scrypt::ScryptParams::new(log_n, self.r, self.p).map_err(ScryptError::from)?;
+		scrypt::scrypt(password, salt, &params, out).map_err(ScryptError::from)?;
+		Ok(())
+	}
+}
+
+/// argon2id, version 1.3.
+#[cfg(feature = "argon2")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Argon2id {
+	/// Memory size in KiB.
+	pub mem_cost: u32,
+	/// Number of passes.
+	pub time_cost: u32,
+	/// Degree of parallelism.
+	pub lanes: u32,
+}
+
+#[cfg(feature = "argon2")]
+impl Default for Argon2id {
+	fn default() -> Self {
+		Argon2id { mem_cost: 65_536, time_cost: 3, lanes: 4 }
+	}
+}
+
+#[cfg(feature = "argon2")]
+impl Kdf for Argon2id {
+	fn derive(&self, password: &[u8], salt: &[u8], out: &mut [u8]) -> Result<(), Error> {
+		let config = argon2::Config {
+			variant: argon2::Variant::Argon2id,
+			version: argon2::Version::Version13,
+			mem_cost: self.mem_cost,
+			time_cost: self.time_cost,
+			lanes: self.lanes,
+			thread_mode: argon2::ThreadMode::Sequential,
+			secret: &[],
+			ad: &[],
+			hash_length: out.len() as u32,
+		};
+		let hash = argon2::hash_raw(password, salt, &config)?;
+		out.copy_from_slice(&hash);
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use hex_literal::hex;
+
+	#[test]
+	fn pbkdf2_rfc7914_vector() {
+		let mut out = [0u8; 64];
+		Pbkdf2 { iterations: 1 }.derive(b"passwd", b"salt", &mut out).unwrap();
+		assert_eq!(
+			&out[..],
+			&hex!("55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc49ca9cccf179b645991664b39d77ef317c71b845b1e30bd509112041d3a19783")[..]
+		);
+	}
+
+	#[test]
+	fn scrypt_rfc7914_vector() {
+		let mut out = [0u8; 64];
+		Scrypt { n: 1024, r: 8, p: 16 }.derive(b"password", b"NaCl", &mut out).unwrap();
+		assert_eq!(
+			&out[..],
+			&hex!("fdbabe1c9d3472007856e7190d01e9fe7c6ad7cbc8237830e77376634b3731622eaf30d92e22a3886ff109279d9830dac727afb94a83ee6d8360cbdfa2cc0640")[..]
+		);
+	}
+
+	#[test]
+	fn scrypt_rejects_invalid_params() {
+		let mut out = [0u8; 32];
+		assert!(Scrypt { n: 1, r: 8, p: 1 }.derive(b"password", b"salt", &mut out).is_err());
+		assert!(Scrypt { n: 1024, r: 0, p: 1 }.derive(b"password", b"salt", &mut out).is_err());
+	}
+
+	#[test]
+	fn kdf_is_object_safe() {
+		let kdfs: Vec<Box<dyn Kdf>> = vec![Box::new(Pbkdf2 { iterations: 2 }), Box::new(Scrypt { n: 16, r: 1, p: 1 })];
+		for kdf in kdfs {
+			let mut out = [0u8; 32];
+			kdf.derive(b"password", b"salt", &mut out).unwrap();
+			assert_ne!(out, [0u8; 32]);
+		}
+	}
+
+	#[cfg(feature = "argon2")]
+	#[test]
+	fn argon2id_reference_vector() {
+		let mut out = [0u8; 32];
+		Argon2id { mem_cost: 256, time_cost: 2, lanes: 1 }.derive(b"password", b"somesalt", &mut out).unwrap();
+		assert_eq!(out, hex!("9dfeb910e80bad0311fee20f9c0e2b12c17987b4cac90c2ef54d5b3021c68bfe"));
+	}
+}
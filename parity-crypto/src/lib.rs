@@ -16,14 +16,18 @@
 
 //! Crypto utils used by ethstore and network.
 
+pub mod aead;
 pub mod aes;
 pub mod digest;
 pub mod error;
 pub mod hmac;
+pub mod kdf;
 pub mod pbkdf2;
 #[cfg(feature = "publickey")]
 pub mod publickey;
 pub mod scrypt;
+pub mod secret;
+pub mod stream;
 
 pub use crate::error::Error;
 
@@ -56,12 +60,23 @@ where
 	}
 }
 
-pub fn derive_key_iterations(password: &[u8], salt: &[u8], c: u32) -> (Vec<u8>, Vec<u8>) {
-	let mut derived_key = [0u8; KEY_LENGTH];
-	pbkdf2::sha256(c, pbkdf2::Salt(salt), pbkdf2::Secret(password), &mut derived_key);
-	let derived_right_bits = &derived_key[0..KEY_LENGTH_AES];
-	let derived_left_bits = &derived_key[KEY_LENGTH_AES..KEY_LENGTH];
-	(derived_right_bits.to_vec(), derived_left_bits.to_vec())
+/// Derived key halves, zeroed on drop.
+pub type DerivedKeyHalves = (secret::Secret<[u8; KEY_LENGTH_AES]>, secret::Secret<[u8; KEY_LENGTH_AES]>);
+
+pub fn derive_key_iterations(password: &[u8], salt: &[u8], c: u32) -> DerivedKeyHalves {
+	let mut derived_key = secret::Secret::new([0u8; KEY_LENGTH]);
+	pbkdf2::sha256(c, pbkdf2::Salt(salt), pbkdf2::Secret(password), derived_key.expose_mut());
+	split_derived_key(&derived_key)
+}
+
+/// Splits a derived key into its right and left bits, without copying them outside of `Secret`s.
+fn split_derived_key(derived_key: &secret::Secret<[u8; KEY_LENGTH]>) -> DerivedKeyHalves {
+	let derived_key = derived_key.expose();
+	let mut derived_right_bits = secret::Secret::new([0u8; KEY_LENGTH_AES]);
+	let mut derived_left_bits = secret::Secret::new([0u8; KEY_LENGTH_AES]);
+	derived_right_bits.expose_mut().copy_from_slice(&derived_key[0..KEY_LENGTH_AES]);
+	derived_left_bits.expose_mut().copy_from_slice(&derived_key[KEY_LENGTH_AES..KEY_LENGTH]);
+	(derived_right_bits, derived_left_bits)
 }
 
 pub fn derive_mac(derived_left_bits: &[u8], cipher_text: &[u8]) -> Vec<u8> {
@@ -17,7 +17,7 @@
 //! Functions for ECIES scheme encryption and decryption
 
 use super::{ecdh, Error, Generator, Public, Random, Secret};
-use crate::{aes, digest, hmac, is_equal};
+use crate::{aes, digest, hmac, is_equal, secret::Secret as KeyMaterial};
 use ethereum_types::H128;
 
 const ENC_VERSION: u8 = 0x04;
@@ -29,8 +29,9 @@ const ENC_VERSION: u8 = 0x04;
 pub fn encrypt(public: &Public, auth_data: &[u8], plain: &[u8]) -> Result<Vec<u8>, Error> {
 	let r = Random.generate()?;
 	let z = ecdh::agree(r.secret(), public)?;
-	let mut key = [0u8; 32];
-	kdf(&z, &[0u8; 0], &mut key);
+	let mut key = KeyMaterial::new([0u8; 32]);
+	kdf(&z, &[0u8; 0], key.expose_mut());
+	let key = key.expose();
 
 	let ekey = &key[0..16];
 	let mkey = hmac::SigKey::sha256(&digest::sha256(&key[16..32]));
@@ -70,8 +71,9 @@ pub fn decrypt(secret: &Secret, auth_data: &[u8], encrypted: &[u8]) -> Result<Ve
 	let e = &encrypted[1..];
 	let p = Public::from_slice(&e[0..64]);
 	let z = ecdh::agree(secret, &p)?;
-	let mut key = [0u8; 32];
-	kdf(&z, &[0u8; 0], &mut key);
+	let mut key = KeyMaterial::new([0u8; 32]);
+	kdf(&z, &[0u8; 0], key.expose_mut());
+	let key = key.expose();
 
 	let ekey = &key[0..16];
 	let mkey = hmac::SigKey::sha256(&digest::sha256(&key[16..32]));
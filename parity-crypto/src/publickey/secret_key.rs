@@ -17,6 +17,7 @@
 //! Secret key implementation
 
 use super::{Error, SECP256K1};
+use crate::secret::Secret as KeyMaterial;
 use ethereum_types::H256;
 use secp256k1::constants::SECRET_KEY_SIZE as SECP256K1_SECRET_KEY_SIZE;
 use secp256k1::key;
@@ -24,35 +25,35 @@ use std::convert::TryFrom;
 use std::fmt;
 use std::ops::Deref;
 use std::str::FromStr;
-use zeroize::Zeroize;
 
 /// Represents secret key
 #[derive(Clone, PartialEq, Eq)]
 pub struct Secret {
-	inner: H256,
-}
-
-impl Drop for Secret {
-	fn drop(&mut self) {
-		self.inner.0.zeroize()
-	}
+	inner: KeyMaterial<H256>,
 }
 
 impl fmt::LowerHex for Secret {
 	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-		self.inner.fmt(fmt)
+		self.inner.expose().fmt(fmt)
 	}
 }
 
 impl fmt::Debug for Secret {
 	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-		self.inner.fmt(fmt)
+		fmt::Debug::fmt(&self.inner, fmt)
 	}
 }
 
 impl fmt::Display for Secret {
 	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-		write!(fmt, "Secret: 0x{:x}{:x}..{:x}{:x}", self.inner[0], self.inner[1], self.inner[30], self.inner[31])
+		write!(
+			fmt,
+			"Secret: 0x{:x}{:x}..{:x}{:x}",
+			self.inner.expose()[0],
+			self.inner.expose()[1],
+			self.inner.expose()[30],
+			self.inner.expose()[31]
+		)
 	}
 }
 
@@ -64,12 +65,12 @@ impl Secret {
 		}
 		let mut h = H256::zero();
 		h.as_bytes_mut().copy_from_slice(&key[0..32]);
-		Some(Secret { inner: h })
+		Some(Secret { inner: KeyMaterial::new(h) })
 	}
 
 	/// Creates zero key, which is invalid for crypto operations, but valid for math operation.
 	pub fn zero() -> Self {
-		Secret { inner: H256::zero() }
+		Secret { inner: KeyMaterial::new(H256::zero()) }
 	}
 
 	/// Imports and validates the key.
@@ -85,7 +86,7 @@ impl Secret {
 
 	/// Wrapper over hex conversion
 	pub fn to_hex(&self) -> String {
-		format!("{:x}", self.inner)
+		format!("{:x}", self.inner.expose())
 	}
 
 	/// Inplace add one secret key to another (scalar + scalar)
@@ -221,7 +222,7 @@ impl FromStr for Secret {
 
 impl From<[u8; 32]> for Secret {
 	fn from(k: [u8; 32]) -> Self {
-		Secret { inner: H256(k) }
+		Secret { inner: KeyMaterial::new(H256(k)) }
 	}
 }
 
@@ -251,7 +252,7 @@ impl Deref for Secret {
 	type Target = H256;
 
 	fn deref(&self) -> &Self::Target {
-		&self.inner
+		self.inner.expose()
 	}
 }
 
@@ -273,6 +274,12 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn debug_does_not_print_the_key() {
+		let secret = Secret::from_str("a100df7a048e50ed308ea696dc600215098141cb391e9527329df289f9383f65").unwrap();
+		assert_eq!(format!("{:?}", secret), "Secret(***)");
+	}
+
 	#[test]
 	fn secret_inversion_is_reversible_with_inversion() {
 		let secret = Random.generate().unwrap().secret().clone();
@@ -14,30 +14,21 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-use super::{KEY_LENGTH, KEY_LENGTH_AES};
-use crate::error::ScryptError;
-use scrypt::{scrypt, ScryptParams};
+use super::{split_derived_key, DerivedKeyHalves, KEY_LENGTH};
+use crate::error::{self, ScryptError};
+use crate::kdf::{Kdf, Scrypt};
+use crate::secret::Secret;
 
 #[cfg(test)]
 use std::io::Error;
 
-pub fn derive_key(pass: &[u8], salt: &[u8], n: u32, p: u32, r: u32) -> Result<(Vec<u8>, Vec<u8>), ScryptError> {
-	// sanity checks
-	let log_n = (32 - n.leading_zeros() - 1) as u8;
-	if log_n as u32 >= r * 16 {
-		return Err(ScryptError::InvalidN);
-	}
-
-	if p as u64 > ((u32::max_value() as u64 - 1) * 32) / (128 * (r as u64)) {
-		return Err(ScryptError::InvalidP);
-	}
-
-	let mut derived_key = vec![0u8; KEY_LENGTH];
-	let scrypt_params = ScryptParams::new(log_n, r, p)?;
-	scrypt(pass, salt, &scrypt_params, &mut derived_key)?;
-	let derived_right_bits = &derived_key[0..KEY_LENGTH_AES];
-	let derived_left_bits = &derived_key[KEY_LENGTH_AES..KEY_LENGTH];
-	Ok((derived_right_bits.to_vec(), derived_left_bits.to_vec()))
+pub fn derive_key(pass: &[u8], salt: &[u8], n: u32, p: u32, r: u32) -> Result<DerivedKeyHalves, ScryptError> {
+	let mut derived_key = Secret::new([0u8; KEY_LENGTH]);
+	Scrypt { n, r, p }.derive(pass, salt, derived_key.expose_mut()).map_err(|err| match err {
+		error::Error::Scrypt(err) => err,
+		_ => unreachable!("scrypt only fails with scrypt errors; qed"),
+	})?;
+	Ok(split_derived_key(&derived_key))
 }
 
 // test is build from previous crypto lib behaviour, values may be incorrect
@@ -55,10 +46,10 @@ pub fn test_derive() -> Result<(), Error> {
 	let l2 = [253, 123, 132, 12, 188, 89, 196, 2, 107, 224, 239, 231, 135, 177, 125, 62];
 
 	let (l, r) = derive_key(&pass[..], &salt, 262, 1, 8).unwrap();
-	assert!(l == r1);
-	assert!(r == l1);
+	assert!(*l.expose() == r1);
+	assert!(*r.expose() == l1);
 	let (l, r) = derive_key(&pass[..], &salt, 144, 4, 4).unwrap();
-	assert!(l == r2);
-	assert!(r == l2);
+	assert!(*l.expose() == r2);
+	assert!(*r.expose() == l2);
 	Ok(())
 }
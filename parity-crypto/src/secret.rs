@@ -0,0 +1,114 @@
+// Copyright 2015-2019 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Wrapper for key material.
+
+use std::fmt;
+
+use subtle::ConstantTimeEq;
+use zeroize::Zeroize;
+
+/// Key material that is zeroed on drop.
+///
+/// Equality is checked in constant time and `Debug` doesn't print the contents;
+/// use `expose` to access the bytes. Usually wraps a `[u8; N]`, but any buffer
+/// that can be mutably borrowed as bytes works, e.g. `Box<[u8]>` for keys of varying length.
+/// The buffer must not reallocate, a `Vec<u8>` could leave copies of the secret behind.
+#[derive(Clone)]
+pub struct Secret<T: AsMut<[u8]>>(T);
+
+impl<T: AsMut<[u8]>> Secret<T> {
+	/// Takes ownership of the key material.
+	pub fn new(inner: T) -> Self {
+		Secret(inner)
+	}
+
+	/// Borrows the key material.
+	pub fn expose(&self) -> &T {
+		&self.0
+	}
+
+	/// Mutably borrows the key material.
+	pub fn expose_mut(&mut self) -> &mut T {
+		&mut self.0
+	}
+}
+
+impl<T: AsMut<[u8]> + Default> Default for Secret<T> {
+	fn default() -> Self {
+		Secret(T::default())
+	}
+}
+
+impl<T: AsMut<[u8]>> From<T> for Secret<T> {
+	fn from(inner: T) -> Self {
+		Secret(inner)
+	}
+}
+
+impl From<&[u8]> for Secret<Box<[u8]>> {
+	fn from(data: &[u8]) -> Self {
+		Secret(data.into())
+	}
+}
+
+impl<T: AsMut<[u8]>> Drop for Secret<T> {
+	fn drop(&mut self) {
+		self.0.as_mut().zeroize()
+	}
+}
+
+impl<T: AsMut<[u8]> + AsRef<[u8]>> PartialEq for Secret<T> {
+	fn eq(&self, other: &Self) -> bool {
+		self.0.as_ref().ct_eq(other.0.as_ref()).into()
+	}
+}
+
+impl<T: AsMut<[u8]> + AsRef<[u8]>> Eq for Secret<T> {}
+
+impl<T: AsMut<[u8]>> fmt::Debug for Secret<T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "Secret(***)")
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::Secret;
+	use std::{mem::ManuallyDrop, ptr};
+
+	#[test]
+	fn should_zero_on_drop() {
+		let mut secret = ManuallyDrop::new(Secret::new([0x42u8; 32]));
+		let inner: *const [u8; 32] = secret.expose();
+		unsafe {
+			ptr::drop_in_place(&mut *secret);
+			assert_eq!(*inner, [0u8; 32]);
+		}
+	}
+
+	#[test]
+	fn should_compare_contents() {
+		assert_eq!(Secret::new([1u8, 2, 3]), Secret::new([1u8, 2, 3]));
+		assert_ne!(Secret::new([1u8, 2, 3]), Secret::new([1u8, 2, 4]));
+		assert_ne!(Secret::<Box<[u8]>>::from(&[1u8, 2][..]), Secret::from(&[1u8, 2, 3][..]));
+	}
+
+	#[test]
+	fn should_not_leak_in_debug() {
+		assert_eq!(format!("{:?}", Secret::new(*b"sikrit")), "Secret(***)");
+	}
+}
@@ -0,0 +1,293 @@
+// Copyright 2015-2019 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Streaming authenticated encryption over `Read` and `Write`.
+//!
+//! The plain text is split in chunks of `CHUNK_SIZE` bytes, each one encrypted with AES-256-GCM
+//! and followed by its authentication tag. The stream starts with a random nonce prefix; the nonce
+//! of a chunk is the prefix, the chunk index as a 32 bits big-endian counter and a byte set to 1
+//! for the last chunk only. Reordered, dropped or truncated chunks therefore fail to decrypt.
+
+use std::io::{self, Read, Write};
+
+use rand::{rngs::OsRng, RngCore};
+
+use crate::aead::{Aes256Gcm, KEY_LENGTH, NONCE_LENGTH, TAG_LENGTH};
+use crate::error::SymmError;
+
+/// Size of the plain text chunks.
+pub const CHUNK_SIZE: usize = 64 * 1024;
+/// Length of the nonce prefix starting the stream.
+pub const PREFIX_LENGTH: usize = NONCE_LENGTH - 5;
+
+const SEALED_CHUNK_SIZE: usize = CHUNK_SIZE + TAG_LENGTH;
+
+struct ChunkNonces {
+	prefix: [u8; PREFIX_LENGTH],
+	index: u32,
+	exhausted: bool,
+}
+
+impl ChunkNonces {
+	fn next(&mut self, last: bool) -> Result<[u8; NONCE_LENGTH], SymmError> {
+		if self.exhausted {
+			return Err(SymmError::nonce_exhausted());
+		}
+		let mut nonce = [0u8; NONCE_LENGTH];
+		nonce[..PREFIX_LENGTH].copy_from_slice(&self.prefix);
+		nonce[PREFIX_LENGTH..NONCE_LENGTH - 1].copy_from_slice(&self.index.to_be_bytes());
+		nonce[NONCE_LENGTH - 1] = last as u8;
+		match self.index.checked_add(1) {
+			Some(index) => self.index = index,
+			None => self.exhausted = true,
+		}
+		Ok(nonce)
+	}
+}
+
+fn invalid_data(err: SymmError) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+/// Encrypts everything written to it into the inner writer.
+///
+/// `finish` must be called once everything has been written: it writes the last chunk, without
+/// which the stream can't be decrypted.
+pub struct EncryptingWriter<W: Write> {
+	inner: W,
+	cipher: Aes256Gcm,
+	nonces: ChunkNonces,
+	buffer: Vec<u8>,
+}
+
+impl<W: Write> EncryptingWriter<W> {
+	/// New writer with a random nonce prefix, which is written right away.
+	pub fn new(mut inner: W, key: &[u8; KEY_LENGTH]) -> io::Result<Self> {
+		let mut prefix = [0u8; PREFIX_LENGTH];
+		OsRng.fill_bytes(&mut prefix);
+		inner.write_all(&prefix)?;
+		Ok(EncryptingWriter {
+			inner,
+			cipher: Aes256Gcm::new(key),
+			nonces: ChunkNonces { prefix, index: 0, exhausted: false },
+			buffer: Vec::with_capacity(CHUNK_SIZE),
+		})
+	}
+
+	/// Write the last chunk and return the inner writer.
+	pub fn finish(mut self) -> io::Result<W> {
+		self.write_chunk(true)?;
+		self.inner.flush()?;
+		Ok(self.inner)
+	}
+
+	fn write_chunk(&mut self, last: bool) -> io::Result<()> {
+		let nonce = self.nonces.next(last).map_err(invalid_data)?;
+		let sealed = self.cipher.encrypt(&nonce, &self.buffer, &[]).map_err(invalid_data)?;
+		self.inner.write_all(&sealed)?;
+		self.buffer.clear();
+		Ok(())
+	}
+}
+
+impl<W: Write> Write for EncryptingWriter<W> {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		// A full chunk is only written once more data arrives, since the last chunk may be full.
+		if self.buffer.len() == CHUNK_SIZE && !buf.is_empty() {
+			self.write_chunk(false)?;
+		}
+		let len = buf.len().min(CHUNK_SIZE - self.buffer.len());
+		self.buffer.extend_from_slice(&buf[..len]);
+		Ok(len)
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		self.inner.flush()
+	}
+}
+
+/// Decrypts a stream written by `EncryptingWriter`.
+///
+/// Reading fails with `io::ErrorKind::InvalidData` if the stream has been tampered with,
+/// truncated, or was encrypted with another key. Data is only returned once its chunk has
+/// been authenticated.
+pub struct DecryptingReader<R: Read> {
+	inner: R,
+	cipher: Aes256Gcm,
+	nonces: ChunkNonces,
+	// Sealed data read ahead, one byte more than a chunk tells that it isn't the last one.
+	sealed: Vec<u8>,
+	plain: Vec<u8>,
+	position: usize,
+	finished: bool,
+}
+
+impl<R: Read> DecryptingReader<R> {
+	/// New reader, reading the nonce prefix right away.
+	pub fn new(mut inner: R, key: &[u8; KEY_LENGTH]) -> io::Result<Self> {
+		let mut prefix = [0u8; PREFIX_LENGTH];
+		inner.read_exact(&mut prefix)?;
+		Ok(DecryptingReader {
+			inner,
+			cipher: Aes256Gcm::new(key),
+			nonces: ChunkNonces { prefix, index: 0, exhausted: false },
+			sealed: Vec::with_capacity(SEALED_CHUNK_SIZE + 1),
+			plain: Vec::new(),
+			position: 0,
+			finished: false,
+		})
+	}
+
+	/// Return the inner reader.
+	pub fn into_inner(self) -> R {
+		self.inner
+	}
+
+	fn read_chunk(&mut self) -> io::Result<()> {
+		while self.sealed.len() <= SEALED_CHUNK_SIZE {
+			let start = self.sealed.len();
+			self.sealed.resize(SEALED_CHUNK_SIZE + 1, 0);
+			let read = match self.inner.read(&mut self.sealed[start..]) {
+				Ok(read) => read,
+				Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {
+					self.sealed.truncate(start);
+					continue;
+				}
+				Err(e) => {
+					self.sealed.truncate(start);
+					return Err(e);
+				}
+			};
+			self.sealed.truncate(start + read);
+			if read == 0 {
+				break;
+			}
+		}
+
+		let last = self.sealed.len() <= SEALED_CHUNK_SIZE;
+		let len = self.sealed.len().min(SEALED_CHUNK_SIZE);
+		let nonce = self.nonces.next(last).map_err(invalid_data)?;
+		self.plain = self.cipher.decrypt(&nonce, &self.sealed[..len], &[]).map_err(invalid_data)?;
+		self.position = 0;
+		self.sealed.drain(..len);
+		self.finished = last;
+		Ok(())
+	}
+}
+
+impl<R: Read> Read for DecryptingReader<R> {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		while self.position == self.plain.len() {
+			if self.finished || buf.is_empty() {
+				return Ok(0);
+			}
+			self.read_chunk()?;
+		}
+		let len = buf.len().min(self.plain.len() - self.position);
+		buf[..len].copy_from_slice(&self.plain[self.position..self.position + len]);
+		self.position += len;
+		Ok(len)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const KEY: [u8; KEY_LENGTH] = [7; KEY_LENGTH];
+
+	fn encrypt(plain: &[u8]) -> Vec<u8> {
+		let mut writer = EncryptingWriter::new(Vec::new(), &KEY).unwrap();
+		// odd sized writes, to cross chunk boundaries
+		for part in plain.chunks(1000) {
+			writer.write_all(part).unwrap();
+		}
+		writer.finish().unwrap()
+	}
+
+	fn decrypt(sealed: &[u8], key: &[u8; KEY_LENGTH]) -> io::Result<Vec<u8>> {
+		let mut plain = Vec::new();
+		DecryptingReader::new(sealed, key)?.read_to_end(&mut plain)?;
+		Ok(plain)
+	}
+
+	#[test]
+	fn should_roundtrip_any_length() {
+		for &len in &[0, 1, CHUNK_SIZE - 1, CHUNK_SIZE, CHUNK_SIZE + 1, 3 * CHUNK_SIZE + 17] {
+			let plain = (0..len).map(|i| i as u8).collect::<Vec<_>>();
+			let sealed = encrypt(&plain);
+			let chunks = len / CHUNK_SIZE + if len % CHUNK_SIZE == 0 && len > 0 { 0 } else { 1 };
+			assert_eq!(sealed.len(), PREFIX_LENGTH + len + chunks * TAG_LENGTH);
+			assert_eq!(decrypt(&sealed, &KEY).unwrap(), plain);
+		}
+	}
+
+	#[test]
+	fn should_reject_truncated_or_tampered_streams() {
+		let plain = vec![1u8; 2 * CHUNK_SIZE + 5];
+		let sealed = encrypt(&plain);
+
+		// dropping the last chunk leaves a valid looking, but not final, chunk
+		let truncated = &sealed[..PREFIX_LENGTH + 2 * SEALED_CHUNK_SIZE];
+		assert_eq!(decrypt(truncated, &KEY).unwrap_err().kind(), io::ErrorKind::InvalidData);
+		assert!(decrypt(&sealed[..sealed.len() - 1], &KEY).is_err());
+
+		let mut tampered = sealed.clone();
+		tampered[PREFIX_LENGTH + CHUNK_SIZE + 3] ^= 1;
+		assert!(decrypt(&tampered, &KEY).is_err());
+
+		assert!(decrypt(&sealed, &[8; KEY_LENGTH]).is_err());
+		assert!(decrypt(&sealed[..3], &KEY).is_err());
+	}
+
+	#[test]
+	fn should_only_return_authenticated_data() {
+		let plain = vec![1u8; CHUNK_SIZE + 5];
+		let mut sealed = encrypt(&plain);
+		let len = sealed.len();
+		sealed[len - 1] ^= 1;
+
+		let mut reader = DecryptingReader::new(&sealed[..], &KEY).unwrap();
+		let mut buf = vec![0u8; CHUNK_SIZE];
+		reader.read_exact(&mut buf).unwrap();
+		assert!(reader.read(&mut buf).is_err());
+	}
+
+	#[test]
+	fn should_retry_interrupted_reads() {
+		// interrupts every other read and returns short reads otherwise
+		struct Interrupting<'a>(&'a [u8], bool);
+
+		impl Read for Interrupting<'_> {
+			fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+				self.1 = !self.1;
+				if self.1 {
+					return Err(io::ErrorKind::Interrupted.into());
+				}
+				let len = buf.len().min(self.0.len()).min(100);
+				buf[..len].copy_from_slice(&self.0[..len]);
+				self.0 = &self.0[len..];
+				Ok(len)
+			}
+		}
+
+		let plain = (0..2 * CHUNK_SIZE + 5).map(|i| i as u8).collect::<Vec<_>>();
+		let sealed = encrypt(&plain);
+		let mut decrypted = Vec::new();
+		DecryptingReader::new(Interrupting(&sealed, false), &KEY).unwrap().read_to_end(&mut decrypted).unwrap();
+		assert_eq!(decrypted, plain);
+	}
+}
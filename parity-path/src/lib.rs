@@ -15,8 +15,13 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 //! Path utilities
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io::{self, Write};
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use home::home_dir;
 
@@ -57,6 +62,183 @@ pub fn config_path_with(name: &str, then: &str) -> PathBuf {
 	path
 }
 
+/// Get the data directory of application `name`, following the platform conventions:
+///
+/// - `$XDG_DATA_HOME/<name>` or `~/.local/share/<name>` on Linux and other unix systems
+/// - `~/Library/Application Support/<Name>` on Mac Os
+/// - `%APPDATA%\<Name>` on Windows
+///
+/// `name` should be capitalized, it is lowercased where the platform expects it.
+/// Returns `None` if the home directory can't be determined.
+pub fn data_dir(name: &str) -> Option<PathBuf> {
+	platform_dir(name, "XDG_DATA_HOME", &[".local", "share"], &["Library", "Application Support"], "APPDATA")
+}
+
+/// Get the configuration directory of application `name`:
+///
+/// - `$XDG_CONFIG_HOME/<name>` or `~/.config/<name>` on Linux and other unix systems
+/// - `~/Library/Application Support/<Name>` on Mac Os
+/// - `%APPDATA%\<Name>` on Windows
+pub fn config_dir(name: &str) -> Option<PathBuf> {
+	platform_dir(name, "XDG_CONFIG_HOME", &[".config"], &["Library", "Application Support"], "APPDATA")
+}
+
+/// Get the cache directory of application `name`:
+///
+/// - `$XDG_CACHE_HOME/<name>` or `~/.cache/<name>` on Linux and other unix systems
+/// - `~/Library/Caches/<Name>` on Mac Os
+/// - `%LOCALAPPDATA%\<Name>` on Windows
+pub fn cache_dir(name: &str) -> Option<PathBuf> {
+	platform_dir(name, "XDG_CACHE_HOME", &[".cache"], &["Library", "Caches"], "LOCALAPPDATA")
+}
+
+#[cfg(target_os = "macos")]
+fn platform_dir(
+	name: &str,
+	_xdg_var: &str,
+	_xdg_default: &[&str],
+	macos: &[&str],
+	_windows_var: &str,
+) -> Option<PathBuf> {
+	let mut path = home_dir()?;
+	path.extend(macos);
+	path.push(name);
+	Some(path)
+}
+
+#[cfg(windows)]
+fn platform_dir(
+	name: &str,
+	_xdg_var: &str,
+	_xdg_default: &[&str],
+	_macos: &[&str],
+	windows_var: &str,
+) -> Option<PathBuf> {
+	let mut path = match absolute_env(windows_var) {
+		Some(path) => path,
+		None => {
+			let mut home = home_dir()?;
+			home.push("AppData");
+			home.push(if windows_var == "LOCALAPPDATA" { "Local" } else { "Roaming" });
+			home
+		}
+	};
+	path.push(name);
+	Some(path)
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn platform_dir(
+	name: &str,
+	xdg_var: &str,
+	xdg_default: &[&str],
+	_macos: &[&str],
+	_windows_var: &str,
+) -> Option<PathBuf> {
+	let mut path = match absolute_env(xdg_var) {
+		Some(path) => path,
+		None => {
+			let mut home = home_dir()?;
+			home.extend(xdg_default);
+			home
+		}
+	};
+	path.push(name.to_lowercase());
+	Some(path)
+}
+
+/// Reads a path from the environment, ignoring relative paths as the XDG specification requires.
+#[allow(dead_code)]
+fn absolute_env(var: &str) -> Option<PathBuf> {
+	env::var_os(var).map(PathBuf::from).filter(|path| path.is_absolute())
+}
+
+/// Get the directory of `chain` inside the application directory `base`, i.e. `<base>/chains/<chain>`.
+pub fn chain_path(base: &Path, chain: &str) -> PathBuf {
+	let mut path = base.join("chains");
+	path.push(chain);
+	path
+}
+
+/// Get the database directory of `chain` inside the application directory `base`, i.e. `<base>/chains/<chain>/db`.
+pub fn chain_db_path(base: &Path, chain: &str) -> PathBuf {
+	let mut path = chain_path(base, chain);
+	path.push("db");
+	path
+}
+
+/// Get the key store directory of `chain` inside the application directory `base`, i.e. `<base>/keys/<chain>`.
+pub fn keys_path(base: &Path, chain: &str) -> PathBuf {
+	let mut path = base.join("keys");
+	path.push(chain);
+	path
+}
+
+/// Replaces the contents of the file at `path` with `bytes`, so that after a crash the file
+/// holds either the old or the new contents, never a mix.
+///
+/// The data is written to a new temporary file in the same directory, flushed to disk and renamed
+/// over `path`. The permissions of the replaced file are kept. On unix the directory is synced too,
+/// to persist the rename. Concurrent writes to the same path don't interfere, the last rename wins.
+pub fn atomic_write<P: AsRef<Path>>(path: P, bytes: &[u8]) -> io::Result<()> {
+	let path = path.as_ref();
+	let file_name = path
+		.file_name()
+		.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "atomic_write path has no file name"))?;
+	let dir = match path.parent() {
+		Some(dir) if !dir.as_os_str().is_empty() => dir,
+		_ => Path::new("."),
+	};
+
+	let (temp_path, file) = create_temp(dir, file_name)?;
+	let result = write_synced(file, path, bytes).and_then(|_| fs::rename(&temp_path, path));
+	if result.is_err() {
+		let _ = fs::remove_file(&temp_path);
+		return result;
+	}
+
+	sync_dir(dir)
+}
+
+/// Creates a temporary file next to `file_name` in `dir`, with a name no other writer uses.
+fn create_temp(dir: &Path, file_name: &OsStr) -> io::Result<(PathBuf, fs::File)> {
+	static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+	loop {
+		let mut temp_name = OsString::from(".");
+		temp_name.push(file_name);
+		temp_name.push(format!(".tmp{}.{}", std::process::id(), COUNTER.fetch_add(1, Ordering::Relaxed)));
+		let temp_path = dir.join(temp_name);
+		match fs::OpenOptions::new().write(true).create_new(true).open(&temp_path) {
+			Ok(file) => return Ok((temp_path, file)),
+			// left behind by a crashed process with the same id
+			Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+			Err(e) => return Err(e),
+		}
+	}
+}
+
+/// Writes `bytes` to `file` with the permissions of `original`, if it exists.
+fn write_synced(mut file: fs::File, original: &Path, bytes: &[u8]) -> io::Result<()> {
+	match fs::metadata(original) {
+		Ok(metadata) => file.set_permissions(metadata.permissions())?,
+		Err(ref e) if e.kind() == io::ErrorKind::NotFound => (),
+		Err(e) => return Err(e),
+	}
+	file.write_all(bytes)?;
+	file.sync_all()
+}
+
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> {
+	fs::File::open(dir)?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> io::Result<()> {
+	Ok(())
+}
+
 /// Default ethereum paths
 pub mod ethereum {
 	use std::path::PathBuf;
@@ -103,3 +285,86 @@ pub fn restrict_permissions_owner(_file_path: &Path, _write: bool, _executable:
 	//TODO: implement me
 	Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use tempdir::TempDir;
+
+	#[test]
+	fn chain_paths() {
+		let base = Path::new("base");
+		assert_eq!(chain_path(base, "foundation"), Path::new("base/chains/foundation"));
+		assert_eq!(chain_db_path(base, "foundation"), Path::new("base/chains/foundation/db"));
+		assert_eq!(keys_path(base, "foundation"), Path::new("base/keys/foundation"));
+	}
+
+	#[test]
+	fn atomic_write_replaces_contents() {
+		let dir = TempDir::new("parity-path").unwrap();
+		let path = dir.path().join("config.toml");
+
+		atomic_write(&path, b"first").unwrap();
+		assert_eq!(fs::read(&path).unwrap(), b"first");
+		atomic_write(&path, b"second").unwrap();
+		assert_eq!(fs::read(&path).unwrap(), b"second");
+
+		// No temporary files are left behind.
+		assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+	}
+
+	#[test]
+	fn atomic_write_from_many_threads() {
+		let dir = TempDir::new("parity-path").unwrap();
+		let path = dir.path().join("config.toml");
+
+		let threads: Vec<_> = (0..8u8)
+			.map(|i| {
+				let path = path.clone();
+				std::thread::spawn(move || {
+					for _ in 0..20 {
+						atomic_write(&path, &[i; 1024]).unwrap();
+					}
+				})
+			})
+			.collect();
+		for thread in threads {
+			thread.join().unwrap();
+		}
+
+		let contents = fs::read(&path).unwrap();
+		assert_eq!(contents.len(), 1024);
+		assert!(contents.iter().all(|&byte| byte == contents[0]));
+		assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn atomic_write_keeps_permissions() {
+		use std::os::unix::fs::PermissionsExt;
+
+		let dir = TempDir::new("parity-path").unwrap();
+		let path = dir.path().join("key.json");
+		atomic_write(&path, b"first").unwrap();
+		restrict_permissions_owner(&path, true, false).unwrap();
+
+		atomic_write(&path, b"second").unwrap();
+		assert_eq!(fs::read(&path).unwrap(), b"second");
+		assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+	}
+
+	#[test]
+	fn atomic_write_fails_in_missing_directory() {
+		let dir = TempDir::new("parity-path").unwrap();
+		assert!(atomic_write(dir.path().join("missing").join("file"), b"data").is_err());
+		assert!(atomic_write(dir.path(), b"data").is_err());
+	}
+
+	#[cfg(not(any(target_os = "macos", windows)))]
+	#[test]
+	fn data_dir_is_lowercase_on_unix() {
+		let path = data_dir("Parity").unwrap();
+		assert!(path.ends_with("parity"));
+		assert!(path.is_absolute());
+	}
+}
@@ -24,7 +24,7 @@ use elastic_array::{
 	ElasticArray1024, ElasticArray128, ElasticArray16, ElasticArray2, ElasticArray2048, ElasticArray256,
 	ElasticArray32, ElasticArray36, ElasticArray4, ElasticArray512, ElasticArray64, ElasticArray8,
 };
-use ethereum_types::{Bloom, H128, H160, H256, H264, H32, H512, H520, H64, U128, U256, U512, U64};
+use ethereum_types::{Bloom, Public, Signature, H128, H160, H256, H264, H32, H512, H520, H64, U128, U256, U512, U64};
 use parking_lot::{Mutex, RwLock};
 
 #[cfg(not(feature = "std"))]
@@ -34,7 +34,7 @@ use core as std;
 malloc_size_of_is_0!(std::time::Instant);
 malloc_size_of_is_0!(std::time::Duration);
 
-malloc_size_of_is_0!(U64, U128, U256, U512, H32, H64, H128, H160, H256, H264, H512, H520, Bloom);
+malloc_size_of_is_0!(U64, U128, U256, U512, H32, H64, H128, H160, H256, H264, H512, H520, Bloom, Public, Signature);
 
 macro_rules! impl_elastic_array {
 	($name: ident, $dummy: ident, $size: expr) => {
@@ -23,7 +23,8 @@
 #[cfg(not(feature = "std"))]
 extern crate alloc;
 
-use malloc_size_of_derive as malloc_size_derive;
+// Allows the derive macro to refer to this crate from its own tests.
+extern crate self as parity_util_mem;
 
 cfg_if::cfg_if! {
 	if #[cfg(all(
@@ -72,12 +73,13 @@ pub mod impls;
 
 pub use allocators::MallocSizeOfExt;
 pub use malloc_size::{MallocSizeOf, MallocSizeOfOps};
-pub use malloc_size_derive::*;
+pub use parity_util_mem_derive::*;
 
 #[cfg(feature = "std")]
 #[cfg(test)]
 mod test {
-	use super::MallocSizeOfExt;
+	use super::{MallocSizeOf, MallocSizeOfExt};
+	use std::collections::{BTreeSet, BinaryHeap, LinkedList};
 	use std::sync::Arc;
 
 	#[test]
@@ -86,4 +88,51 @@ mod test {
 		let s = val.malloc_size_of();
 		assert!(s > 0);
 	}
+
+	#[test]
+	fn test_derive() {
+		#[derive(MallocSizeOf)]
+		struct Trivia {
+			name: String,
+			#[ignore_malloc_size_of = "shared with other structures"]
+			shared: Arc<Vec<u8>>,
+		}
+
+		#[derive(MallocSizeOf)]
+		enum Answer<T> {
+			Yes(Vec<T>),
+			No,
+		}
+
+		let trivia = Trivia { name: "a".repeat(1024), shared: Arc::new(vec![0; 4096]) };
+		assert!(trivia.malloc_size_of() >= 1024);
+		assert!(trivia.malloc_size_of() < 4096);
+		assert!(Answer::Yes(vec![0u64; 128]).malloc_size_of() >= 1024);
+		assert_eq!(Answer::<u64>::No.malloc_size_of(), 0);
+	}
+
+	#[test]
+	fn test_containers() {
+		let set: BTreeSet<Vec<u8>> = (0..16u8).map(|i| vec![i; 256]).collect();
+		assert!(set.malloc_size_of() >= 16 * 256);
+
+		let heap: BinaryHeap<String> = (0..8).map(|i| i.to_string().repeat(128)).collect();
+		assert!(heap.malloc_size_of() >= 8 * 128);
+
+		let list: LinkedList<Vec<u8>> = (0..4u8).map(|i| vec![i; 512]).collect();
+		assert!(list.malloc_size_of() >= 4 * 512);
+
+		let array = [vec![0u8; 1024], vec![1u8; 1024]];
+		assert!(array.malloc_size_of() >= 2048);
+	}
+
+	#[cfg(feature = "smallvec")]
+	#[test]
+	fn test_smallvec() {
+		let mut v: smallvec::SmallVec<[u8; 4]> = smallvec::SmallVec::new();
+		v.extend_from_slice(&[1, 2, 3, 4]);
+		assert_eq!(v.malloc_size_of(), 0);
+		v.extend_from_slice(&[0; 1024]);
+		assert!(v.malloc_size_of() >= 1028);
+	}
 }
@@ -22,7 +22,7 @@
 //!
 //! Suggested uses are as follows.
 //! - When possible, use the `MallocSizeOf` trait. (Deriving support is
-//!   provided by the `malloc_size_of_derive` crate.)
+//!   provided by the `parity-util-mem-derive` crate.)
 //! - If you need an additional synchronization argument, provide a function
 //!   that is like the standard trait method, but with the extra argument.
 //! - If you need multiple measurements for a type, provide a function named
@@ -222,6 +222,17 @@ pub mod inner_allocator_use {
 		}
 	}
 
+	#[cfg(feature = "smallvec")]
+	impl<A: smallvec::Array> MallocShallowSizeOf for smallvec::SmallVec<A> {
+		fn shallow_size_of(&self, ops: &mut MallocSizeOfOps) -> usize {
+			if self.spilled() {
+				unsafe { ops.malloc_size_of(self.as_ptr()) }
+			} else {
+				0
+			}
+		}
+	}
+
 	// currently this seems only fine with jemalloc
 	#[cfg(feature = "std")]
 	#[cfg(all(feature = "jemalloc-global", not(target_os = "windows")))]
@@ -485,6 +496,91 @@ where
 	}
 }
 
+impl<T> MallocShallowSizeOf for rstd::collections::BTreeSet<T> {
+	fn shallow_size_of(&self, ops: &mut MallocSizeOfOps) -> usize {
+		if ops.has_malloc_enclosing_size_of() {
+			// See implementation for HashSet how this works.
+			self.iter().next().map_or(0, |t| unsafe { ops.malloc_enclosing_size_of(t) })
+		} else {
+			// An estimate.
+			self.len() * (size_of::<T>() + size_of::<usize>())
+		}
+	}
+}
+
+impl<T: MallocSizeOf> MallocSizeOf for rstd::collections::BTreeSet<T> {
+	fn size_of(&self, ops: &mut MallocSizeOfOps) -> usize {
+		let mut n = self.shallow_size_of(ops);
+		for t in self.iter() {
+			n += t.size_of(ops);
+		}
+		n
+	}
+}
+
+impl<T> MallocShallowSizeOf for rstd::collections::BinaryHeap<T> {
+	fn shallow_size_of(&self, ops: &mut MallocSizeOfOps) -> usize {
+		if ops.has_malloc_enclosing_size_of() {
+			// The top element is an interior pointer to the backing vector.
+			self.peek().map_or(0, |t| unsafe { ops.malloc_enclosing_size_of(t) })
+		} else {
+			// An estimate.
+			self.capacity() * size_of::<T>()
+		}
+	}
+}
+
+impl<T: MallocSizeOf> MallocSizeOf for rstd::collections::BinaryHeap<T> {
+	fn size_of(&self, ops: &mut MallocSizeOfOps) -> usize {
+		let mut n = self.shallow_size_of(ops);
+		for t in self.iter() {
+			n += t.size_of(ops);
+		}
+		n
+	}
+}
+
+impl<T: MallocSizeOf> MallocSizeOf for rstd::collections::LinkedList<T> {
+	fn size_of(&self, ops: &mut MallocSizeOfOps) -> usize {
+		// Every element lives in its own node, the links are estimated.
+		let mut n = self.len() * 2 * size_of::<usize>();
+		for t in self.iter() {
+			n += size_of::<T>() + t.size_of(ops);
+		}
+		n
+	}
+}
+
+#[cfg(feature = "smallvec")]
+impl<A> MallocSizeOf for smallvec::SmallVec<A>
+where
+	A: smallvec::Array,
+	A::Item: MallocSizeOf,
+{
+	fn size_of(&self, ops: &mut MallocSizeOfOps) -> usize {
+		let mut n = self.shallow_size_of(ops);
+		for elem in self.iter() {
+			n += elem.size_of(ops);
+		}
+		n
+	}
+}
+
+macro_rules! impl_array {
+	($($n: expr),+) => {
+		$(
+			impl<T: MallocSizeOf> MallocSizeOf for [T; $n] {
+				fn size_of(&self, ops: &mut MallocSizeOfOps) -> usize {
+					self[..].size_of(ops)
+				}
+			}
+		)+
+	};
+}
+
+impl_array!(0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16);
+impl_array!(17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32);
+
 // PhantomData is always 0.
 impl<T> MallocSizeOf for rstd::marker::PhantomData<T> {
 	fn size_of(&self, _ops: &mut MallocSizeOfOps) -> usize {
@@ -52,6 +52,17 @@ impl<T> MallocShallowSizeOf for Vec<T> {
 	}
 }
 
+#[cfg(feature = "smallvec")]
+impl<A: smallvec::Array> MallocShallowSizeOf for smallvec::SmallVec<A> {
+	fn shallow_size_of(&self, _ops: &mut MallocSizeOfOps) -> usize {
+		if self.spilled() {
+			self.capacity() * size_of::<A::Item>()
+		} else {
+			0
+		}
+	}
+}
+
 impl<T> MallocUnconditionalShallowSizeOf for Arc<T> {
 	fn unconditional_shallow_size_of(&self, _ops: &mut MallocSizeOfOps) -> usize {
 		size_of::<T>()
@@ -18,7 +18,7 @@ use std::collections::hash_map::DefaultHasher;
 use std::hash::Hasher;
 
 use criterion::{criterion_group, criterion_main, Criterion};
-use plain_hasher::PlainHasher;
+use plain_hasher::{NoHasher, PlainHasher};
 
 fn bench_write_hasher(c: &mut Criterion) {
 	c.bench_function("write_plain_hasher", |b| {
@@ -30,6 +30,15 @@ fn bench_write_hasher(c: &mut Criterion) {
 			});
 		})
 	});
+	c.bench_function("write_no_hasher", |b| {
+		b.iter(|| {
+			(0..100u8).fold(NoHasher::default(), |mut old, new| {
+				let bb = [new; 32];
+				old.write(&bb);
+				old
+			});
+		})
+	});
 	c.bench_function("write_default_hasher", |b| {
 		b.iter(|| {
 			(0..100u8).fold(DefaultHasher::default(), |mut old, new| {
@@ -16,10 +16,15 @@
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use core::hash::Hasher;
+use core::fmt;
+use core::hash::{BuildHasher, Hasher};
+use core::marker::PhantomData;
 
 use crunchy::unroll;
 
+#[cfg(feature = "std")]
+use std::collections::{HashMap, HashSet};
+
 /// Hasher that just takes 8 bytes of the provided value.
 /// May only be used for keys which are 32 bytes.
 #[derive(Default)]
@@ -52,6 +57,106 @@ impl Hasher for PlainHasher {
 	}
 }
 
+/// Hasher for keys that are already uniformly distributed, e.g. hashes, random ids or public keys.
+///
+/// Integers are used as the hash value directly, byte strings are folded into 8 bytes by xor-ing
+/// every 8 bytes chunk. For 32 bytes keys this gives the same result as `PlainHasher`.
+#[derive(Default, Clone, Copy)]
+pub struct NoHasher {
+	hash: u64,
+}
+
+impl Hasher for NoHasher {
+	#[inline]
+	fn finish(&self) -> u64 {
+		self.hash
+	}
+
+	#[inline]
+	fn write(&mut self, bytes: &[u8]) {
+		let mut chunks = bytes.chunks_exact(8);
+		for chunk in &mut chunks {
+			let mut word = [0u8; 8];
+			word.copy_from_slice(chunk);
+			self.hash ^= u64::from_ne_bytes(word);
+		}
+		let rest = chunks.remainder();
+		if !rest.is_empty() {
+			let mut word = [0u8; 8];
+			word[..rest.len()].copy_from_slice(rest);
+			self.hash ^= u64::from_ne_bytes(word);
+		}
+	}
+
+	#[inline]
+	fn write_u8(&mut self, i: u8) {
+		self.hash ^= i as u64;
+	}
+
+	#[inline]
+	fn write_u16(&mut self, i: u16) {
+		self.hash ^= i as u64;
+	}
+
+	#[inline]
+	fn write_u32(&mut self, i: u32) {
+		self.hash ^= i as u64;
+	}
+
+	#[inline]
+	fn write_u64(&mut self, i: u64) {
+		self.hash ^= i;
+	}
+
+	#[inline]
+	fn write_usize(&mut self, i: usize) {
+		self.hash ^= i as u64;
+	}
+}
+
+/// `BuildHasher` creating `NoHasher`s for keys of type `K`.
+///
+/// `K` only documents the key type of the map: use it for keys that are already uniformly
+/// distributed like `u64` ids, `H160` addresses or `H256` hashes, never for user-controlled values.
+pub struct NoHashBuilder<K>(PhantomData<fn(K)>);
+
+impl<K> BuildHasher for NoHashBuilder<K> {
+	type Hasher = NoHasher;
+
+	#[inline]
+	fn build_hasher(&self) -> NoHasher {
+		NoHasher::default()
+	}
+}
+
+impl<K> Default for NoHashBuilder<K> {
+	fn default() -> Self {
+		NoHashBuilder(PhantomData)
+	}
+}
+
+impl<K> Clone for NoHashBuilder<K> {
+	fn clone(&self) -> Self {
+		*self
+	}
+}
+
+impl<K> Copy for NoHashBuilder<K> {}
+
+impl<K> fmt::Debug for NoHashBuilder<K> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str("NoHashBuilder")
+	}
+}
+
+/// `HashMap` using `NoHasher`.
+#[cfg(feature = "std")]
+pub type NoHashMap<K, V> = HashMap<K, V, NoHashBuilder<K>>;
+
+/// `HashSet` using `NoHasher`.
+#[cfg(feature = "std")]
+pub type NoHashSet<K> = HashSet<K, NoHashBuilder<K>>;
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -64,4 +169,63 @@ mod tests {
 		hasher.write(&bytes);
 		assert_eq!(hasher.prefix, 47);
 	}
+
+	#[test]
+	fn no_hasher_matches_plain_hasher() {
+		let mut bytes = [0u8; 32];
+		for (i, b) in bytes.iter_mut().enumerate() {
+			*b = (i * 7) as u8;
+		}
+		let mut plain = PlainHasher::default();
+		plain.write(&bytes);
+		let mut no_hash = NoHasher::default();
+		no_hash.write(&bytes);
+		assert_eq!(plain.finish(), no_hash.finish());
+	}
+
+	#[test]
+	fn no_hasher_uses_integers_directly() {
+		let builder = NoHashBuilder::<u64>::default();
+		let mut hasher = builder.build_hasher();
+		hasher.write_u64(0xdead_beef);
+		assert_eq!(hasher.finish(), 0xdead_beef);
+	}
+
+	#[test]
+	fn no_hasher_folds_short_keys() {
+		let mut hasher = NoHasher::default();
+		hasher.write(&[1u8; 20]);
+		assert_eq!(hasher.finish(), u64::from_ne_bytes([1, 1, 1, 1, 0, 0, 0, 0]));
+	}
+
+	#[cfg(feature = "std")]
+	#[test]
+	fn no_hash_map_works_with_fixed_hashes() {
+		// Hashed like `fixed_hash` types.
+		#[derive(PartialEq, Eq)]
+		struct H160([u8; 20]);
+
+		impl core::hash::Hash for H160 {
+			fn hash<H: Hasher>(&self, state: &mut H) {
+				state.write(&self.0);
+			}
+		}
+
+		let address = |i: u8| {
+			let mut bytes = [0u8; 20];
+			bytes[0] = i;
+			bytes[19] = i;
+			H160(bytes)
+		};
+
+		let mut map = NoHashMap::default();
+		for i in 0..100 {
+			map.insert(address(i), i);
+		}
+		assert_eq!(map.len(), 100);
+		assert_eq!(map.get(&address(42)), Some(&42));
+
+		let set: NoHashSet<u64> = (0..100).collect();
+		assert!(set.contains(&99));
+	}
 }
@@ -21,7 +21,7 @@ pub use core as core_;
 macro_rules! impl_uint_rlp {
 	($name: ident, $size: expr) => {
 		impl $crate::rlp::Encodable for $name {
-			fn rlp_append(&self, s: &mut $crate::rlp::RlpStream) {
+			fn rlp_append<B: $crate::rlp::RlpBuffer>(&self, s: &mut $crate::rlp::RlpStream<B>) {
 				let leading_empty_bytes = $size * 8 - (self.bits() + 7) / 8;
 				let mut buffer = [0u8; $size * 8];
 				self.to_big_endian(&mut buffer);
@@ -50,7 +50,7 @@ macro_rules! impl_uint_rlp {
 macro_rules! impl_fixed_hash_rlp {
 	($name: ident, $size: expr) => {
 		impl $crate::rlp::Encodable for $name {
-			fn rlp_append(&self, s: &mut $crate::rlp::RlpStream) {
+			fn rlp_append<B: $crate::rlp::RlpBuffer>(&self, s: &mut $crate::rlp::RlpStream<B>) {
 				s.encoder().encode_value(self.as_ref());
 			}
 		}
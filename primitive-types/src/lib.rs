@@ -10,7 +10,14 @@
 //!
 //! Those are uint types `U128`, `U256` and `U512`, and fixed hash types `H160`,
 //! `H256` and `H512`, with optional serde serialization, parity-scale-codec and
-//! rlp encoding.
+//! rlp encoding, and conversions from and to `num_bigint::BigUint` with the
+//! `num-bigint` feature.
+//!
+//! `ethereum-types` re-exports these types, so `ethereum_types::U256` and `primitive_types::U256`
+//! are the same type when both come from the same release. The uint types of different releases
+//! share their representation, little-endian `u64` words, so a value is moved between two
+//! releases by its words with `from_words` and `into_words`, e.g. `U256::from_words(other.0)`
+//! for a release predating `into_words`.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
@@ -93,8 +100,100 @@ mod rlp {
 	impl_fixed_hash_rlp!(H512, 64);
 }
 
+#[cfg(feature = "num-bigint")]
+mod num_bigint {
+	use super::*;
+	use ::num_bigint::BigUint;
+
+	macro_rules! impl_biguint_conversions {
+		($name: ident, $n_words: expr) => {
+			impl From<$name> for BigUint {
+				fn from(value: $name) -> BigUint {
+					BigUint::from(&value)
+				}
+			}
+
+			impl<'a> From<&'a $name> for BigUint {
+				fn from(value: &'a $name) -> BigUint {
+					let mut bytes = [0u8; $n_words * 8];
+					value.to_little_endian(&mut bytes);
+					BigUint::from_bytes_le(&bytes)
+				}
+			}
+
+			impl TryFrom<BigUint> for $name {
+				type Error = Error;
+
+				fn try_from(value: BigUint) -> Result<$name, Error> {
+					$name::try_from(&value)
+				}
+			}
+
+			impl<'a> TryFrom<&'a BigUint> for $name {
+				type Error = Error;
+
+				fn try_from(value: &'a BigUint) -> Result<$name, Error> {
+					let bytes = value.to_bytes_le();
+					if bytes.len() > $n_words * 8 {
+						return Err(Error::Overflow);
+					}
+					Ok($name::from_little_endian(&bytes))
+				}
+			}
+		};
+	}
+
+	impl_biguint_conversions!(U128, 2);
+	impl_biguint_conversions!(U256, 4);
+	impl_biguint_conversions!(U512, 8);
+
+	#[cfg(test)]
+	mod tests {
+		use super::*;
+
+		#[test]
+		fn biguint_roundtrip() {
+			let values = [U256::zero(), U256::one(), U256::from(u64::MAX) + 1, U256::MAX];
+			for value in &values {
+				let big = BigUint::from(value);
+				assert_eq!(big.to_str_radix(10), value.to_string());
+				assert_eq!(U256::try_from(big), Ok(*value));
+			}
+		}
+
+		#[test]
+		fn biguint_overflow() {
+			let big = BigUint::from(U256::MAX) + 1u32;
+			assert_eq!(U256::try_from(&big), Err(Error::Overflow));
+			assert_eq!(U512::try_from(&big), Ok(U512::from(U256::MAX) + 1));
+			assert_eq!(U128::try_from(BigUint::from(U256::one() << 128)), Err(Error::Overflow));
+		}
+	}
+}
+
 impl_fixed_hash_conversions!(H256, H160);
 
+macro_rules! impl_words {
+	($name: ident, $n_words: expr) => {
+		impl $name {
+			/// Creates the value from its little-endian `u64` words, such as those of the same type
+			/// from another release of this crate.
+			pub const fn from_words(words: [u64; $n_words]) -> Self {
+				$name(words)
+			}
+
+			/// Returns the little-endian `u64` words of the value, see `from_words`.
+			pub const fn into_words(self) -> [u64; $n_words] {
+				self.0
+			}
+		}
+	};
+}
+
+impl_words!(U128, 2);
+impl_words!(U256, 4);
+impl_words!(U512, 8);
+
 impl U256 {
 	/// Multiplies two 256-bit integers to produce full 512-bit integer
 	/// No overflow possible
@@ -0,0 +1,229 @@
+// Copyright 2015-2019 Parity Technologies
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Untyped view of rlp for debugging.
+//!
+//! Any rlp item can be turned into a [`Value`](enum.Value.html), printed as JSON with
+//! hex-encoded payloads, and parsed back again:
+//!
+//! ```rust
+//! use rlp::debug::Value;
+//!
+//! let data = vec![0xc9, 0x83, b'c', b'a', b't', 0xc4, 0x83, b'd', b'o', b'g'];
+//! let value = Value::from_bytes(&data).unwrap();
+//! assert_eq!(value.to_string(), r#"["0x636174", ["0x646f67"]]"#);
+//!
+//! let parsed: Value = r#"["0x636174", ["0x646f67"]]"#.parse().unwrap();
+//! assert_eq!(parsed.to_rlp(), data);
+//! ```
+//!
+//! The alternate flag (`{:#}`) pretty-prints nested lists on separate, indented lines.
+//!
+//! Both decoding and parsing reject lists nested deeper than [`MAX_DEPTH`](constant.MAX_DEPTH.html).
+
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+use core::str::FromStr;
+
+use rustc_hex::{FromHex, ToHex};
+
+use crate::error::DecoderError;
+use crate::rlpin::Rlp;
+use crate::stream::{RlpBuffer, RlpStream};
+use crate::traits::{Decodable, Encodable};
+
+/// Maximum nesting of lists accepted when decoding or parsing a `Value`. A top-level list has depth 1.
+pub const MAX_DEPTH: usize = 128;
+
+/// An untyped rlp item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+	/// Byte string.
+	Data(Vec<u8>),
+	/// List of items.
+	List(Vec<Value>),
+}
+
+impl Value {
+	/// Decode the first rlp item in `bytes`.
+	pub fn from_bytes(bytes: &[u8]) -> Result<Value, DecoderError> {
+		Rlp::new(bytes).as_val()
+	}
+
+	/// Encode the value back to rlp.
+	pub fn to_rlp(&self) -> Vec<u8> {
+		crate::encode(self)
+	}
+
+	fn decode_nested(rlp: &Rlp, depth: usize) -> Result<Value, DecoderError> {
+		if rlp.is_list() {
+			if depth >= MAX_DEPTH {
+				return Err(DecoderError::RlpNestingTooDeep);
+			}
+			rlp.iter().map(|item| Value::decode_nested(&item, depth + 1)).collect::<Result<_, _>>().map(Value::List)
+		} else {
+			rlp.decoder().decode_value(|bytes| Ok(Value::Data(bytes.to_vec())))
+		}
+	}
+
+	fn fmt_indented(&self, f: &mut fmt::Formatter, indent: usize) -> fmt::Result {
+		match self {
+			Value::Data(data) => write!(f, "\"0x{}\"", data.to_hex::<String>()),
+			Value::List(items) if items.is_empty() => write!(f, "[]"),
+			Value::List(items) => {
+				writeln!(f, "[")?;
+				for (i, item) in items.iter().enumerate() {
+					write!(f, "{:width$}", "", width = (indent + 1) * 2)?;
+					item.fmt_indented(f, indent + 1)?;
+					if i + 1 < items.len() {
+						write!(f, ",")?;
+					}
+					writeln!(f)?;
+				}
+				write!(f, "{:width$}]", "", width = indent * 2)
+			}
+		}
+	}
+}
+
+impl Encodable for Value {
+	fn rlp_append<B: RlpBuffer>(&self, s: &mut RlpStream<B>) {
+		match self {
+			Value::Data(data) => {
+				s.encoder().encode_value(data);
+			}
+			Value::List(items) => {
+				s.begin_list(items.len());
+				for item in items {
+					s.append(item);
+				}
+			}
+		}
+	}
+}
+
+impl Decodable for Value {
+	fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+		Value::decode_nested(rlp, 0)
+	}
+}
+
+impl fmt::Display for Value {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if f.alternate() {
+			return self.fmt_indented(f, 0);
+		}
+		match self {
+			Value::Data(_) => self.fmt_indented(f, 0),
+			Value::List(items) => {
+				write!(f, "[")?;
+				for (i, item) in items.iter().enumerate() {
+					if i > 0 {
+						write!(f, ", ")?;
+					}
+					write!(f, "{}", item)?;
+				}
+				write!(f, "]")
+			}
+		}
+	}
+}
+
+/// Error returned when parsing a [`Value`](enum.Value.html) from its JSON representation fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseValueError {
+	/// Byte offset in the input at which parsing failed.
+	pub position: usize,
+}
+
+impl fmt::Display for ParseValueError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "invalid rlp value at position {}", self.position)
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseValueError {}
+
+struct Parser<'a> {
+	input: &'a str,
+	position: usize,
+	// number of lists we are currently inside of
+	depth: usize,
+}
+
+impl<'a> Parser<'a> {
+	fn error(&self) -> ParseValueError {
+		ParseValueError { position: self.position }
+	}
+
+	fn skip_whitespace(&mut self) {
+		let rest = &self.input[self.position..];
+		self.position += rest.len() - rest.trim_start().len();
+	}
+
+	fn eat(&mut self, c: char) -> bool {
+		self.skip_whitespace();
+		if self.input[self.position..].starts_with(c) {
+			self.position += c.len_utf8();
+			true
+		} else {
+			false
+		}
+	}
+
+	fn parse_value(&mut self) -> Result<Value, ParseValueError> {
+		self.skip_whitespace();
+		let start = self.position;
+		if self.eat('[') {
+			if self.depth >= MAX_DEPTH {
+				return Err(ParseValueError { position: start });
+			}
+			let mut items = Vec::new();
+			if self.eat(']') {
+				return Ok(Value::List(items));
+			}
+			self.depth += 1;
+			loop {
+				items.push(self.parse_value()?);
+				if self.eat(']') {
+					self.depth -= 1;
+					return Ok(Value::List(items));
+				}
+				if !self.eat(',') {
+					return Err(self.error());
+				}
+			}
+		}
+
+		if !self.eat('"') {
+			return Err(self.error());
+		}
+		let start = self.position;
+		let len = self.input[start..].find('"').ok_or_else(|| self.error())?;
+		let hex = &self.input[start..start + len];
+		let data = hex.strip_prefix("0x").unwrap_or(hex).from_hex().map_err(|_| self.error())?;
+		self.position = start + len + 1;
+		Ok(Value::Data(data))
+	}
+}
+
+impl FromStr for Value {
+	type Err = ParseValueError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut parser = Parser { input: s, position: 0, depth: 0 };
+		let value = parser.parse_value()?;
+		parser.skip_whitespace();
+		if parser.position != s.len() {
+			return Err(parser.error());
+		}
+		Ok(value)
+	}
+}
@@ -33,6 +33,12 @@ pub enum DecoderError {
 	RlpInconsistentLengthAndData,
 	/// Declared length is invalid and results in overflow
 	RlpInvalidLength,
+	/// Lists are nested deeper than the decoder limits allow.
+	RlpNestingTooDeep,
+	/// Rlp contains more items than the decoder limits allow.
+	RlpTooManyItems,
+	/// Declared payload length is larger than the decoder limits allow.
+	RlpPayloadTooLarge,
 	/// Custom rlp decoding error.
 	Custom(&'static str),
 }
@@ -49,3 +55,27 @@ impl fmt::Display for DecoderError {
 		fmt::Debug::fmt(&self, f)
 	}
 }
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+/// Error decoding an item of a list, see `Rlp::iter_decoded`.
+pub struct DecoderErrorWithOffset {
+	/// Index of the item in the list.
+	pub index: usize,
+	/// Byte offset of the item in the raw list, header included, as returned by `Rlp::at_with_offset`.
+	pub offset: usize,
+	/// The error.
+	pub error: DecoderError,
+}
+
+#[cfg(feature = "std")]
+impl StdError for DecoderErrorWithOffset {
+	fn description(&self) -> &str {
+		"list item decoder error"
+	}
+}
+
+impl fmt::Display for DecoderErrorWithOffset {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "item {} at byte {}: {}", self.index, self.offset, self.error)
+	}
+}
@@ -13,7 +13,7 @@ use core::{mem, str};
 
 use crate::error::DecoderError;
 use crate::rlpin::Rlp;
-use crate::stream::RlpStream;
+use crate::stream::{RlpBuffer, RlpStream};
 use crate::traits::{Decodable, Encodable};
 
 pub fn decode_usize(bytes: &[u8]) -> Result<usize, DecoderError> {
@@ -34,7 +34,7 @@ pub fn decode_usize(bytes: &[u8]) -> Result<usize, DecoderError> {
 }
 
 impl Encodable for bool {
-	fn rlp_append(&self, s: &mut RlpStream) {
+	fn rlp_append<B: RlpBuffer>(&self, s: &mut RlpStream<B>) {
 		s.encoder().encode_iter(once(if *self { 1u8 } else { 0 }));
 	}
 }
@@ -50,13 +50,13 @@ impl Decodable for bool {
 }
 
 impl<'a> Encodable for &'a [u8] {
-	fn rlp_append(&self, s: &mut RlpStream) {
+	fn rlp_append<B: RlpBuffer>(&self, s: &mut RlpStream<B>) {
 		s.encoder().encode_value(self);
 	}
 }
 
 impl Encodable for Vec<u8> {
-	fn rlp_append(&self, s: &mut RlpStream) {
+	fn rlp_append<B: RlpBuffer>(&self, s: &mut RlpStream<B>) {
 		s.encoder().encode_value(self);
 	}
 }
@@ -71,7 +71,7 @@ impl<T> Encodable for Option<T>
 where
 	T: Encodable,
 {
-	fn rlp_append(&self, s: &mut RlpStream) {
+	fn rlp_append<B: RlpBuffer>(&self, s: &mut RlpStream<B>) {
 		match *self {
 			None => {
 				s.begin_list(0);
@@ -99,7 +99,7 @@ where
 }
 
 impl Encodable for u8 {
-	fn rlp_append(&self, s: &mut RlpStream) {
+	fn rlp_append<B: RlpBuffer>(&self, s: &mut RlpStream<B>) {
 		if *self != 0 {
 			s.encoder().encode_iter(once(*self));
 		} else {
@@ -122,7 +122,7 @@ impl Decodable for u8 {
 macro_rules! impl_encodable_for_u {
 	($name: ident) => {
 		impl Encodable for $name {
-			fn rlp_append(&self, s: &mut RlpStream) {
+			fn rlp_append<B: RlpBuffer>(&self, s: &mut RlpStream<B>) {
 				let leading_empty_bytes = self.leading_zeros() as usize / 8;
 				let buffer = self.to_be_bytes();
 				s.encoder().encode_value(&buffer[leading_empty_bytes..]);
@@ -164,7 +164,7 @@ impl_decodable_for_u!(u32);
 impl_decodable_for_u!(u64);
 
 impl Encodable for usize {
-	fn rlp_append(&self, s: &mut RlpStream) {
+	fn rlp_append<B: RlpBuffer>(&self, s: &mut RlpStream<B>) {
 		(*self as u64).rlp_append(s);
 	}
 }
@@ -176,13 +176,13 @@ impl Decodable for usize {
 }
 
 impl<'a> Encodable for &'a str {
-	fn rlp_append(&self, s: &mut RlpStream) {
+	fn rlp_append<B: RlpBuffer>(&self, s: &mut RlpStream<B>) {
 		s.encoder().encode_value(self.as_bytes());
 	}
 }
 
 impl Encodable for String {
-	fn rlp_append(&self, s: &mut RlpStream) {
+	fn rlp_append<B: RlpBuffer>(&self, s: &mut RlpStream<B>) {
 		s.encoder().encode_value(self.as_bytes());
 	}
 }
@@ -37,8 +37,10 @@
 #[cfg(not(feature = "std"))]
 extern crate alloc;
 
+pub mod debug;
 mod error;
 mod impls;
+mod limits;
 mod rlpin;
 mod stream;
 mod traits;
@@ -46,10 +48,12 @@ mod traits;
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 use core::borrow::Borrow;
+use core::mem;
 
-pub use self::error::DecoderError;
-pub use self::rlpin::{PayloadInfo, Prototype, Rlp, RlpIterator};
-pub use self::stream::RlpStream;
+pub use self::error::{DecoderError, DecoderErrorWithOffset};
+pub use self::limits::DecoderLimits;
+pub use self::rlpin::{PayloadInfo, Prototype, Rlp, RlpDecodedIterator, RlpIterator};
+pub use self::stream::{RlpBuffer, RlpStream};
 pub use self::traits::{Decodable, Encodable};
 
 /// The RLP encoded empty data (used to mean "null value").
@@ -76,6 +80,22 @@ where
 	rlp.as_val()
 }
 
+/// Shortcut function to decode untrusted rlp, enforcing the given resource limits.
+///
+/// ```rust
+/// let limits = rlp::DecoderLimits { max_depth: 1, ..Default::default() };
+/// let nested = vec![0xc2, 0xc1, 0xc0];
+/// let res: Result<Vec<u8>, _> = rlp::decode_with_limits(&nested, &limits);
+/// assert_eq!(res, Err(rlp::DecoderError::RlpNestingTooDeep));
+/// ```
+pub fn decode_with_limits<T>(bytes: &[u8], limits: &DecoderLimits) -> Result<T, DecoderError>
+where
+	T: Decodable,
+{
+	let rlp = Rlp::new_with_limits(bytes, limits)?;
+	rlp.as_val()
+}
+
 pub fn decode_list<T>(bytes: &[u8]) -> Vec<T>
 where
 	T: Decodable,
@@ -84,6 +104,47 @@ where
 	rlp.as_list().expect("trusted rlp should be valid")
 }
 
+/// Header of the item at the start of `bytes`, which may hold only part of the item.
+///
+/// `PayloadInfo::total` is the number of bytes the whole item takes, e.g. to know how much
+/// to read from a stream before decoding. Fails with `DecoderError::RlpIsTooShort` if `bytes`
+/// doesn't hold the complete header yet.
+pub fn peek_payload_info(bytes: &[u8]) -> Result<PayloadInfo, DecoderError> {
+	PayloadInfo::from(bytes)
+}
+
+/// Number of items of the list at the start of `bytes`, reading only the headers of the items.
+///
+/// Unlike `Rlp::item_count`, the items themselves are not validated and bytes after the list
+/// are ignored. Fails with `DecoderError::RlpIsTooShort` if the list is incomplete.
+pub fn item_count_unchecked(bytes: &[u8]) -> Result<usize, DecoderError> {
+	match bytes.first() {
+		Some(&b) if b >= 0xc0 => (),
+		Some(_) => return Err(DecoderError::RlpExpectedToBeList),
+		None => return Err(DecoderError::RlpIsTooShort),
+	}
+	let info = PayloadInfo::from(bytes)?;
+	let end = info.header_len.checked_add(info.value_len).ok_or(DecoderError::RlpIsTooBig)?;
+	let payload = bytes.get(info.header_len..end).ok_or(DecoderError::RlpIsTooShort)?;
+	let mut count = 0;
+	let mut offset = 0;
+	while offset < payload.len() {
+		let item = PayloadInfo::from(&payload[offset..]).map_err(|e| match e {
+			DecoderError::RlpIsTooShort => DecoderError::RlpInconsistentLengthAndData,
+			e => e,
+		})?;
+		offset = offset
+			.checked_add(item.header_len)
+			.and_then(|o| o.checked_add(item.value_len))
+			.ok_or(DecoderError::RlpInconsistentLengthAndData)?;
+		count += 1;
+	}
+	if offset != payload.len() {
+		return Err(DecoderError::RlpInconsistentLengthAndData);
+	}
+	Ok(count)
+}
+
 /// Shortcut function to encode structure into rlp.
 ///
 /// ```rust
@@ -104,6 +165,27 @@ where
 	stream.drain()
 }
 
+/// Shortcut function to encode structure into rlp, appending to an existing buffer.
+///
+/// Allows hot encoding paths to reuse a single allocation for many items. Works with
+/// any `RlpBuffer`, not only `Vec<u8>`.
+///
+/// ```rust
+/// let mut out = Vec::with_capacity(64);
+/// rlp::encode_into(&mut out, &"cat");
+/// rlp::encode_into(&mut out, &"dog");
+/// assert_eq!(out, vec![0x83, b'c', b'a', b't', 0x83, b'd', b'o', b'g']);
+/// ```
+pub fn encode_into<E, B>(out: &mut B, object: &E)
+where
+	E: Encodable,
+	B: RlpBuffer,
+{
+	let mut stream = RlpStream::appending_to(mem::take(out));
+	stream.append(object);
+	*out = stream.drain();
+}
+
 pub fn encode_list<E, K>(object: &[K]) -> Vec<u8>
 where
 	E: Encodable,
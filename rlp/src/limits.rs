@@ -0,0 +1,89 @@
+// Copyright 2015-2019 Parity Technologies
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use crate::error::DecoderError;
+use crate::rlpin::BasicDecoder;
+
+/// Resource limits enforced when decoding untrusted rlp.
+///
+/// The default value imposes no limits at all, so that it can be used as a base
+/// for overriding just the limits one cares about:
+///
+/// ```rust
+/// let limits = rlp::DecoderLimits { max_depth: 16, ..Default::default() };
+/// assert!(limits.check(&[0xc2, 0xc1, 0xc0]).is_ok());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecoderLimits {
+	/// Maximum nesting of lists. A top-level list has depth 1.
+	pub max_depth: usize,
+	/// Maximum number of items (data and lists) in the whole rlp.
+	pub max_items: usize,
+	/// Maximum declared payload length of a single item.
+	pub max_payload_len: usize,
+}
+
+impl Default for DecoderLimits {
+	fn default() -> Self {
+		DecoderLimits {
+			max_depth: usize::MAX,
+			max_items: usize::MAX,
+			max_payload_len: usize::MAX,
+		}
+	}
+}
+
+impl DecoderLimits {
+	/// Walks the first rlp item in `bytes` and checks it against the limits.
+	///
+	/// The walk is iterative, so arbitrarily deep payloads cannot exhaust the stack. Every
+	/// declared payload length is also checked against the bytes actually available in the
+	/// enclosing item. Empty input is accepted, like `Rlp::new` accepts it.
+	pub fn check(&self, bytes: &[u8]) -> Result<(), DecoderError> {
+		// end offsets of the lists we are currently inside of
+		let mut list_ends: Vec<usize> = Vec::new();
+		let mut offset = 0;
+		let mut items = 0;
+
+		if bytes.is_empty() {
+			return Ok(());
+		}
+
+		loop {
+			while list_ends.last() == Some(&offset) {
+				list_ends.pop();
+			}
+			if offset > 0 && list_ends.is_empty() {
+				return Ok(());
+			}
+
+			let end = list_ends.last().cloned().unwrap_or(bytes.len());
+			let item = BasicDecoder::payload_info(&bytes[offset..end])?;
+			if item.value_len > self.max_payload_len {
+				return Err(DecoderError::RlpPayloadTooLarge);
+			}
+			items += 1;
+			if items > self.max_items {
+				return Err(DecoderError::RlpTooManyItems);
+			}
+
+			if bytes[offset] >= 0xc0 {
+				if list_ends.len() >= self.max_depth {
+					return Err(DecoderError::RlpNestingTooDeep);
+				}
+				list_ends.push(offset + item.total());
+				offset += item.header_len;
+			} else {
+				offset += item.total();
+			}
+		}
+	}
+}
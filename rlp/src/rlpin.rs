@@ -10,11 +10,13 @@
 use alloc::{string::String, vec::Vec};
 use core::cell::Cell;
 use core::fmt;
+use core::marker::PhantomData;
 
 use rustc_hex::ToHex;
 
-use crate::error::DecoderError;
+use crate::error::{DecoderError, DecoderErrorWithOffset};
 use crate::impls::decode_usize;
+use crate::limits::DecoderLimits;
 use crate::traits::Decodable;
 
 /// rlp offset
@@ -132,6 +134,15 @@ impl<'a> Rlp<'a> {
 		Rlp { bytes, offset_cache: Cell::new(None), count_cache: Cell::new(None) }
 	}
 
+	/// Create a view onto untrusted rlp, checking it against the given limits first.
+	///
+	/// Returns an error if nesting, item count or any declared payload length exceeds
+	/// the limits, or if a declared length runs past the enclosing item.
+	pub fn new_with_limits(bytes: &'a [u8], limits: &DecoderLimits) -> Result<Rlp<'a>, DecoderError> {
+		limits.check(bytes)?;
+		Ok(Rlp::new(bytes))
+	}
+
 	pub fn as_raw<'view>(&'view self) -> &'a [u8]
 	where
 		'a: 'view,
@@ -287,6 +298,25 @@ impl<'a> Rlp<'a> {
 		self.iter().map(|rlp| rlp.as_val()).collect()
 	}
 
+	/// Decode the items of a list one by one, reporting the index and offset of failing items.
+	///
+	/// Items following one which fails to decode are still decoded. The iterator ends after
+	/// an error if the list itself is malformed, since the following items can't be found.
+	pub fn iter_decoded<T>(&self) -> RlpDecodedIterator<'a, T>
+	where
+		T: Decodable,
+	{
+		let (rest, offset, error) = if !self.is_list() {
+			(&[][..], 0, Some(DecoderError::RlpExpectedToBeList))
+		} else {
+			match self.consume_list_payload() {
+				Ok((payload, header_len)) => (payload, header_len, None),
+				Err(err) => (&[][..], 0, Some(err)),
+			}
+		};
+		RlpDecodedIterator { rest, offset, index: 0, error, _marker: PhantomData }
+	}
+
 	pub fn val_at<T>(&self, index: usize) -> Result<T, DecoderError>
 	where
 		T: Decodable,
@@ -369,6 +399,49 @@ impl<'a, 'view> Iterator for RlpIterator<'a, 'view> {
 	}
 }
 
+/// Iterator decoding the items of a list, see `Rlp::iter_decoded`.
+pub struct RlpDecodedIterator<'a, T> {
+	rest: &'a [u8],
+	offset: usize,
+	index: usize,
+	// Error to report before ending, when the list is malformed.
+	error: Option<DecoderError>,
+	_marker: PhantomData<fn() -> T>,
+}
+
+impl<'a, T: Decodable> RlpDecodedIterator<'a, T> {
+	fn fail(&mut self, error: DecoderError) -> DecoderErrorWithOffset {
+		self.rest = &[];
+		DecoderErrorWithOffset { index: self.index, offset: self.offset, error }
+	}
+}
+
+impl<'a, T: Decodable> Iterator for RlpDecodedIterator<'a, T> {
+	type Item = Result<T, DecoderErrorWithOffset>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if let Some(error) = self.error.take() {
+			return Some(Err(self.fail(error)));
+		}
+		if self.rest.is_empty() {
+			return None;
+		}
+		let item = match BasicDecoder::payload_info(self.rest) {
+			Ok(info) => info.total(),
+			Err(error) => return Some(Err(self.fail(error))),
+		};
+		let result = T::decode(&Rlp::new(&self.rest[..item])).map_err(|error| DecoderErrorWithOffset {
+			index: self.index,
+			offset: self.offset,
+			error,
+		});
+		self.rest = &self.rest[item..];
+		self.offset += item;
+		self.index += 1;
+		Some(result)
+	}
+}
+
 pub struct BasicDecoder<'a> {
 	rlp: &'a [u8],
 }
@@ -379,7 +452,7 @@ impl<'a> BasicDecoder<'a> {
 	}
 
 	/// Return first item info.
-	fn payload_info(bytes: &[u8]) -> Result<PayloadInfo, DecoderError> {
+	pub(crate) fn payload_info(bytes: &[u8]) -> Result<PayloadInfo, DecoderError> {
 		let item = PayloadInfo::from(bytes)?;
 		match item.header_len.checked_add(item.value_len) {
 			Some(x) if x <= bytes.len() => Ok(item),
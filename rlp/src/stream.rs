@@ -9,9 +9,72 @@
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 use core::borrow::Borrow;
+use core::ops::DerefMut;
 
 use crate::traits::Encodable;
 
+/// Output buffer an `RlpStream` can encode into.
+///
+/// Implemented for `Vec<u8>` and, behind the `bytes` and `smallvec` features,
+/// for `bytes::BytesMut` and `smallvec::SmallVec`.
+pub trait RlpBuffer: Default + Extend<u8> + DerefMut<Target = [u8]> {
+	/// Appends a single byte.
+	fn push(&mut self, byte: u8);
+
+	/// Appends all bytes of the slice.
+	fn extend_from_slice(&mut self, bytes: &[u8]);
+
+	/// Removes all bytes, keeping the allocated capacity.
+	fn clear(&mut self);
+}
+
+impl RlpBuffer for Vec<u8> {
+	fn push(&mut self, byte: u8) {
+		Vec::push(self, byte)
+	}
+
+	fn extend_from_slice(&mut self, bytes: &[u8]) {
+		Vec::extend_from_slice(self, bytes)
+	}
+
+	fn clear(&mut self) {
+		Vec::clear(self)
+	}
+}
+
+#[cfg(feature = "bytes")]
+impl RlpBuffer for bytes::BytesMut {
+	fn push(&mut self, byte: u8) {
+		bytes::BytesMut::extend_from_slice(self, &[byte])
+	}
+
+	fn extend_from_slice(&mut self, bytes: &[u8]) {
+		bytes::BytesMut::extend_from_slice(self, bytes)
+	}
+
+	fn clear(&mut self) {
+		bytes::BytesMut::clear(self)
+	}
+}
+
+#[cfg(feature = "smallvec")]
+impl<A> RlpBuffer for smallvec::SmallVec<A>
+where
+	A: smallvec::Array<Item = u8>,
+{
+	fn push(&mut self, byte: u8) {
+		smallvec::SmallVec::push(self, byte)
+	}
+
+	fn extend_from_slice(&mut self, bytes: &[u8]) {
+		smallvec::SmallVec::extend_from_slice(self, bytes)
+	}
+
+	fn clear(&mut self) {
+		smallvec::SmallVec::clear(self)
+	}
+}
+
 #[derive(Debug, Copy, Clone)]
 struct ListInfo {
 	position: usize,
@@ -26,9 +89,12 @@ impl ListInfo {
 }
 
 /// Appendable rlp encoder.
-pub struct RlpStream {
+///
+/// Encodes into a `Vec<u8>` by default, any other `RlpBuffer` can be used
+/// through `RlpStream::new_with_buffer`.
+pub struct RlpStream<B: RlpBuffer = Vec<u8>> {
 	unfinished_lists: Vec<ListInfo>,
-	buffer: Vec<u8>,
+	buffer: B,
 	finished_list: bool,
 }
 
@@ -50,6 +116,30 @@ impl RlpStream {
 		stream.begin_list(len);
 		stream
 	}
+}
+
+impl<B: RlpBuffer> RlpStream<B> {
+	/// Initializes instance of empty `Stream`, encoding into `buffer`.
+	///
+	/// The buffer is cleared before use, only its capacity is kept.
+	pub fn new_with_buffer(mut buffer: B) -> Self {
+		buffer.clear();
+		RlpStream { unfinished_lists: Vec::with_capacity(16), buffer, finished_list: false }
+	}
+
+	/// Initializes the `Stream` as a list, encoding into `buffer`.
+	pub fn new_list_with_buffer(len: usize, buffer: B) -> Self {
+		let mut stream = RlpStream::new_with_buffer(buffer);
+		stream.begin_list(len);
+		stream
+	}
+
+	/// Initializes a stream which appends to the existing contents of `buffer`.
+	///
+	/// `len` and `estimate_size` of such a stream include the existing contents.
+	pub(crate) fn appending_to(buffer: B) -> Self {
+		RlpStream { unfinished_lists: Vec::new(), buffer, finished_list: false }
+	}
 
 	/// Apends null to the end of stream, chainable.
 	///
@@ -75,8 +165,8 @@ impl RlpStream {
 		self
 	}
 
-	/// Drain the object and return the underlying ElasticArray. Panics if it is not finished.
-	pub fn drain(self) -> Vec<u8> {
+	/// Drain the object and return the underlying buffer. Panics if it is not finished.
+	pub fn drain(self) -> B {
 		self.out()
 	}
 
@@ -179,7 +269,7 @@ impl RlpStream {
 	/// 	assert_eq!(out, vec![0xca, 0xc8, 0x83, b'c', b'a', b't', 0x83, b'd', b'o', b'g', 0x80]);
 	/// }
 	/// ```
-	pub fn begin_list(&mut self, len: usize) -> &mut RlpStream {
+	pub fn begin_list(&mut self, len: usize) -> &mut Self {
 		self.finished_list = false;
 		match len {
 			0 => {
@@ -203,7 +293,7 @@ impl RlpStream {
 	}
 
 	/// Declare appending the list of unknown size, chainable.
-	pub fn begin_unbounded_list(&mut self) -> &mut RlpStream {
+	pub fn begin_unbounded_list(&mut self) -> &mut Self {
 		self.finished_list = false;
 		// payload is longer than 1 byte only for lists > 55 bytes
 		// by pushing always this 1 byte we may avoid unnecessary shift of data
@@ -247,7 +337,7 @@ impl RlpStream {
 		self.len() == 0
 	}
 
-	/// Clear the output stream so far.
+	/// Clear the output stream so far, keeping the allocated buffer for reuse.
 	///
 	/// ```rust
 	/// extern crate rlp;
@@ -297,7 +387,7 @@ impl RlpStream {
 	/// Streams out encoded bytes.
 	///
 	/// panic! if stream is not finished.
-	pub fn out(self) -> Vec<u8> {
+	pub fn out(self) -> B {
 		if self.is_finished() {
 			self.buffer
 		} else {
@@ -332,7 +422,7 @@ impl RlpStream {
 		self.finished_list = should_finish;
 	}
 
-	pub fn encoder(&mut self) -> BasicEncoder {
+	pub fn encoder(&mut self) -> BasicEncoder<'_, B> {
 		BasicEncoder::new(self)
 	}
 
@@ -355,12 +445,12 @@ impl RlpStream {
 	}
 }
 
-pub struct BasicEncoder<'a> {
-	buffer: &'a mut Vec<u8>,
+pub struct BasicEncoder<'a, B: RlpBuffer = Vec<u8>> {
+	buffer: &'a mut B,
 }
 
-impl<'a> BasicEncoder<'a> {
-	fn new(stream: &'a mut RlpStream) -> Self {
+impl<'a, B: RlpBuffer> BasicEncoder<'a, B> {
+	fn new(stream: &'a mut RlpStream<B>) -> Self {
 		BasicEncoder { buffer: &mut stream.buffer }
 	}
 
@@ -12,7 +12,7 @@ use alloc::vec::Vec;
 
 use crate::error::DecoderError;
 use crate::rlpin::Rlp;
-use crate::stream::RlpStream;
+use crate::stream::{RlpBuffer, RlpStream};
 
 /// RLP decodable trait
 pub trait Decodable: Sized {
@@ -23,7 +23,7 @@ pub trait Decodable: Sized {
 /// Structure encodable to RLP
 pub trait Encodable {
 	/// Append a value to the stream
-	fn rlp_append(&self, s: &mut RlpStream);
+	fn rlp_append<B: RlpBuffer>(&self, s: &mut RlpStream<B>);
 
 	/// Get rlp-encoded bytes for this instance
 	fn rlp_bytes(&self) -> Vec<u8> {
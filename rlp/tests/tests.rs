@@ -10,7 +10,10 @@ use core::{cmp, fmt};
 
 use hex_literal::hex;
 use primitive_types::{H160, U256};
-use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
+use rlp::{
+	debug::{ParseValueError, Value},
+	Decodable, DecoderError, DecoderErrorWithOffset, DecoderLimits, Encodable, Rlp, RlpBuffer, RlpStream,
+};
 
 #[test]
 fn test_rlp_display() {
@@ -97,6 +100,26 @@ fn rlp_at_err() {
 	}
 }
 
+#[test]
+fn rlp_iter_decoded() {
+	let err = |index, offset, error| Err(DecoderErrorWithOffset { index, offset, error });
+
+	// the second item is too long for a u16
+	let data = [0xc6, 0x01, 0x83, b'c', b'a', b't', 0x05];
+	let items: Vec<Result<u16, _>> = Rlp::new(&data).iter_decoded().collect();
+	assert_eq!(items, vec![Ok(1), err(1, 2, DecoderError::RlpIsTooBig), Ok(5)]);
+	assert_eq!(format!("{}", items[1].clone().unwrap_err()), "item 1 at byte 2: RlpIsTooBig");
+
+	// the second item runs past the end of the list
+	let data = [0xc4, 0x01, 0x83, b'c', b'a'];
+	let items: Vec<Result<u16, _>> = Rlp::new(&data).iter_decoded().collect();
+	assert_eq!(items, vec![Ok(1), err(1, 2, DecoderError::RlpIsTooShort)]);
+
+	let items: Vec<Result<u16, _>> = Rlp::new(&[0x05]).iter_decoded().collect();
+	assert_eq!(items, vec![err(0, 0, DecoderError::RlpExpectedToBeList)]);
+	assert_eq!(Rlp::new(&[0xc0]).iter_decoded::<u16>().count(), 0);
+}
+
 #[test]
 fn rlp_iter() {
 	let data = vec![0xc8, 0x83, b'c', b'a', b't', 0x83, b'd', b'o', b'g'];
@@ -555,7 +578,7 @@ fn test_nested_list_roundtrip() {
 	struct Inner(u64, u64);
 
 	impl Encodable for Inner {
-		fn rlp_append(&self, s: &mut RlpStream) {
+		fn rlp_append<B: RlpBuffer>(&self, s: &mut RlpStream<B>) {
 			s.begin_unbounded_list().append(&self.0).append(&self.1).finalize_unbounded_list();
 		}
 	}
@@ -570,7 +593,7 @@ fn test_nested_list_roundtrip() {
 	struct Nest<T>(Vec<T>);
 
 	impl<T: Encodable> Encodable for Nest<T> {
-		fn rlp_append(&self, s: &mut RlpStream) {
+		fn rlp_append<B: RlpBuffer>(&self, s: &mut RlpStream<B>) {
 			s.begin_unbounded_list().append_list(&self.0).finalize_unbounded_list();
 		}
 	}
@@ -609,3 +632,169 @@ fn test_list_at() {
 	let rlp2 = rlp.at(2).unwrap();
 	assert_eq!(rlp2.val_at::<u16>(2).unwrap(), 33338);
 }
+
+#[test]
+fn test_decoder_limits_depth() {
+	let limits = DecoderLimits { max_depth: 2, ..Default::default() };
+	assert!(Rlp::new_with_limits(&[0xc1, 0xc0], &limits).is_ok());
+	assert_eq!(Rlp::new_with_limits(&[0xc2, 0xc1, 0xc0], &limits).unwrap_err(), DecoderError::RlpNestingTooDeep);
+
+	// deeply nested payload is rejected without recursion
+	let depth = 100_000;
+	let mut stream = RlpStream::new();
+	for _ in 0..depth {
+		stream.begin_unbounded_list();
+	}
+	for _ in 0..depth {
+		stream.finalize_unbounded_list();
+	}
+	let nested = stream.out();
+	assert_eq!(Rlp::new_with_limits(&nested, &limits).unwrap_err(), DecoderError::RlpNestingTooDeep);
+	assert!(DecoderLimits::default().check(&nested).is_ok());
+}
+
+#[test]
+fn test_decoder_limits_items() {
+	let data = vec![0xc8, 0x83, b'c', b'a', b't', 0x83, b'd', b'o', b'g'];
+	let limits = DecoderLimits { max_items: 3, ..Default::default() };
+	let animals: Vec<String> = Rlp::new_with_limits(&data, &limits).unwrap().as_list().unwrap();
+	assert_eq!(animals, vec!["cat".to_owned(), "dog".to_owned()]);
+
+	let limits = DecoderLimits { max_items: 2, ..Default::default() };
+	assert_eq!(rlp::decode_with_limits::<String>(&data, &limits).unwrap_err(), DecoderError::RlpTooManyItems);
+}
+
+#[test]
+fn test_decoder_limits_payload_len() {
+	let data = vec![0x83, b'c', b'a', b't'];
+	let limits = DecoderLimits { max_payload_len: 3, ..Default::default() };
+	assert_eq!(rlp::decode_with_limits::<String>(&data, &limits).unwrap(), "cat".to_owned());
+
+	let limits = DecoderLimits { max_payload_len: 2, ..Default::default() };
+	assert_eq!(rlp::decode_with_limits::<String>(&data, &limits).unwrap_err(), DecoderError::RlpPayloadTooLarge);
+
+	// inner item declares more bytes than its enclosing list holds
+	let data = vec![0xc3, 0x83, b'c', b'a', b't'];
+	assert_eq!(DecoderLimits::default().check(&data).unwrap_err(), DecoderError::RlpIsTooShort);
+}
+
+#[test]
+fn test_rlp_stream_buffer_reuse() {
+	let mut stream = RlpStream::new_list(2);
+	stream.append(&"cat").append(&"dog");
+	let mut buffer = stream.out();
+	let capacity = buffer.capacity();
+	buffer.push(0xff);
+
+	let mut stream = RlpStream::new_with_buffer(buffer);
+	assert!(stream.is_empty());
+	stream.append(&"cat");
+	let buffer = stream.out();
+	assert_eq!(buffer, vec![0x83, b'c', b'a', b't']);
+	assert_eq!(buffer.capacity(), capacity);
+}
+
+#[test]
+fn test_encode_into() {
+	let mut out = vec![0x01];
+	rlp::encode_into(&mut out, &"cat");
+	rlp::encode_into(&mut out, &0x0400u64);
+	rlp::encode_into(&mut out, &[0x42u8; 60].to_vec());
+	let mut expected = vec![0x01, 0x83, b'c', b'a', b't', 0x82, 0x04, 0x00, 0xb8, 60];
+	expected.extend_from_slice(&[0x42u8; 60]);
+	assert_eq!(out, expected);
+}
+
+#[test]
+fn test_debug_value_roundtrip() {
+	let data = hex!("f84d0589010efbef67941f79b2a056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421a0c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470");
+	let value = Value::from_bytes(&data).unwrap();
+	let json = value.to_string();
+	assert_eq!(json, Rlp::new(&data).to_string());
+	assert_eq!(json.parse::<Value>().unwrap(), value);
+	assert_eq!(value.to_rlp(), data.to_vec());
+}
+
+#[test]
+fn test_debug_value_pretty() {
+	let value =
+		Value::List(vec![Value::Data(vec![]), Value::List(vec![Value::Data(b"cat".to_vec()), Value::List(vec![])])]);
+	let pretty = format!("{:#}", value);
+	assert_eq!(pretty, "[\n  \"0x\",\n  [\n    \"0x636174\",\n    []\n  ]\n]");
+	assert_eq!(pretty.parse::<Value>().unwrap(), value);
+	assert_eq!(rlp::decode::<Value>(&value.to_rlp()).unwrap(), value);
+}
+
+#[test]
+fn test_debug_value_parse_error() {
+	assert_eq!("[\"0x01\" \"0x02\"]".parse::<Value>().unwrap_err(), ParseValueError { position: 8 });
+	assert_eq!("\"0xzz\"".parse::<Value>().unwrap_err(), ParseValueError { position: 1 });
+	assert_eq!("[] x".parse::<Value>().unwrap_err(), ParseValueError { position: 3 });
+	assert_eq!("\"0x0x01\"".parse::<Value>().unwrap_err(), ParseValueError { position: 1 });
+}
+
+#[test]
+fn test_debug_value_depth_limit() {
+	let nested = |depth| (0..depth).fold(Value::List(vec![]), |value, _| Value::List(vec![value]));
+	let deepest = nested(rlp::debug::MAX_DEPTH - 1);
+	assert_eq!(Value::from_bytes(&deepest.to_rlp()).unwrap(), deepest);
+	assert_eq!(deepest.to_string().parse::<Value>().unwrap(), deepest);
+
+	let too_deep = nested(rlp::debug::MAX_DEPTH);
+	assert_eq!(Value::from_bytes(&too_deep.to_rlp()), Err(DecoderError::RlpNestingTooDeep));
+	let json = too_deep.to_string();
+	assert_eq!(json.parse::<Value>().unwrap_err(), ParseValueError { position: rlp::debug::MAX_DEPTH });
+}
+
+#[cfg(feature = "bytes")]
+#[test]
+fn test_rlp_stream_bytes_mut() {
+	let mut stream = RlpStream::new_list_with_buffer(2, bytes::BytesMut::new());
+	stream.append(&"cat").append(&vec![0x42u8; 60]);
+	let out = stream.out();
+	assert_eq!(&out[..], &rlp::encode_list::<Vec<u8>, _>(&[b"cat".to_vec(), vec![0x42u8; 60]])[..]);
+
+	let mut out = bytes::BytesMut::new();
+	rlp::encode_into(&mut out, &U256::from(1024));
+	assert_eq!(&out[..], &[0x82, 0x04, 0x00]);
+}
+
+#[cfg(feature = "smallvec")]
+#[test]
+fn test_rlp_stream_smallvec() {
+	let mut stream = RlpStream::new_with_buffer(smallvec::SmallVec::<[u8; 32]>::new());
+	stream.begin_unbounded_list().append(&"cat").append(&"dog").finalize_unbounded_list();
+	let out = stream.out();
+	assert!(!out.spilled());
+	assert_eq!(&out[..], &[0xc8, 0x83, b'c', b'a', b't', 0x83, b'd', b'o', b'g']);
+}
+
+#[test]
+fn test_peek_payload_info() {
+	let mut stream = RlpStream::new_list(3);
+	stream.append(&"cat").append(&vec![0u8; 60]).append_list(&[1u64, 2]);
+	let encoded = stream.out();
+
+	let info = rlp::peek_payload_info(&encoded[..2]).unwrap();
+	assert_eq!(info.total(), encoded.len());
+	assert_eq!(rlp::peek_payload_info(&encoded[..1]).unwrap_err(), DecoderError::RlpIsTooShort);
+	assert_eq!(rlp::peek_payload_info(&[]).unwrap_err(), DecoderError::RlpIsTooShort);
+	assert_eq!(rlp::peek_payload_info(&[0x05]).unwrap().total(), 1);
+}
+
+#[test]
+fn test_item_count_unchecked() {
+	let mut stream = RlpStream::new_list(3);
+	stream.append(&"cat").append(&vec![0u8; 60]).append_list(&[1u64, 2]);
+	let mut encoded = stream.out();
+	assert_eq!(rlp::item_count_unchecked(&encoded), Ok(3));
+	assert_eq!(rlp::item_count_unchecked(&encoded[..encoded.len() - 1]), Err(DecoderError::RlpIsTooShort));
+	assert_eq!(rlp::item_count_unchecked(&[0x83, b'c', b'a', b't']), Err(DecoderError::RlpExpectedToBeList));
+	assert_eq!(rlp::item_count_unchecked(&[0xc0]), Ok(0));
+
+	// trailing bytes are ignored
+	encoded.push(0x80);
+	assert_eq!(rlp::item_count_unchecked(&encoded), Ok(3));
+	// the last item claims more bytes than the list holds
+	assert_eq!(rlp::item_count_unchecked(&[0xc2, 0x01, 0x82]), Err(DecoderError::RlpInconsistentLengthAndData));
+}
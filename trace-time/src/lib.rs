@@ -15,9 +15,19 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 //! Performance timer with logging
+//!
+//! Nested `trace_time!` scopes on the same thread form a tree of `Span`s. The tree is reported once
+//! the outermost scope ends, and only if it took at least the configured threshold (see `set_threshold`).
+//! By default the tree is logged under the `perf` target, `set_reporter` replaces logging with a callback.
+//! Timers do nothing unless a reporter is set or trace logging is enabled for the `perf` target.
 
-use log::trace;
-use std::time::Instant;
+use lazy_static::lazy_static;
+use log::{log_enabled, trace, Level};
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
 
 #[macro_export]
 macro_rules! trace_time {
@@ -26,24 +36,163 @@ macro_rules! trace_time {
 	};
 }
 
-/// Performance timer with logging. Starts measuring time in the constructor, prints
-/// elapsed time in the destructor or when `stop` is called.
+type Reporter = Box<dyn Fn(&Span) + Send + Sync>;
+
+lazy_static! {
+	static ref REPORTER: RwLock<Option<Reporter>> = RwLock::new(None);
+}
+
+static THRESHOLD_NANOS: AtomicU64 = AtomicU64::new(0);
+
+// Whether `REPORTER` is set, to avoid taking its lock for every timer.
+static HAS_REPORTER: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+	static OPEN_SPANS: RefCell<Vec<(Instant, Span)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Sets the minimal duration of the outermost scope for the tree to be reported. Defaults to zero.
+pub fn set_threshold(threshold: Duration) {
+	THRESHOLD_NANOS.store(threshold.as_nanos() as u64, Ordering::Relaxed);
+}
+
+/// Reports finished trees to `reporter` instead of the log.
+pub fn set_reporter<F>(reporter: F)
+where
+	F: Fn(&Span) + Send + Sync + 'static,
+{
+	*REPORTER.write().expect("reporter lock is never poisoned; qed") = Some(Box::new(reporter));
+	HAS_REPORTER.store(true, Ordering::Relaxed);
+}
+
+/// Reports finished trees to the log again.
+pub fn reset_reporter() {
+	*REPORTER.write().expect("reporter lock is never poisoned; qed") = None;
+	HAS_REPORTER.store(false, Ordering::Relaxed);
+}
+
+/// Timing of a finished scope.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+	/// Name given to the timer.
+	pub name: &'static str,
+	/// Time spent in the scope.
+	pub elapsed: Duration,
+	/// Scopes nested in this one, in order of completion.
+	pub children: Vec<Span>,
+}
+
+impl Span {
+	fn log(&self, depth: usize) {
+		trace!(target: "perf", "{:indent$}{}: {:.2}ms", "", self.name, self.elapsed.as_millis(), indent = depth * 2);
+		for child in &self.children {
+			child.log(depth + 1);
+		}
+	}
+}
+
+/// Performance timer with logging. Starts measuring time in the constructor, reports
+/// elapsed time in the destructor.
+///
+/// Timers are bound to the thread they were created on, whose open scopes they close.
 pub struct PerfTimer {
-	name: &'static str,
-	start: Instant,
+	// position in `OPEN_SPANS`, `None` if there is nothing to report to
+	depth: Option<usize>,
+	_not_send: PhantomData<*const ()>,
 }
 
 impl PerfTimer {
 	/// Create an instance with given name.
 	pub fn new(name: &'static str) -> PerfTimer {
-		PerfTimer { name, start: Instant::now() }
+		let enabled = HAS_REPORTER.load(Ordering::Relaxed) || log_enabled!(target: "perf", Level::Trace);
+		let depth = if enabled {
+			let span = Span { name, elapsed: Duration::default(), children: Vec::new() };
+			Some(OPEN_SPANS.with(|open| {
+				let mut open = open.borrow_mut();
+				open.push((Instant::now(), span));
+				open.len() - 1
+			}))
+		} else {
+			None
+		};
+		PerfTimer { depth, _not_send: PhantomData }
 	}
 }
 
 impl Drop for PerfTimer {
 	fn drop(&mut self) {
-		let elapsed = self.start.elapsed();
-		let ms = elapsed.as_millis();
-		trace!(target: "perf", "{}: {:.2}ms", self.name, ms);
+		let depth = match self.depth {
+			Some(depth) => depth,
+			None => return,
+		};
+		let root = OPEN_SPANS.with(|open| {
+			let mut open = open.borrow_mut();
+			// Timers dropped out of order leave nothing to close here.
+			if open.len() <= depth {
+				return None;
+			}
+			// Close scopes that outlived their parent first.
+			while open.len() > depth {
+				let (start, mut span) = open.pop().expect("length checked above; qed");
+				span.elapsed = start.elapsed();
+				match open.last_mut() {
+					Some((_, parent)) => parent.children.push(span),
+					None => return Some(span),
+				}
+			}
+			None
+		});
+
+		if let Some(root) = root {
+			if root.elapsed.as_nanos() < THRESHOLD_NANOS.load(Ordering::Relaxed) as u128 {
+				return;
+			}
+			match &*REPORTER.read().expect("reporter lock is never poisoned; qed") {
+				Some(reporter) => reporter(&root),
+				None => root.log(0),
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::{Arc, Mutex};
+
+	#[test]
+	fn should_report_nested_scopes_over_threshold() {
+		let reported = Arc::new(Mutex::new(Vec::new()));
+		let sink = reported.clone();
+		// without a reporter or trace logging nothing is tracked
+		let timer = PerfTimer::new("unreported");
+		assert_eq!(timer.depth, None);
+		drop(timer);
+		set_reporter(move |span: &Span| sink.lock().unwrap().push(span.clone()));
+
+		{
+			trace_time!("root");
+			{
+				trace_time!("first");
+				trace_time!("inner");
+			}
+			trace_time!("second");
+		}
+
+		set_threshold(Duration::from_secs(60));
+		{
+			trace_time!("fast");
+		}
+		set_threshold(Duration::default());
+		reset_reporter();
+
+		let reported = reported.lock().unwrap();
+		assert_eq!(reported.len(), 1);
+		let root = &reported[0];
+		assert_eq!(root.name, "root");
+		let names: Vec<_> = root.children.iter().map(|child| child.name).collect();
+		assert_eq!(names, vec!["first", "second"]);
+		assert_eq!(root.children[0].children[0].name, "inner");
+		assert!(root.children.iter().all(|child| child.elapsed <= root.elapsed));
 	}
 }
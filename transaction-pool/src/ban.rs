@@ -0,0 +1,136 @@
+// Copyright 2015-2019 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Temporary bans of invalid transactions and their senders.
+
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+/// Options of the `BanList`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BanOptions {
+	/// How long a ban lasts.
+	pub cooldown: Duration,
+	/// Ban the senders of invalid transactions too, not only the transactions.
+	pub ban_senders: bool,
+	/// Maximal number of bans. The oldest ban is lifted when the limit is reached.
+	pub max_bans: usize,
+}
+
+impl Default for BanOptions {
+	fn default() -> Self {
+		BanOptions { cooldown: Duration::from_secs(180), ban_senders: false, max_bans: 4096 }
+	}
+}
+
+/// A banned transaction or sender.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Ban<H, S> {
+	/// A transaction, by hash.
+	Hash(H),
+	/// All transactions of a sender.
+	Sender(S),
+}
+
+/// Hashes and senders whose transactions are rejected until their ban expires.
+///
+/// Set with `Pool::set_ban_list`, transactions removed from the pool as invalid are banned
+/// automatically. Bans expire in the order they were made, so expiry is cheap enough to be
+/// checked on every import.
+#[derive(Debug)]
+pub struct BanList<H: Eq + Hash, S: Eq + Hash> {
+	options: BanOptions,
+	expiry: HashMap<Ban<H, S>, Instant>,
+	// Bans in the order they were made. Renewed bans are left in place, `expiry` has the current time.
+	queue: VecDeque<(Instant, Ban<H, S>)>,
+}
+
+impl<H: Eq + Hash + Clone, S: Eq + Hash + Clone> BanList<H, S> {
+	/// Creates an empty ban list.
+	pub fn new(options: BanOptions) -> Self {
+		BanList { options, expiry: HashMap::new(), queue: VecDeque::new() }
+	}
+
+	/// Returns the options of the ban list.
+	pub fn options(&self) -> &BanOptions {
+		&self.options
+	}
+
+	/// Bans given hash or sender for the cooldown period, or renews its ban.
+	///
+	/// Returns the ban lifted to stay within `BanOptions::max_bans`, if any.
+	pub fn ban(&mut self, ban: Ban<H, S>, now: Instant) -> Option<Ban<H, S>> {
+		let until = now + self.options.cooldown;
+		self.queue.push_back((until, ban.clone()));
+		if self.expiry.insert(ban, until).is_some() || self.expiry.len() <= self.options.max_bans {
+			return None;
+		}
+		self.pop_oldest()
+	}
+
+	/// Lifts a ban. Returns false if it's not banned.
+	pub fn unban(&mut self, ban: &Ban<H, S>) -> bool {
+		self.expiry.remove(ban).is_some()
+	}
+
+	/// Returns true if given hash or sender is banned.
+	pub fn is_banned(&self, ban: &Ban<H, S>, now: Instant) -> bool {
+		matches!(self.expiry.get(ban), Some(until) if *until > now)
+	}
+
+	/// Returns true if the transaction or its sender is banned.
+	pub fn is_transaction_banned(&self, hash: &H, sender: &S, now: Instant) -> bool {
+		!self.expiry.is_empty()
+			&& (self.is_banned(&Ban::Hash(hash.clone()), now) || self.is_banned(&Ban::Sender(sender.clone()), now))
+	}
+
+	/// Removes the expired bans and returns them.
+	pub fn expire(&mut self, now: Instant) -> Vec<Ban<H, S>> {
+		let mut expired = Vec::new();
+		while let Some((until, _)) = self.queue.front() {
+			if *until > now {
+				break;
+			}
+			let (until, ban) = self.queue.pop_front().expect("front is some; qed");
+			if self.expiry.get(&ban) == Some(&until) {
+				self.expiry.remove(&ban);
+				expired.push(ban);
+			}
+		}
+		expired
+	}
+
+	/// Number of active bans.
+	pub fn len(&self) -> usize {
+		self.expiry.len()
+	}
+
+	/// Returns true if nothing is banned.
+	pub fn is_empty(&self) -> bool {
+		self.expiry.is_empty()
+	}
+
+	fn pop_oldest(&mut self) -> Option<Ban<H, S>> {
+		while let Some((until, ban)) = self.queue.pop_front() {
+			if self.expiry.get(&ban) == Some(&until) {
+				self.expiry.remove(&ban);
+				return Some(ban);
+			}
+		}
+		None
+	}
+}
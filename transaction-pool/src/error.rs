@@ -25,6 +25,12 @@ pub enum Error<Hash: fmt::Debug + fmt::LowerHex> {
 	TooCheapToEnter(Hash, String),
 	/// Transaction is too cheap to replace existing transaction that occupies the same slot.
 	TooCheapToReplace(Hash, Hash),
+	/// Transaction nonce is lower than the current nonce of the sender.
+	StaleNonce(Hash),
+	/// Transaction has a nonce gap and the future queue is full.
+	FutureQueueFull(Hash),
+	/// Transaction or its sender is banned, see `BanList`.
+	Banned(Hash),
 }
 
 /// Transaction Pool Result
@@ -38,6 +44,9 @@ impl<H: fmt::Debug + fmt::LowerHex> fmt::Display for Error<H> {
 				write!(f, "[{:x}] too cheap to enter the pool. Min score: {}", hash, min_score)
 			}
 			Error::TooCheapToReplace(old_hash, hash) => write!(f, "[{:x}] too cheap to replace: {:x}", hash, old_hash),
+			Error::StaleNonce(hash) => write!(f, "[{:x}] nonce is too low", hash),
+			Error::FutureQueueFull(hash) => write!(f, "[{:x}] future queue is full", hash),
+			Error::Banned(hash) => write!(f, "[{:x}] banned", hash),
 		}
 	}
 }
@@ -58,6 +67,9 @@ where
 			(&TooCheapToReplace(ref old1, ref new1), &TooCheapToReplace(ref old2, ref new2)) => {
 				old1 == old2 && new1 == new2
 			}
+			(&StaleNonce(ref h1), &StaleNonce(ref h2)) => h1 == h2,
+			(&FutureQueueFull(ref h1), &FutureQueueFull(ref h2)) => h1 == h2,
+			(&Banned(ref h1), &Banned(ref h2)) => h1 == h2,
 			_ => false,
 		}
 	}
@@ -0,0 +1,192 @@
+// Copyright 2015-2019 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Stream of pool events for subscribers that can't implement `Listener` themselves.
+
+use std::fmt::{Debug, LowerHex};
+use std::sync::{mpsc, Arc};
+
+use crate::{ban::Ban, error::Error, listener::Listener, VerifiedTransaction};
+
+/// Number of events a subscriber may lag behind by default, see `EventListener::with_capacity`.
+pub const DEFAULT_EVENT_CAPACITY: usize = 1024;
+
+/// A change of a transaction status in the pool.
+#[derive(Debug)]
+pub enum Event<T: VerifiedTransaction> {
+	/// The transaction has been added to the pool.
+	Added(Arc<T>),
+	/// The transaction has taken place of another transaction from the same sender.
+	Replaced {
+		/// The transaction that was removed.
+		old: Arc<T>,
+		/// The transaction that was added.
+		new: Arc<T>,
+	},
+	/// The transaction was rejected from the pool.
+	Rejected {
+		/// The rejected transaction.
+		transaction: Arc<T>,
+		/// Description of the rejection error.
+		reason: String,
+	},
+	/// The transaction was pushed out from the pool because of the limits.
+	Dropped(Arc<T>),
+	/// The transaction was marked as invalid by executor.
+	Invalid(Arc<T>),
+	/// The transaction has been canceled.
+	Canceled(Arc<T>),
+	/// The transaction has been culled from the pool, usually because it was mined.
+	Culled(Arc<T>),
+	/// The transaction hash or sender has been banned.
+	Banned(Ban<T::Hash, T::Sender>),
+	/// The ban has expired or has been lifted.
+	Unbanned(Ban<T::Hash, T::Sender>),
+}
+
+impl<T: VerifiedTransaction> Clone for Event<T> {
+	fn clone(&self) -> Self {
+		match self {
+			Event::Added(tx) => Event::Added(tx.clone()),
+			Event::Replaced { old, new } => Event::Replaced { old: old.clone(), new: new.clone() },
+			Event::Rejected { transaction, reason } => {
+				Event::Rejected { transaction: transaction.clone(), reason: reason.clone() }
+			}
+			Event::Dropped(tx) => Event::Dropped(tx.clone()),
+			Event::Invalid(tx) => Event::Invalid(tx.clone()),
+			Event::Canceled(tx) => Event::Canceled(tx.clone()),
+			Event::Culled(tx) => Event::Culled(tx.clone()),
+			Event::Banned(ban) => Event::Banned(ban.clone()),
+			Event::Unbanned(ban) => Event::Unbanned(ban.clone()),
+		}
+	}
+}
+
+/// A `Listener` broadcasting every notification as an `Event` to all subscribed streams.
+///
+/// Can be combined with other listeners as `(EventListener<T>, L)`.
+/// Subscribers whose `EventStream` has been dropped are removed on the next event.
+///
+/// Every subscriber buffers at most `capacity` events. A subscriber lagging further behind is
+/// disconnected: its stream yields the buffered events and then ends, so that a stalled
+/// subscriber can't make the pool's memory grow without bound.
+#[derive(Debug)]
+pub struct EventListener<T: VerifiedTransaction> {
+	subscribers: Vec<mpsc::SyncSender<Event<T>>>,
+	capacity: usize,
+}
+
+impl<T: VerifiedTransaction> Default for EventListener<T> {
+	fn default() -> Self {
+		Self::with_capacity(DEFAULT_EVENT_CAPACITY)
+	}
+}
+
+impl<T: VerifiedTransaction> EventListener<T> {
+	/// Creates a listener without subscribers, buffering up to `DEFAULT_EVENT_CAPACITY` events per subscriber.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Creates a listener without subscribers, buffering up to `capacity` events per subscriber.
+	pub fn with_capacity(capacity: usize) -> Self {
+		EventListener { subscribers: Vec::new(), capacity }
+	}
+
+	/// Returns a new stream receiving all events from now on.
+	pub fn subscribe(&mut self) -> EventStream<T> {
+		let (sender, receiver) = mpsc::sync_channel(self.capacity);
+		self.subscribers.push(sender);
+		EventStream { receiver }
+	}
+
+	/// Returns the number of live subscribers.
+	pub fn subscribers(&self) -> usize {
+		self.subscribers.len()
+	}
+
+	fn notify(&mut self, event: Event<T>) {
+		// full subscribers are disconnected rather than waited for
+		self.subscribers.retain(|subscriber| subscriber.try_send(event.clone()).is_ok());
+	}
+}
+
+impl<T: VerifiedTransaction> Listener<T> for EventListener<T> {
+	fn added(&mut self, tx: &Arc<T>, old: Option<&Arc<T>>) {
+		let event = match old {
+			Some(old) => Event::Replaced { old: old.clone(), new: tx.clone() },
+			None => Event::Added(tx.clone()),
+		};
+		self.notify(event);
+	}
+
+	fn rejected<H: Debug + LowerHex>(&mut self, tx: &Arc<T>, reason: &Error<H>) {
+		self.notify(Event::Rejected { transaction: tx.clone(), reason: reason.to_string() });
+	}
+
+	fn dropped(&mut self, tx: &Arc<T>, _by: Option<&T>) {
+		self.notify(Event::Dropped(tx.clone()));
+	}
+
+	fn invalid(&mut self, tx: &Arc<T>) {
+		self.notify(Event::Invalid(tx.clone()));
+	}
+
+	fn canceled(&mut self, tx: &Arc<T>) {
+		self.notify(Event::Canceled(tx.clone()));
+	}
+
+	fn culled(&mut self, tx: &Arc<T>) {
+		self.notify(Event::Culled(tx.clone()));
+	}
+
+	fn banned(&mut self, ban: &Ban<T::Hash, T::Sender>) {
+		self.notify(Event::Banned(ban.clone()));
+	}
+
+	fn unbanned(&mut self, ban: &Ban<T::Hash, T::Sender>) {
+		self.notify(Event::Unbanned(ban.clone()));
+	}
+}
+
+/// Receiving end of an `EventListener` subscription.
+///
+/// Iterating blocks until the next event arrives and finishes when the listener is dropped or
+/// the subscriber has been disconnected for lagging behind.
+#[derive(Debug)]
+pub struct EventStream<T: VerifiedTransaction> {
+	receiver: mpsc::Receiver<Event<T>>,
+}
+
+impl<T: VerifiedTransaction> EventStream<T> {
+	/// Returns the next event if there is one, without blocking.
+	pub fn try_next(&self) -> Option<Event<T>> {
+		self.receiver.try_recv().ok()
+	}
+
+	/// Returns an iterator over the events received so far, without blocking.
+	pub fn try_iter(&self) -> impl Iterator<Item = Event<T>> + '_ {
+		self.receiver.try_iter()
+	}
+}
+
+impl<T: VerifiedTransaction> Iterator for EventStream<T> {
+	type Item = Event<T>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		self.receiver.recv().ok()
+	}
+}
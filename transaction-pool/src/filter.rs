@@ -0,0 +1,40 @@
+// Copyright 2015-2019 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashSet;
+use std::hash::Hash;
+
+/// Restricts the transactions returned by `Pool::pending_filtered`.
+///
+/// The default filter doesn't restrict anything.
+#[derive(Debug, Clone)]
+pub struct PendingFilter<Sender: Eq + Hash, Score> {
+	/// Only return transactions from given senders.
+	pub senders: Option<HashSet<Sender>>,
+	/// Stop at the first ready transaction with a lower score.
+	/// Since transactions are returned by score, no later transaction could pass the threshold.
+	pub min_score: Option<Score>,
+	/// Number of ready transactions to skip.
+	pub offset: usize,
+	/// Maximal number of transactions to return.
+	pub limit: Option<usize>,
+}
+
+impl<Sender: Eq + Hash, Score> Default for PendingFilter<Sender, Score> {
+	fn default() -> Self {
+		PendingFilter { senders: None, min_score: None, offset: 0, limit: None }
+	}
+}
@@ -0,0 +1,309 @@
+// Copyright 2015-2019 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Parking of transactions with nonce gaps.
+//!
+//! The `Pool` makes no assumption about nonces. For transactions which do have sequential nonces,
+//! the `FutureQueue` keeps the ones that can't be ready yet out of the pool, and moves them to
+//! the pool as soon as the transactions filling the gap are imported.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::Arc;
+
+use log::trace;
+
+use crate::{
+	error::{Error, Result},
+	listener::Listener,
+	pool::Pool,
+	replace::ShouldReplace,
+	scoring::{self, Scoring},
+	VerifiedTransaction,
+};
+
+/// A transaction with a sequential nonce.
+pub trait NoncedTransaction: VerifiedTransaction {
+	/// Nonce type.
+	type Nonce: fmt::Debug + Ord + Clone;
+
+	/// Transaction nonce.
+	fn nonce(&self) -> Self::Nonce;
+
+	/// The nonce of the next transaction from the same sender, usually `nonce + 1`.
+	fn next_nonce(&self) -> Self::Nonce;
+}
+
+/// Limits of the `FutureQueue`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FutureOptions {
+	/// Maximal number of parked transactions.
+	pub max_count: usize,
+	/// Maximal number of parked transactions from single sender.
+	pub max_per_sender: usize,
+}
+
+impl Default for FutureOptions {
+	fn default() -> Self {
+		FutureOptions { max_count: 1024, max_per_sender: 16 }
+	}
+}
+
+/// Outcome of `FutureQueue::import`.
+#[derive(Debug)]
+pub enum Imported<T> {
+	/// The transaction was imported to the pool, followed by the parked transactions it unlocked.
+	Ready {
+		/// The imported transaction.
+		transaction: Arc<T>,
+		/// Parked transactions moved to the pool, in nonce order.
+		promoted: Vec<Arc<T>>,
+	},
+	/// The transaction has a nonce gap and was parked.
+	Future,
+}
+
+/// Transactions which can't be ready until transactions with lower nonces are imported.
+///
+/// Parked transactions are not part of the `Pool`, so the `Listener` is only notified about
+/// transactions the queue rejects, and about parked transactions once they are promoted.
+#[derive(Debug)]
+pub struct FutureQueue<T: NoncedTransaction> {
+	options: FutureOptions,
+	by_sender: HashMap<T::Sender, BTreeMap<T::Nonce, T>>,
+	by_hash: HashMap<T::Hash, (T::Sender, T::Nonce)>,
+}
+
+impl<T: NoncedTransaction> Default for FutureQueue<T> {
+	fn default() -> Self {
+		Self::new(FutureOptions::default())
+	}
+}
+
+impl<T: NoncedTransaction> FutureQueue<T> {
+	/// Creates an empty queue with given limits.
+	pub fn new(options: FutureOptions) -> Self {
+		FutureQueue { options, by_sender: HashMap::new(), by_hash: HashMap::new() }
+	}
+
+	/// Imports a transaction to the pool if it continues the sender's transactions, or parks it.
+	///
+	/// `state_nonce` is the nonce of the next transaction of the sender to be included in a block.
+	/// The transaction goes to the pool if its nonce isn't above the nonce following the sender's
+	/// transactions in the pool, which makes it ready or a replacement. Parked transactions it
+	/// unlocks are promoted to the pool with it.
+	///
+	/// A transaction with a nonce below `state_nonce` is rejected as stale. A parked transaction
+	/// with the same nonce is replaced if the pool's `ReplacementPolicy`, or its `Scoring` without
+	/// one, prefers the new one. Transactions which can't be parked are reported to the `Listener`.
+	pub fn import<S, L>(
+		&mut self,
+		pool: &mut Pool<T, S, L>,
+		transaction: T,
+		state_nonce: T::Nonce,
+		replace: &dyn ShouldReplace<T>,
+	) -> Result<Imported<T>, T::Hash>
+	where
+		S: Scoring<T>,
+		L: Listener<T>,
+	{
+		if self.by_hash.contains_key(transaction.hash()) {
+			return Err(Error::AlreadyImported(transaction.hash().clone()));
+		}
+		let nonce = transaction.nonce();
+		if nonce < state_nonce {
+			return Err(Error::StaleNonce(transaction.hash().clone()));
+		}
+
+		let sender = transaction.sender().clone();
+		if nonce <= Self::next_ready_nonce(pool, &sender, state_nonce.clone()) {
+			let transaction = pool.import(transaction, replace)?;
+			let promoted = self.promote(pool, &sender, state_nonce, replace);
+			return Ok(Imported::Ready { transaction, promoted });
+		}
+
+		self.park(pool, transaction)?;
+		Ok(Imported::Future)
+	}
+
+	/// Moves the parked transactions of a sender which became ready to the pool.
+	///
+	/// Call it when the state nonce of the sender changed, or after transactions were added to
+	/// the pool without going through `import`. Parked transactions below `state_nonce` are dropped.
+	///
+	/// A transaction the pool rejects is reported to the `Listener`, and the ones following it stay
+	/// parked.
+	pub fn promote<S, L>(
+		&mut self,
+		pool: &mut Pool<T, S, L>,
+		sender: &T::Sender,
+		state_nonce: T::Nonce,
+		replace: &dyn ShouldReplace<T>,
+	) -> Vec<Arc<T>>
+	where
+		S: Scoring<T>,
+		L: Listener<T>,
+	{
+		let mut promoted = Vec::new();
+		let parked = match self.by_sender.get_mut(sender) {
+			Some(parked) => parked,
+			None => return promoted,
+		};
+
+		let stale = parked.range(..state_nonce.clone()).map(|(nonce, _)| nonce.clone()).collect::<Vec<_>>();
+		for nonce in stale {
+			let tx = parked.remove(&nonce).expect("nonce collected from the map; qed");
+			trace!("[{:?}] Dropping stale future transaction", tx.hash());
+			self.by_hash.remove(tx.hash());
+		}
+
+		let mut next = Self::next_ready_nonce(pool, sender, state_nonce);
+		while let Some(tx) = parked.remove(&next) {
+			self.by_hash.remove(tx.hash());
+			next = tx.next_nonce();
+			let hash = tx.hash().clone();
+			// `Pool::import` reports the other rejections itself
+			if let Err(err) = pool.check_import(&tx) {
+				trace!("[{:?}] Failed to promote future transaction: {}", hash, err);
+				pool.listener_mut().rejected(&Arc::new(tx), &err);
+				break;
+			}
+			match pool.import(tx, replace) {
+				Ok(tx) => promoted.push(tx),
+				Err(err) => {
+					trace!("[{:?}] Failed to promote future transaction: {}", hash, err);
+					break;
+				}
+			}
+		}
+
+		if parked.is_empty() {
+			self.by_sender.remove(sender);
+		}
+		promoted
+	}
+
+	/// Removes a parked transaction.
+	pub fn remove(&mut self, hash: &T::Hash) -> Option<T> {
+		let (sender, nonce) = self.by_hash.remove(hash)?;
+		let parked = self.by_sender.get_mut(&sender).expect("by_hash and by_sender are in sync; qed");
+		let tx = parked.remove(&nonce);
+		if parked.is_empty() {
+			self.by_sender.remove(&sender);
+		}
+		tx
+	}
+
+	/// Returns true if the transaction is parked.
+	pub fn contains(&self, hash: &T::Hash) -> bool {
+		self.by_hash.contains_key(hash)
+	}
+
+	/// Returns the parked transactions from given sender, in nonce order.
+	pub fn from_sender(&self, sender: &T::Sender) -> impl DoubleEndedIterator<Item = &T> {
+		self.by_sender.get(sender).into_iter().flat_map(|parked| parked.values())
+	}
+
+	/// Number of parked transactions.
+	pub fn len(&self) -> usize {
+		self.by_hash.len()
+	}
+
+	/// Returns true if no transaction is parked.
+	pub fn is_empty(&self) -> bool {
+		self.by_hash.is_empty()
+	}
+
+	/// Removes all parked transactions.
+	pub fn clear(&mut self) {
+		self.by_sender.clear();
+		self.by_hash.clear();
+	}
+
+	/// The nonce following the contiguous transactions of the sender in the pool, from `state_nonce`.
+	fn next_ready_nonce<S, L>(pool: &Pool<T, S, L>, sender: &T::Sender, state_nonce: T::Nonce) -> T::Nonce
+	where
+		S: Scoring<T>,
+		L: Listener<T>,
+	{
+		let mut txs = pool.sender_transactions(sender).map(|tx| (tx.nonce(), tx.next_nonce())).collect::<Vec<_>>();
+		txs.sort();
+		let mut next = state_nonce;
+		for (nonce, after) in txs {
+			if nonce == next {
+				next = after;
+			} else if nonce > next {
+				break;
+			}
+		}
+		next
+	}
+
+	fn park<S, L>(&mut self, pool: &mut Pool<T, S, L>, transaction: T) -> Result<(), T::Hash>
+	where
+		S: Scoring<T>,
+		L: Listener<T>,
+	{
+		if let Err(err) = self.make_room(pool, &transaction) {
+			pool.listener_mut().rejected(&Arc::new(transaction), &err);
+			return Err(err);
+		}
+		let sender = transaction.sender().clone();
+		let nonce = transaction.nonce();
+		self.by_hash.insert(transaction.hash().clone(), (sender.clone(), nonce.clone()));
+		self.by_sender.entry(sender).or_default().insert(nonce, transaction);
+		Ok(())
+	}
+
+	/// Removes the parked transaction replaced by `transaction`, or the one it pushes out of a full sender.
+	fn make_room<S, L>(&mut self, pool: &Pool<T, S, L>, transaction: &T) -> Result<(), T::Hash>
+	where
+		S: Scoring<T>,
+		L: Listener<T>,
+	{
+		let sender = transaction.sender();
+		let nonce = transaction.nonce();
+
+		if let Some(old) = self.by_sender.get(sender).and_then(|parked| parked.get(&nonce)) {
+			let choice = match pool.replacement_policy() {
+				Some(replacement) => replacement.choose(old, transaction),
+				None => pool.scoring().choose(old, transaction),
+			};
+			if let scoring::Choice::RejectNew = choice {
+				return Err(Error::TooCheapToReplace(old.hash().clone(), transaction.hash().clone()));
+			}
+			let hash = old.hash().clone();
+			self.remove(&hash);
+			return Ok(());
+		}
+
+		let parked = self.from_sender(sender).count();
+		if parked >= self.options.max_per_sender {
+			// A full sender keeps its lowest nonces, which are the closest to being ready.
+			let highest = self.from_sender(sender).next_back().map(|tx| (tx.nonce(), tx.hash().clone()));
+			match highest {
+				Some((highest, hash)) if nonce < highest => {
+					trace!("[{:?}] Dropping future transaction with the highest nonce", hash);
+					self.remove(&hash);
+				}
+				_ => return Err(Error::FutureQueueFull(transaction.hash().clone())),
+			}
+		} else if self.by_hash.len() >= self.options.max_count {
+			return Err(Error::FutureQueueFull(transaction.hash().clone()));
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,145 @@
+// Copyright 2015-2019 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Persistence of pool contents in a `KeyValueDB`.
+//!
+//! `JournalListener` writes every change of the pool to a database column, keyed by transaction hash.
+//! After a restart, `Journal::load` reads the stored transactions back and passes them through
+//! a `Verifier`, so that they can be imported again.
+
+use std::fmt::{Debug, LowerHex};
+use std::io;
+use std::sync::Arc;
+
+use kvdb::{DBTransaction, KeyValueDB};
+use log::warn;
+
+use crate::{error::Error, listener::Listener, verifier::Verifier, VerifiedTransaction};
+
+/// Pool contents stored in a single database column.
+#[derive(Clone)]
+pub struct Journal {
+	db: Arc<dyn KeyValueDB>,
+	column: u32,
+}
+
+impl Journal {
+	/// Creates a journal stored in given column of the database.
+	pub fn new(db: Arc<dyn KeyValueDB>, column: u32) -> Self {
+		Journal { db, column }
+	}
+
+	/// Reads all stored transactions and verifies them again.
+	///
+	/// Entries that can't be decoded or fail the verification are removed from the journal.
+	/// The returned transactions are meant to be imported to the pool, which journals them again.
+	pub fn load<U, V, F>(&self, decode: F, verifier: &V) -> io::Result<Vec<V::VerifiedTransaction>>
+	where
+		V: Verifier<U>,
+		F: Fn(&[u8]) -> Option<U>,
+	{
+		let mut verified = Vec::new();
+		let mut invalid = DBTransaction::new();
+		for (key, value) in self.db.iter(self.column) {
+			match decode(&value).map(|tx| verifier.verify_transaction(tx)) {
+				Some(Ok(tx)) => verified.push(tx),
+				_ => invalid.delete(self.column, &key),
+			}
+		}
+		self.db.write(invalid)?;
+		Ok(verified)
+	}
+
+	/// Removes all stored transactions.
+	pub fn clear(&self) -> io::Result<()> {
+		let mut transaction = DBTransaction::new();
+		for (key, _) in self.db.iter(self.column) {
+			transaction.delete(self.column, &key);
+		}
+		self.db.write(transaction).map(|_| ())
+	}
+
+	fn write(&self, transaction: DBTransaction) {
+		if let Err(err) = self.db.write(transaction) {
+			warn!("Failed to write transaction pool journal: {}", err);
+		}
+	}
+
+	fn remove<T>(&self, tx: &T)
+	where
+		T: VerifiedTransaction,
+		T::Hash: AsRef<[u8]>,
+	{
+		let mut transaction = DBTransaction::new();
+		transaction.delete(self.column, tx.hash().as_ref());
+		self.write(transaction);
+	}
+}
+
+/// A `Listener` journaling every change of the pool.
+///
+/// Transactions are serialized with the supplied `encode` function. Write errors are logged,
+/// since they can't fail the pool operation; combine with other listeners as `(JournalListener<E>, L)`.
+pub struct JournalListener<E> {
+	journal: Journal,
+	encode: E,
+}
+
+impl<E> JournalListener<E> {
+	/// Creates a listener writing to given journal.
+	pub fn new(journal: Journal, encode: E) -> Self {
+		JournalListener { journal, encode }
+	}
+
+	/// Borrows the journal.
+	pub fn journal(&self) -> &Journal {
+		&self.journal
+	}
+}
+
+impl<T, E> Listener<T> for JournalListener<E>
+where
+	T: VerifiedTransaction,
+	T::Hash: AsRef<[u8]>,
+	E: Fn(&T) -> Vec<u8>,
+{
+	fn added(&mut self, tx: &Arc<T>, old: Option<&Arc<T>>) {
+		let mut transaction = DBTransaction::new();
+		if let Some(old) = old {
+			transaction.delete(self.journal.column, old.hash().as_ref());
+		}
+		transaction.put(self.journal.column, tx.hash().as_ref(), &(self.encode)(tx));
+		self.journal.write(transaction);
+	}
+
+	fn rejected<H: Debug + LowerHex>(&mut self, _tx: &Arc<T>, _reason: &Error<H>) {}
+
+	fn dropped(&mut self, tx: &Arc<T>, _by: Option<&T>) {
+		self.journal.remove(&**tx);
+	}
+
+	fn invalid(&mut self, tx: &Arc<T>) {
+		self.journal.remove(&**tx);
+	}
+
+	fn canceled(&mut self, tx: &Arc<T>) {
+		self.journal.remove(&**tx);
+	}
+
+	fn culled(&mut self, tx: &Arc<T>) {
+		self.journal.remove(&**tx);
+	}
+}
@@ -57,9 +57,11 @@
 //!    and the worst transaction.
 //! 3. Whenever new transaction is inserted to the queue:
 //!    - first check all the limits (overall, memory, per-sender)
+//!    - if a limit is reached evict a transaction chosen by the `EvictionPolicy`
 //!    - retrieve all transactions from a sender
 //!    - binary search for position to insert the transaction
-//!    - decide if we are replacing existing transaction (3 outcomes: drop, replace, insert)
+//!    - decide if we are replacing existing transaction (3 outcomes: drop, replace, insert),
+//!      consulting the `ReplacementPolicy` (if any) when the transaction occupies an existing slot
 //!    - update best and worst transaction from that sender if affected
 //! 4. Pending List construction:
 //!    - Take the best transaction (by priority) from all senders to the List
@@ -71,7 +73,13 @@
 #[cfg(test)]
 mod tests;
 
+mod ban;
 mod error;
+mod events;
+mod filter;
+mod future;
+#[cfg(feature = "persistence")]
+mod journal;
 mod listener;
 mod options;
 mod pool;
@@ -83,14 +91,20 @@ mod verifier;
 
 pub mod scoring;
 
+pub use self::ban::{Ban, BanList, BanOptions};
 pub use self::error::Error;
+pub use self::events::{Event, EventListener, EventStream, DEFAULT_EVENT_CAPACITY};
+pub use self::filter::PendingFilter;
+pub use self::future::{FutureOptions, FutureQueue, Imported, NoncedTransaction};
+#[cfg(feature = "persistence")]
+pub use self::journal::{Journal, JournalListener};
 pub use self::listener::{Listener, NoopListener};
-pub use self::options::Options;
+pub use self::options::{EvictionPolicy, Options};
 pub use self::pool::{PendingIterator, Pool, Transaction, UnorderedIterator};
 pub use self::ready::{Readiness, Ready};
-pub use self::replace::{ReplaceTransaction, ShouldReplace};
+pub use self::replace::{PercentageBump, ReplaceTransaction, ReplacementPolicy, ShouldReplace};
 pub use self::scoring::Scoring;
-pub use self::status::{LightStatus, Status};
+pub use self::status::{DetailedStatus, LightStatus, Status};
 pub use self::verifier::Verifier;
 
 use std::fmt;
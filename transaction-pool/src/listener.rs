@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-use crate::error::Error;
+use crate::{ban::Ban, error::Error, VerifiedTransaction};
 use std::{
 	fmt::{Debug, LowerHex},
 	sync::Arc,
@@ -45,6 +45,20 @@ pub trait Listener<T> {
 
 	/// The transaction has been culled from the pool.
 	fn culled(&mut self, _tx: &Arc<T>) {}
+
+	/// The transaction hash or sender has been banned, see `BanList`.
+	fn banned(&mut self, _ban: &Ban<T::Hash, T::Sender>)
+	where
+		T: VerifiedTransaction,
+	{
+	}
+
+	/// The ban has expired or has been lifted.
+	fn unbanned(&mut self, _ban: &Ban<T::Hash, T::Sender>)
+	where
+		T: VerifiedTransaction,
+	{
+	}
 }
 
 /// A no-op implementation of `Listener`.
@@ -86,4 +100,20 @@ where
 		self.0.culled(tx);
 		self.1.culled(tx);
 	}
+
+	fn banned(&mut self, ban: &Ban<T::Hash, T::Sender>)
+	where
+		T: VerifiedTransaction,
+	{
+		self.0.banned(ban);
+		self.1.banned(ban);
+	}
+
+	fn unbanned(&mut self, ban: &Ban<T::Hash, T::Sender>)
+	where
+		T: VerifiedTransaction,
+	{
+		self.0.unbanned(ban);
+		self.1.unbanned(ban);
+	}
 }
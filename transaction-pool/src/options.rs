@@ -14,6 +14,8 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::time::Duration;
+
 /// Transaction Pool options.
 #[derive(Clone, Debug, PartialEq)]
 pub struct Options {
@@ -23,10 +25,42 @@ pub struct Options {
 	pub max_per_sender: usize,
 	/// Maximal memory usage.
 	pub max_mem_usage: usize,
+	/// Which transaction to push out when `max_count` or `max_mem_usage` is reached.
+	pub eviction: EvictionPolicy,
+	/// Maximal time a transaction can stay in the pool.
+	/// Older transactions are culled whenever a new transaction is imported.
+	pub max_age: Option<Duration>,
 }
 
 impl Default for Options {
 	fn default() -> Self {
-		Options { max_count: 1024, max_per_sender: 16, max_mem_usage: 8 * 1024 * 1024 }
+		Options {
+			max_count: 1024,
+			max_per_sender: 16,
+			max_mem_usage: 8 * 1024 * 1024,
+			eviction: EvictionPolicy::default(),
+			max_age: None,
+		}
+	}
+}
+
+/// Chooses the eviction candidate when the pool limits are reached.
+///
+/// Only the last transaction of each sender (by `Scoring` order) is a candidate,
+/// so evicting never leaves a gap in a sender's transactions.
+/// The candidate is still subject to the `ShouldReplace` decision.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EvictionPolicy {
+	/// Evict the transaction with the lowest score.
+	LowestScore,
+	/// Evict the transaction that has been in the pool the longest.
+	Oldest,
+	/// Evict the transaction with the highest memory usage.
+	Largest,
+}
+
+impl Default for EvictionPolicy {
+	fn default() -> Self {
+		EvictionPolicy::LowestScore
 	}
 }
@@ -15,18 +15,21 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use log::{trace, warn};
-use std::collections::{hash_map, BTreeSet, HashMap};
+use std::collections::{hash_map, BTreeMap, BTreeSet, HashMap};
 use std::slice;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use crate::{
+	ban::{Ban, BanList, BanOptions},
 	error,
+	filter::PendingFilter,
 	listener::{Listener, NoopListener},
-	options::Options,
+	options::{EvictionPolicy, Options},
 	ready::{Readiness, Ready},
-	replace::{ReplaceTransaction, ShouldReplace},
+	replace::{ReplaceTransaction, ReplacementPolicy, ShouldReplace},
 	scoring::{self, ScoreWithRef, Scoring},
-	status::{LightStatus, Status},
+	status::{DetailedStatus, LightStatus, Status},
 	transactions::{AddResult, Transactions},
 	VerifiedTransaction,
 };
@@ -40,13 +43,19 @@ use crate::{
 pub struct Transaction<T> {
 	/// Sequential id of the transaction
 	pub insertion_id: u64,
+	/// Time the transaction was imported to the pool
+	pub imported_at: Instant,
 	/// Shared transaction
 	pub transaction: Arc<T>,
 }
 
 impl<T> Clone for Transaction<T> {
 	fn clone(&self) -> Self {
-		Transaction { insertion_id: self.insertion_id, transaction: self.transaction.clone() }
+		Transaction {
+			insertion_id: self.insertion_id,
+			imported_at: self.imported_at,
+			transaction: self.transaction.clone(),
+		}
 	}
 }
 
@@ -63,11 +72,15 @@ impl<T> ::std::ops::Deref for Transaction<T> {
 pub struct Pool<T: VerifiedTransaction, S: Scoring<T>, L = NoopListener> {
 	listener: L,
 	scoring: S,
+	replacement: Option<Box<dyn ReplacementPolicy<T> + Send + Sync>>,
+	bans: Option<BanList<T::Hash, T::Sender>>,
 	options: Options,
 	mem_usage: usize,
 
 	transactions: HashMap<T::Sender, Transactions<T, S>>,
 	by_hash: HashMap<T::Hash, Transaction<T>>,
+	/// Hashes by `insertion_id`, i.e. from the oldest to the newest.
+	by_insertion_id: BTreeMap<u64, T::Hash>,
 
 	best_transactions: BTreeSet<ScoreWithRef<T, S::Score>>,
 	worst_transactions: BTreeSet<ScoreWithRef<T, S::Score>>,
@@ -112,16 +125,64 @@ where
 		Pool {
 			listener,
 			scoring,
+			replacement: None,
+			bans: None,
 			options,
 			mem_usage: 0,
 			transactions,
 			by_hash,
+			by_insertion_id: Default::default(),
 			best_transactions: Default::default(),
 			worst_transactions: Default::default(),
 			insertion_id: 0,
 		}
 	}
 
+	/// Sets the policy deciding if a transaction replaces the pooled one occupying the same slot.
+	///
+	/// Without a policy, `Scoring::choose` decides.
+	pub fn set_replacement_policy<P: ReplacementPolicy<T> + Send + Sync + 'static>(&mut self, policy: P) {
+		self.replacement = Some(Box::new(policy));
+	}
+
+	/// Enables banning of invalid transactions, see `BanList`.
+	///
+	/// Transactions removed from the pool as invalid are banned, and imports of banned transactions
+	/// fail with `Error::Banned`. Replaces the current ban list, if any, without notifying the listener.
+	pub fn set_ban_list(&mut self, options: BanOptions) {
+		self.bans = Some(BanList::new(options));
+	}
+
+	/// Borrows the ban list, if banning is enabled.
+	pub fn ban_list(&self) -> Option<&BanList<T::Hash, T::Sender>> {
+		self.bans.as_ref()
+	}
+
+	/// Bans a transaction hash or sender, e.g. after the transaction failed verification.
+	///
+	/// Does nothing if banning is not enabled.
+	pub fn ban(&mut self, ban: Ban<T::Hash, T::Sender>) {
+		if let Some(ref mut bans) = self.bans {
+			let lifted = bans.ban(ban.clone(), Instant::now());
+			self.listener.banned(&ban);
+			if let Some(lifted) = lifted {
+				self.listener.unbanned(&lifted);
+			}
+		}
+	}
+
+	/// Lifts a ban. Returns false if it's not banned.
+	pub fn unban(&mut self, ban: &Ban<T::Hash, T::Sender>) -> bool {
+		let unbanned = match self.bans {
+			Some(ref mut bans) => bans.unban(ban),
+			None => false,
+		};
+		if unbanned {
+			self.listener.unbanned(ban);
+		}
+		unbanned
+	}
+
 	/// Attempts to import new transaction to the pool, returns a `Arc<T>` or an `Error`.
 	///
 	/// NOTE: Since `Ready`ness is separate from the pool it's possible to import stalled transactions.
@@ -131,19 +192,30 @@ where
 	/// either because of limits (see `Options`) or because `Scoring` decides that the transaction
 	/// replaces an existing transaction from that sender.
 	///
-	/// If any limit is reached the transaction with the lowest `Score` will be compared with the
+	/// If any limit is reached the eviction candidate chosen by the configured `EvictionPolicy`
+	/// (by default the transaction with the lowest `Score`) will be compared with the
 	/// new transaction via the supplied `ShouldReplace` implementation and may be evicted.
 	///
+	/// If `Options::max_age` is set, transactions older than that are culled first.
+	///
+	/// If banning is enabled, expired bans are lifted and banned transactions are rejected.
+	///
 	/// The `Listener` will be informed on any drops or rejections.
 	pub fn import(&mut self, transaction: T, replace: &dyn ShouldReplace<T>) -> error::Result<Arc<T>, T::Hash> {
 		let mem_usage = transaction.mem_usage();
 
-		if self.by_hash.contains_key(transaction.hash()) {
-			return Err(error::Error::AlreadyImported(transaction.hash().clone()));
+		self.check_import(&transaction)?;
+
+		if let Some(max_age) = self.options.max_age {
+			self.cull_older_than(max_age);
 		}
 
 		self.insertion_id += 1;
-		let transaction = Transaction { insertion_id: self.insertion_id, transaction: Arc::new(transaction) };
+		let transaction = Transaction {
+			insertion_id: self.insertion_id,
+			imported_at: Instant::now(),
+			transaction: Arc::new(transaction),
+		};
 
 		// TODO [ToDr] Most likely move this after the transaction is inserted.
 		// Avoid using should_replace, but rather use scoring for that.
@@ -181,7 +253,12 @@ where
 				self.transactions.entry(transaction.sender().clone()).or_insert_with(Transactions::default);
 			// get worst and best transactions for comparison
 			let prev = transactions.worst_and_best();
-			let result = transactions.add(transaction, &self.scoring, self.options.max_per_sender);
+			let result = transactions.add(
+				transaction,
+				&self.scoring,
+				self.replacement.as_ref().map(|replacement| &**replacement as _),
+				self.options.max_per_sender,
+			);
 			let current = transactions.worst_and_best();
 			(result, prev, current)
 		};
@@ -213,10 +290,31 @@ where
 		}
 	}
 
+	/// Checks that the transaction isn't imported already or banned, lifting expired bans.
+	///
+	/// The `Listener` isn't notified about these rejections.
+	pub(crate) fn check_import(&mut self, transaction: &T) -> error::Result<(), T::Hash> {
+		if self.by_hash.contains_key(transaction.hash()) {
+			return Err(error::Error::AlreadyImported(transaction.hash().clone()));
+		}
+
+		if let Some(ref mut bans) = self.bans {
+			let now = Instant::now();
+			for ban in bans.expire(now) {
+				self.listener.unbanned(&ban);
+			}
+			if bans.is_transaction_banned(transaction.hash(), transaction.sender(), now) {
+				return Err(error::Error::Banned(transaction.hash().clone()));
+			}
+		}
+		Ok(())
+	}
+
 	/// Updates state of the pool statistics if the transaction was added to a set.
 	fn finalize_insert(&mut self, new: &Transaction<T>, old: Option<&Transaction<T>>) {
 		self.mem_usage += new.mem_usage();
 		self.by_hash.insert(new.hash().clone(), new.clone());
+		self.by_insertion_id.insert(new.insertion_id, new.hash().clone());
 
 		if let Some(old) = old {
 			self.finalize_remove(old.hash());
@@ -226,6 +324,7 @@ where
 	/// Updates the pool statistics if transaction was removed.
 	fn finalize_remove(&mut self, hash: &T::Hash) -> Option<Arc<T>> {
 		self.by_hash.remove(hash).map(|old| {
+			self.by_insertion_id.remove(&old.insertion_id);
 			self.mem_usage -= old.transaction.mem_usage();
 			old.transaction
 		})
@@ -277,16 +376,26 @@ where
 		}
 	}
 
-	/// Attempts to remove the worst transaction from the pool if it's worse than the given one.
+	/// Returns the next transaction to evict according to `EvictionPolicy`.
+	fn eviction_candidate(&self) -> Option<&ScoreWithRef<T, S::Score>> {
+		let mut candidates = self.worst_transactions.iter();
+		match self.options.eviction {
+			EvictionPolicy::LowestScore => candidates.next_back(),
+			EvictionPolicy::Oldest => candidates.min_by_key(|tx| tx.transaction.insertion_id),
+			EvictionPolicy::Largest => candidates.max_by_key(|tx| tx.transaction.mem_usage()),
+		}
+	}
+
+	/// Attempts to remove the eviction candidate from the pool if it's worse than the given one.
 	///
-	/// Returns `None` in case we couldn't decide if the transaction should replace the worst transaction or not.
+	/// Returns `None` in case we couldn't decide if the transaction should replace the candidate or not.
 	/// In such case we will accept the transaction even though it is going to exceed the limit.
 	fn remove_worst(
 		&mut self,
 		transaction: &Transaction<T>,
 		replace: &dyn ShouldReplace<T>,
 	) -> error::Result<Option<Transaction<T>>, T::Hash> {
-		let to_remove = match self.worst_transactions.iter().next_back() {
+		let to_remove = match self.eviction_candidate() {
 			// No elements to remove? and the pool is still full?
 			None => {
 				warn!("The pool is full but there are no transactions to remove.");
@@ -352,6 +461,7 @@ where
 	/// NOTE: the drop-notification order will be arbitrary.
 	pub fn clear(&mut self) {
 		self.mem_usage = 0;
+		self.by_insertion_id.clear();
 		self.transactions.clear();
 		self.best_transactions.clear();
 		self.worst_transactions.clear();
@@ -364,11 +474,17 @@ where
 	/// Removes single transaction from the pool.
 	/// Depending on the `is_invalid` flag the listener
 	/// will either get a `cancelled` or `invalid` notification.
+	/// Invalid transactions are banned if banning is enabled.
 	pub fn remove(&mut self, hash: &T::Hash, is_invalid: bool) -> Option<Arc<T>> {
 		if let Some(tx) = self.finalize_remove(hash) {
 			self.remove_from_set(tx.sender(), |set, scoring| set.remove(&tx, scoring));
 			if is_invalid {
 				self.listener.invalid(&tx);
+				let ban_senders = matches!(self.bans, Some(ref bans) if bans.options().ban_senders);
+				self.ban(Ban::Hash(tx.hash().clone()));
+				if ban_senders {
+					self.ban(Ban::Sender(tx.sender().clone()));
+				}
 			} else {
 				self.listener.canceled(&tx);
 			}
@@ -415,6 +531,35 @@ where
 		removed
 	}
 
+	/// Removes all transactions imported more than `age` ago.
+	///
+	/// NOTE: later transactions from the same sender are kept even if they can't become ready anymore,
+	/// they are going to be culled once they expire as well.
+	pub fn cull_older_than(&mut self, age: Duration) -> usize {
+		match Instant::now().checked_sub(age) {
+			Some(time) => self.cull_imported_before(time),
+			None => 0,
+		}
+	}
+
+	/// Removes all transactions imported before given time.
+	/// The listener gets a `culled` notification for each of them.
+	pub fn cull_imported_before(&mut self, time: Instant) -> usize {
+		let mut removed = 0;
+		while let Some(hash) = self.by_insertion_id.values().next().cloned() {
+			match self.by_hash.get(&hash) {
+				Some(tx) if tx.imported_at < time => {}
+				_ => break,
+			}
+			let tx = self.finalize_remove(&hash).expect("hash present in by_hash; qed");
+			self.remove_from_set(tx.sender(), |set, scoring| set.remove(&tx, scoring));
+			self.listener.culled(&tx);
+			removed += 1;
+		}
+
+		removed
+	}
+
 	/// Returns a transaction if it's part of the pool or `None` otherwise.
 	pub fn find(&self, hash: &T::Hash) -> Option<Arc<T>> {
 		self.by_hash.get(hash).map(|t| t.transaction.clone())
@@ -437,24 +582,41 @@ where
 
 	/// Returns an iterator of pending (ready) transactions.
 	pub fn pending<R: Ready<T>>(&self, ready: R) -> PendingIterator<'_, T, R, S, L> {
-		PendingIterator { ready, best_transactions: self.best_transactions.clone(), pool: self }
+		self.pending_filtered(ready, PendingFilter::default())
 	}
 
 	/// Returns pending (ready) transactions from given sender.
 	pub fn pending_from_sender<R: Ready<T>>(&self, ready: R, sender: &T::Sender) -> PendingIterator<'_, T, R, S, L> {
-		let best_transactions = self
-			.transactions
+		let best_transactions = self.best_from_sender(sender).into_iter().collect();
+		PendingIterator::new(ready, best_transactions, self, PendingFilter::default())
+	}
+
+	/// Returns an iterator of pending (ready) transactions matching given filter.
+	///
+	/// The filter is applied while constructing the pending set, so only transactions
+	/// from the requested senders are ever checked for readiness.
+	pub fn pending_filtered<R: Ready<T>>(
+		&self,
+		ready: R,
+		filter: PendingFilter<T::Sender, S::Score>,
+	) -> PendingIterator<'_, T, R, S, L> {
+		let best_transactions = match filter.senders {
+			Some(ref senders) => senders.iter().filter_map(|sender| self.best_from_sender(sender)).collect(),
+			None => self.best_transactions.clone(),
+		};
+		PendingIterator::new(ready, best_transactions, self, filter)
+	}
+
+	/// Returns the transactions from given sender, in `Scoring` order.
+	pub(crate) fn sender_transactions(&self, sender: &T::Sender) -> impl Iterator<Item = &Transaction<T>> {
+		self.transactions.get(sender).into_iter().flat_map(|transactions| transactions.iter())
+	}
+
+	fn best_from_sender(&self, sender: &T::Sender) -> Option<ScoreWithRef<T, S::Score>> {
+		self.transactions
 			.get(sender)
 			.and_then(|transactions| transactions.worst_and_best())
 			.map(|(_, best)| ScoreWithRef::new(best.0, best.1))
-			.map(|s| {
-				let mut set = BTreeSet::new();
-				set.insert(s);
-				set
-			})
-			.unwrap_or_default();
-
-		PendingIterator { ready, best_transactions, pool: self }
 	}
 
 	/// Returns unprioritized list of ready transactions.
@@ -508,6 +670,43 @@ where
 		}
 	}
 
+	/// Computes a detailed status of the pool.
+	///
+	/// Scores are counted in `score_buckets.len() + 1` buckets delimited by the given bounds,
+	/// which must be sorted in ascending order.
+	pub fn status_detailed(&self, score_buckets: &[S::Score]) -> DetailedStatus<T::Sender> {
+		let mut per_sender = HashMap::with_capacity(self.transactions.len());
+		let mut score_histogram = vec![0; score_buckets.len() + 1];
+		let mut oldest: Option<Instant> = None;
+		let mut newest: Option<Instant> = None;
+
+		for (sender, transactions) in &self.transactions {
+			per_sender.insert(sender.clone(), transactions.len());
+			for (tx, score) in transactions.iter_scored() {
+				let bucket = score_buckets.iter().take_while(|bound| *bound <= score).count();
+				score_histogram[bucket] += 1;
+				oldest = Some(oldest.map_or(tx.imported_at, |time| time.min(tx.imported_at)));
+				newest = Some(newest.map_or(tx.imported_at, |time| time.max(tx.imported_at)));
+			}
+		}
+
+		DetailedStatus {
+			mem_usage: self.mem_usage,
+			transaction_count: self.by_hash.len(),
+			per_sender,
+			score_histogram,
+			oldest,
+			newest,
+		}
+	}
+
+	/// Returns an unordered iterator over the hashes, senders and scores of all transactions in the pool.
+	pub fn scored_transactions(&self) -> impl Iterator<Item = (&T::Hash, &T::Sender, &S::Score)> {
+		self.transactions.iter().flat_map(|(sender, transactions)| {
+			transactions.iter_scored().map(move |(tx, score)| (tx.hash(), sender, score))
+		})
+	}
+
 	/// Returns current pool options.
 	pub fn options(&self) -> Options {
 		self.options.clone()
@@ -523,6 +722,11 @@ where
 		&self.scoring
 	}
 
+	/// Borrows the replacement policy, if one is set.
+	pub(crate) fn replacement_policy(&self) -> Option<&dyn ReplacementPolicy<T>> {
+		self.replacement.as_ref().map(|replacement| &**replacement as _)
+	}
+
 	/// Borrows listener mutably.
 	pub fn listener_mut(&mut self) -> &mut L {
 		&mut self.listener
@@ -586,6 +790,31 @@ where
 	ready: R,
 	best_transactions: BTreeSet<ScoreWithRef<T, S::Score>>,
 	pool: &'a Pool<T, S, L>,
+	min_score: Option<S::Score>,
+	skip: usize,
+	remaining: usize,
+}
+
+impl<'a, T, R, S, L> PendingIterator<'a, T, R, S, L>
+where
+	T: VerifiedTransaction,
+	S: Scoring<T>,
+{
+	fn new(
+		ready: R,
+		best_transactions: BTreeSet<ScoreWithRef<T, S::Score>>,
+		pool: &'a Pool<T, S, L>,
+		filter: PendingFilter<T::Sender, S::Score>,
+	) -> Self {
+		PendingIterator {
+			ready,
+			best_transactions,
+			pool,
+			min_score: filter.min_score,
+			skip: filter.offset,
+			remaining: filter.limit.unwrap_or(usize::max_value()),
+		}
+	}
 }
 
 impl<'a, T, R, S, L> Iterator for PendingIterator<'a, T, R, S, L>
@@ -597,6 +826,10 @@ where
 	type Item = Arc<T>;
 
 	fn next(&mut self) -> Option<Self::Item> {
+		if self.remaining == 0 {
+			return None;
+		}
+
 		while !self.best_transactions.is_empty() {
 			let best = {
 				let best = self.best_transactions.iter().next().expect("current_best is not empty; qed").clone();
@@ -621,6 +854,15 @@ where
 			}
 
 			if tx_state == Readiness::Ready {
+				if self.min_score.as_ref().map_or(false, |min_score| best.score < *min_score) {
+					self.best_transactions.clear();
+					return None;
+				}
+				if self.skip > 0 {
+					self.skip -= 1;
+					continue;
+				}
+				self.remaining -= 1;
 				return Some(best.transaction.transaction);
 			}
 
@@ -16,6 +16,9 @@
 
 //! When queue limits are reached, decide whether to replace an existing transaction from the pool
 
+use std::fmt;
+use std::ops::{Add, Div, Mul, Sub};
+
 use crate::{pool::Transaction, scoring::Choice};
 
 /// Encapsulates a transaction to be compared, along with pooled transactions from the same sender
@@ -47,3 +50,64 @@ pub trait ShouldReplace<T> {
 	/// NOTE returning `InsertNew` here can lead to some transactions being accepted above pool limits.
 	fn should_replace(&self, old: &ReplaceTransaction<'_, T>, new: &ReplaceTransaction<'_, T>) -> Choice;
 }
+
+/// Decides if a new transaction should replace a pooled transaction from the same sender
+/// occupying the same slot (i.e. `Scoring::compare` returns `Ordering::Equal`).
+///
+/// When set on the pool, it is consulted instead of `Scoring::choose` for such collisions.
+pub trait ReplacementPolicy<T>: fmt::Debug {
+	/// Decides what to do with `new` transaction colliding with `old` one.
+	fn choose(&self, old: &T, new: &T) -> Choice;
+}
+
+/// Replaces a transaction only if the new one pays at least given percentage more.
+///
+/// The price of a transaction is extracted with the supplied function.
+#[derive(Clone)]
+pub struct PercentageBump<F> {
+	percent: u32,
+	price: F,
+}
+
+impl<F> PercentageBump<F> {
+	/// Creates a policy requiring a price bump of `percent` percent.
+	pub fn new(percent: u32, price: F) -> Self {
+		PercentageBump { percent, price }
+	}
+}
+
+impl<F> fmt::Debug for PercentageBump<F> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("PercentageBump").field("percent", &self.percent).finish()
+	}
+}
+
+impl<T, P, F> ReplacementPolicy<T> for PercentageBump<F>
+where
+	F: Fn(&T) -> P,
+	P: Ord + Clone + From<u32> + Add<Output = P> + Sub<Output = P> + Mul<Output = P> + Div<Output = P>,
+{
+	fn choose(&self, old: &T, new: &T) -> Choice {
+		// Replaces if `new * 100 >= old * factor`, computed so that no intermediate value overflows
+		// `P` even for the highest prices: with `old = 100 * q + r` and `factor = 100 * fq + fr` it
+		// holds iff `new >= factor * q + fq * r + ceil(fr * r / 100)`, where `fq * r` fits in a `u32`.
+		// A threshold above the range of `P` means the replacement is not allowed.
+		let factor = 100u32.saturating_add(self.percent);
+		let (fq, fr) = (factor / 100, factor % 100);
+		let (old, new) = ((self.price)(old), (self.price)(new));
+		let hundred = P::from(100);
+		let q = old.clone() / hundred.clone();
+		let r = old - q.clone() * hundred.clone();
+
+		if q > new.clone() / P::from(factor) {
+			return Choice::RejectNew;
+		}
+		let left = new - P::from(factor) * q;
+		let bump = P::from(fq) * r.clone() + (P::from(fr) * r + P::from(99)) / hundred;
+		if left >= bump {
+			Choice::ReplaceOld
+		} else {
+			Choice::RejectNew
+		}
+	}
+}
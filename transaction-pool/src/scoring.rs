@@ -152,7 +152,14 @@ mod tests {
 	use super::*;
 
 	fn score(score: u64, insertion_id: u64) -> ScoreWithRef<(), u64> {
-		ScoreWithRef { score, transaction: Transaction { insertion_id, transaction: Default::default() } }
+		ScoreWithRef {
+			score,
+			transaction: Transaction {
+				insertion_id,
+				imported_at: std::time::Instant::now(),
+				transaction: Default::default(),
+			},
+		}
 	}
 
 	#[test]
@@ -14,6 +14,9 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::HashMap;
+use std::time::Instant;
+
 /// Light pool status.
 /// This status is cheap to compute and can be called frequently.
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
@@ -38,3 +41,24 @@ pub struct Status {
 	/// Number of future (not ready) transactions.
 	pub future: usize,
 }
+
+/// A detailed pool status, meant for monitoring.
+/// NOTE: To compute the status we need to visit each transaction in the pool.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetailedStatus<Sender: Eq + ::std::hash::Hash> {
+	/// Memory usage in bytes, as reported by `VerifiedTransaction::mem_usage`.
+	pub mem_usage: usize,
+	/// Total number of transactions in the pool.
+	pub transaction_count: usize,
+	/// Number of transactions of each sender in the pool.
+	pub per_sender: HashMap<Sender, usize>,
+	/// Number of transactions with a score in each bucket.
+	///
+	/// Bucket `i` counts the scores below the `i`-th bound and not below the previous one,
+	/// the last bucket counts the scores not below the last bound.
+	pub score_histogram: Vec<usize>,
+	/// Import time of the oldest transaction in the pool.
+	pub oldest: Option<Instant>,
+	/// Import time of the newest transaction in the pool.
+	pub newest: Option<Instant>,
+}
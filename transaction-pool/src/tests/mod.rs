@@ -50,6 +50,17 @@ impl VerifiedTransaction for Transaction {
 	}
 }
 
+impl NoncedTransaction for Transaction {
+	type Nonce = U256;
+
+	fn nonce(&self) -> U256 {
+		self.nonce
+	}
+	fn next_nonce(&self) -> U256 {
+		self.nonce + 1
+	}
+}
+
 pub type SharedTransaction = Arc<Transaction>;
 
 type TestPool = Pool<Transaction, DummyScoring>;
@@ -120,6 +131,46 @@ fn should_replace_transaction() {
 	assert_eq!(txq.light_status().transaction_count, 1);
 }
 
+#[test]
+fn should_replace_according_to_replacement_policy() {
+	// given
+	let b = TransactionBuilder::default();
+	let mut txq = TestPool::default();
+	txq.set_replacement_policy(PercentageBump::new(10, |tx: &Transaction| tx.gas_price));
+	let tx1 = b.tx().nonce(0).gas_price(100).new();
+	let tx2 = b.tx().nonce(0).gas_price(109).new();
+	let tx3 = b.tx().nonce(0).gas_price(110).new();
+	let (hash1, hash2) = (tx1.hash, tx2.hash);
+
+	// when
+	import(&mut txq, tx1).unwrap();
+	let err = import(&mut txq, tx2).unwrap_err();
+	let tx3 = import(&mut txq, tx3).unwrap();
+
+	// then
+	assert_eq!(err, error::Error::TooCheapToReplace(hash1, hash2));
+	assert_eq!(txq.light_status().transaction_count, 1);
+	assert_eq!(txq.find(&tx3.hash), Some(tx3));
+}
+
+#[test]
+fn should_not_overflow_in_percentage_bump() {
+	let choose = |percent, old: u64, new: u64| PercentageBump::new(percent, |price: &u64| *price).choose(&old, &new);
+	assert_eq!(choose(10, 5, 6), scoring::Choice::ReplaceOld);
+	assert_eq!(choose(10, 5, 5), scoring::Choice::RejectNew);
+	assert_eq!(choose(10, 1000, 1100), scoring::Choice::ReplaceOld);
+	assert_eq!(choose(10, 1000, 1099), scoring::Choice::RejectNew);
+	assert_eq!(choose(0, u64::MAX, u64::MAX), scoring::Choice::ReplaceOld);
+	assert_eq!(choose(1, u64::MAX / 100 * 99, u64::MAX), scoring::Choice::ReplaceOld);
+	assert_eq!(choose(1, u64::MAX - 1, u64::MAX), scoring::Choice::RejectNew);
+	assert_eq!(choose(u32::MAX, 1, u64::MAX), scoring::Choice::ReplaceOld);
+	assert_eq!(choose(u32::MAX, u64::MAX / 2, u64::MAX), scoring::Choice::RejectNew);
+
+	let policy = PercentageBump::new(u32::MAX, |price: &U256| *price);
+	assert_eq!(policy.choose(&U256::max_value(), &U256::max_value()), scoring::Choice::RejectNew);
+	assert_eq!(policy.choose(&U256::zero(), &U256::zero()), scoring::Choice::ReplaceOld);
+}
+
 #[test]
 fn should_reject_if_above_count() {
 	let b = TransactionBuilder::default();
@@ -191,6 +242,45 @@ fn should_reject_if_above_sender_count() {
 	assert_eq!(txq.light_status().transaction_count, 1);
 }
 
+#[test]
+fn should_evict_according_to_policy() {
+	let b = TransactionBuilder::default();
+	let import_all = |eviction| {
+		let mut txq = TestPool::with_options(Options { max_count: 3, eviction, ..Default::default() });
+		import(&mut txq, b.tx().sender(0).gas_price(5).mem_usage(1).new()).unwrap();
+		import(&mut txq, b.tx().sender(1).gas_price(3).mem_usage(1).new()).unwrap();
+		import(&mut txq, b.tx().sender(2).gas_price(4).mem_usage(3).new()).unwrap();
+		import(&mut txq, b.tx().sender(3).gas_price(10).new()).unwrap();
+		let mut remaining =
+			txq.unordered_pending(NonceReady::default()).map(|tx| tx.gas_price.low_u64()).collect::<Vec<_>>();
+		remaining.sort();
+		remaining
+	};
+
+	assert_eq!(import_all(EvictionPolicy::LowestScore), vec![4, 5, 10]);
+	assert_eq!(import_all(EvictionPolicy::Oldest), vec![3, 4, 10]);
+	assert_eq!(import_all(EvictionPolicy::Largest), vec![3, 5, 10]);
+}
+
+#[test]
+fn should_only_evict_last_transaction_of_a_sender() {
+	let b = TransactionBuilder::default();
+	let mut txq =
+		TestPool::with_options(Options { max_count: 3, eviction: EvictionPolicy::Oldest, ..Default::default() });
+	import(&mut txq, b.tx().nonce(0).gas_price(5).new()).unwrap();
+	import(&mut txq, b.tx().nonce(1).gas_price(5).new()).unwrap();
+	import(&mut txq, b.tx().sender(1).gas_price(1).new()).unwrap();
+
+	// the oldest transaction has a successor, so the next one from that sender goes first
+	import(&mut txq, b.tx().sender(2).gas_price(10).new()).unwrap();
+
+	let pending = txq
+		.pending(NonceReady::default())
+		.map(|tx| (tx.sender.to_low_u64_be(), tx.nonce.low_u64()))
+		.collect::<Vec<_>>();
+	assert_eq!(pending, vec![(2, 0), (0, 0), (1, 0)]);
+}
+
 #[test]
 fn should_construct_pending() {
 	// given
@@ -243,6 +333,36 @@ fn should_construct_pending() {
 	assert_eq!(pending.next(), None);
 }
 
+#[test]
+fn should_construct_filtered_pending() {
+	use std::collections::HashSet;
+
+	// given
+	let b = TransactionBuilder::default();
+	let mut txq = TestPool::default();
+
+	let tx0 = import(&mut txq, b.tx().nonce(0).gas_price(5).new()).unwrap();
+	let tx1 = import(&mut txq, b.tx().nonce(1).gas_price(3).new()).unwrap();
+	let tx2 = import(&mut txq, b.tx().sender(1).nonce(0).gas_price(4).new()).unwrap();
+	let tx3 = import(&mut txq, b.tx().sender(1).nonce(1).gas_price(6).new()).unwrap();
+	let tx4 = import(&mut txq, b.tx().sender(2).nonce(0).gas_price(2).new()).unwrap();
+	let pending = |filter| txq.pending_filtered(NonceReady::default(), filter).collect::<Vec<_>>();
+
+	// then
+	assert_eq!(pending(PendingFilter::default()), vec![tx0.clone(), tx2.clone(), tx3.clone(), tx1.clone(), tx4]);
+
+	let senders = [Address::zero(), Address::from_low_u64_be(1)].iter().cloned().collect::<HashSet<_>>();
+	assert_eq!(
+		pending(PendingFilter { senders: Some(senders), ..Default::default() }),
+		vec![tx0.clone(), tx2.clone(), tx3.clone(), tx1]
+	);
+	assert_eq!(
+		pending(PendingFilter { min_score: Some(4.into()), ..Default::default() }),
+		vec![tx0, tx2.clone(), tx3.clone()]
+	);
+	assert_eq!(pending(PendingFilter { offset: 1, limit: Some(2), ..Default::default() }), vec![tx2, tx3]);
+}
+
 #[test]
 fn should_skip_staled_pending_transactions() {
 	let b = TransactionBuilder::default();
@@ -437,6 +557,46 @@ fn should_cull_stalled_transactions_from_a_sender() {
 	assert_eq!(txq.light_status(), LightStatus { transaction_count: 3, senders: 1, mem_usage: 0 });
 }
 
+#[test]
+fn should_cull_transactions_imported_before_given_time() {
+	use std::time::Instant;
+
+	// given
+	let b = TransactionBuilder::default();
+	let mut txq = TestPool::default();
+
+	import(&mut txq, b.tx().nonce(0).new()).unwrap();
+	import(&mut txq, b.tx().sender(1).nonce(0).new()).unwrap();
+	let time = Instant::now();
+	let tx = import(&mut txq, b.tx().nonce(1).new()).unwrap();
+
+	// when
+	assert_eq!(txq.cull_imported_before(time), 2);
+
+	// then
+	assert_eq!(txq.light_status(), LightStatus { transaction_count: 1, senders: 1, mem_usage: 0 });
+	assert_eq!(txq.find(&tx.hash), Some(tx));
+	assert_eq!(txq.cull_imported_before(time), 0);
+}
+
+#[test]
+fn should_cull_old_transactions_on_import() {
+	use std::{thread, time::Duration};
+
+	// given
+	let b = TransactionBuilder::default();
+	let mut txq = TestPool::with_options(Options { max_age: Some(Duration::from_millis(50)), ..Default::default() });
+	import(&mut txq, b.tx().nonce(0).new()).unwrap();
+	assert_eq!(txq.cull_older_than(Duration::from_secs(60)), 0);
+
+	// when
+	thread::sleep(Duration::from_millis(100));
+	import(&mut txq, b.tx().sender(1).nonce(0).new()).unwrap();
+
+	// then
+	assert_eq!(txq.light_status(), LightStatus { transaction_count: 1, senders: 1, mem_usage: 0 });
+}
+
 #[test]
 fn should_re_insert_after_cull() {
 	// given
@@ -473,6 +633,39 @@ fn should_return_worst_transaction() {
 	assert_eq!(txq.worst_transaction().unwrap().gas_price, 4.into());
 }
 
+#[test]
+fn should_return_detailed_status() {
+	// given
+	let b = TransactionBuilder::default();
+	let mut txq = TestPool::default();
+	let buckets = [U256::from(5), U256::from(10)];
+	let status = txq.status_detailed(&buckets);
+	assert_eq!(status.score_histogram, vec![0, 0, 0]);
+	assert!(status.per_sender.is_empty());
+	assert_eq!(status.oldest, None);
+
+	// when
+	let tx1 = import(&mut txq, b.tx().nonce(0).gas_price(4).mem_usage(2).new()).unwrap();
+	let tx2 = import(&mut txq, b.tx().nonce(1).gas_price(5).new()).unwrap();
+	let tx3 = import(&mut txq, b.tx().sender(1).nonce(0).gas_price(20).mem_usage(3).new()).unwrap();
+
+	// then
+	let status = txq.status_detailed(&buckets);
+	assert_eq!(status.mem_usage, 5);
+	assert_eq!(status.transaction_count, 3);
+	assert_eq!(status.per_sender.len(), 2);
+	assert_eq!(status.per_sender[&tx1.sender], 2);
+	assert_eq!(status.per_sender[&tx3.sender], 1);
+	assert_eq!(status.score_histogram, vec![1, 1, 1]);
+	assert!(status.oldest.unwrap() <= status.newest.unwrap());
+
+	let mut scored = txq.scored_transactions().map(|(hash, _, score)| (*hash, *score)).collect::<Vec<_>>();
+	scored.sort();
+	let mut expected = vec![(tx1.hash, U256::from(4)), (tx2.hash, U256::from(5)), (tx3.hash, U256::from(20))];
+	expected.sort();
+	assert_eq!(scored, expected);
+}
+
 #[test]
 fn should_return_is_full() {
 	// given
@@ -581,6 +774,17 @@ mod listener {
 		fn culled(&mut self, _tx: &SharedTransaction) {
 			self.0.borrow_mut().push("culled".into());
 		}
+
+		fn banned(&mut self, ban: &Ban<H256, Address>) {
+			self.0.borrow_mut().push(match ban {
+				Ban::Hash(_) => "banned hash",
+				Ban::Sender(_) => "banned sender",
+			});
+		}
+
+		fn unbanned(&mut self, _ban: &Ban<H256, Address>) {
+			self.0.borrow_mut().push("unbanned");
+		}
 	}
 
 	#[test]
@@ -675,4 +879,384 @@ mod listener {
 		// then
 		assert_eq!(*results.borrow(), &["added", "added", "culled", "culled"]);
 	}
+
+	#[test]
+	fn ban_invalid_transaction() {
+		let b = TransactionBuilder::default();
+		let listener = MyListener::default();
+		let results = listener.0.clone();
+		let mut txq = Pool::new(listener, DummyScoring::default(), Options::default());
+		txq.set_ban_list(BanOptions { ban_senders: true, ..Default::default() });
+
+		let tx = import(&mut txq, b.tx().nonce(1).new()).unwrap();
+		txq.remove(tx.hash(), true);
+		assert_eq!(*results.borrow(), &["added", "invalid", "banned hash", "banned sender"]);
+
+		// the transaction and any other from the sender are rejected
+		let tx = b.tx().nonce(1).new();
+		let hash = tx.hash;
+		assert_eq!(import(&mut txq, tx).unwrap_err(), error::Error::Banned(hash));
+		let tx = b.tx().nonce(2).new();
+		let hash = tx.hash;
+		assert_eq!(import(&mut txq, tx).unwrap_err(), error::Error::Banned(hash));
+		import(&mut txq, b.tx().sender(1).nonce(1).new()).unwrap();
+
+		assert!(txq.unban(&Ban::Sender(Address::zero())));
+		assert!(!txq.unban(&Ban::Sender(Address::zero())));
+		import(&mut txq, b.tx().nonce(2).new()).unwrap();
+		assert_eq!(txq.ban_list().unwrap().len(), 1);
+		assert_eq!(
+			*results.borrow(),
+			&["added", "invalid", "banned hash", "banned sender", "added", "unbanned", "added"]
+		);
+	}
+}
+
+#[test]
+fn should_lift_expired_and_oldest_bans() {
+	use std::time::{Duration, Instant};
+
+	let secs = Duration::from_secs;
+	let mut bans = BanList::<u64, u64>::new(BanOptions { cooldown: secs(10), max_bans: 2, ..Default::default() });
+	let now = Instant::now();
+	assert_eq!(bans.ban(Ban::Hash(1), now), None);
+	assert_eq!(bans.ban(Ban::Sender(1), now + secs(1)), None);
+	assert!(bans.is_transaction_banned(&2, &1, now + secs(1)));
+	assert!(!bans.is_transaction_banned(&2, &2, now + secs(1)));
+
+	// renewing a ban doesn't lift any other
+	assert_eq!(bans.ban(Ban::Hash(1), now + secs(2)), None);
+	assert_eq!(bans.ban(Ban::Hash(2), now + secs(3)), Some(Ban::Sender(1)));
+	assert_eq!(bans.expire(now + secs(11)), vec![]);
+	assert!(bans.is_banned(&Ban::Hash(1), now + secs(11)));
+	assert_eq!(bans.expire(now + secs(13)), vec![Ban::Hash(1), Ban::Hash(2)]);
+	assert!(bans.is_empty());
+}
+
+mod events {
+	use super::*;
+
+	fn describe(event: Event<Transaction>) -> (&'static str, u64) {
+		match event {
+			Event::Added(tx) => ("added", tx.gas_price.low_u64()),
+			Event::Replaced { new, .. } => ("replaced", new.gas_price.low_u64()),
+			Event::Rejected { transaction, .. } => ("rejected", transaction.gas_price.low_u64()),
+			Event::Dropped(tx) => ("dropped", tx.gas_price.low_u64()),
+			Event::Invalid(tx) => ("invalid", tx.gas_price.low_u64()),
+			Event::Canceled(tx) => ("canceled", tx.gas_price.low_u64()),
+			Event::Culled(tx) => ("culled", tx.gas_price.low_u64()),
+			Event::Banned(Ban::Hash(_)) => ("banned hash", 0),
+			Event::Banned(Ban::Sender(sender)) => ("banned sender", sender.to_low_u64_be()),
+			Event::Unbanned(Ban::Hash(_)) => ("unbanned hash", 0),
+			Event::Unbanned(Ban::Sender(sender)) => ("unbanned sender", sender.to_low_u64_be()),
+		}
+	}
+
+	#[test]
+	fn should_stream_events_to_subscribers() {
+		let b = TransactionBuilder::default();
+		let mut txq =
+			Pool::new(EventListener::new(), DummyScoring::default(), Options { max_count: 2, ..Default::default() });
+		let stream = txq.listener_mut().subscribe();
+
+		let tx = import(&mut txq, b.tx().nonce(0).gas_price(1).new()).unwrap();
+		import(&mut txq, b.tx().nonce(0).gas_price(2).new()).unwrap();
+		import(&mut txq, b.tx().nonce(0).gas_price(1).new()).unwrap_err();
+		import(&mut txq, b.tx().sender(1).gas_price(3).new()).unwrap();
+		import(&mut txq, b.tx().sender(2).gas_price(4).new()).unwrap();
+		txq.remove(tx.hash(), true);
+		txq.cull(Some(&[Address::from_low_u64_be(1), Address::from_low_u64_be(2)]), NonceReady::new(1));
+
+		let late = txq.listener_mut().subscribe();
+		let tx = import(&mut txq, b.tx().sender(3).gas_price(5).new()).unwrap();
+		txq.remove(tx.hash(), false);
+
+		assert_eq!(
+			stream.try_iter().map(describe).collect::<Vec<_>>(),
+			vec![
+				("added", 1),
+				("replaced", 2),
+				("rejected", 1),
+				("added", 3),
+				("dropped", 2),
+				("added", 4),
+				("culled", 3),
+				("culled", 4),
+				("added", 5),
+				("canceled", 5),
+			]
+		);
+		assert_eq!(late.try_iter().map(describe).collect::<Vec<_>>(), vec![("added", 5), ("canceled", 5)]);
+	}
+
+	#[test]
+	fn should_remove_dropped_subscribers() {
+		let b = TransactionBuilder::default();
+		let mut listener = EventListener::new();
+		let stream = listener.subscribe();
+		drop(listener.subscribe());
+		assert_eq!(listener.subscribers(), 2);
+
+		let mut txq = Pool::new((listener, NoopListener), DummyScoring::default(), Options::default());
+		import(&mut txq, b.tx().new()).unwrap();
+		assert_eq!(txq.listener().0.subscribers(), 1);
+
+		drop(txq);
+		assert_eq!(stream.map(describe).collect::<Vec<_>>(), vec![("added", 0)]);
+	}
+
+	#[test]
+	fn should_stream_ban_events() {
+		let b = TransactionBuilder::default();
+		let mut txq = Pool::new(EventListener::new(), DummyScoring::default(), Options::default());
+		txq.set_ban_list(BanOptions { ban_senders: true, ..Default::default() });
+		let stream = txq.listener_mut().subscribe();
+
+		let tx = import(&mut txq, b.tx().sender(1).new()).unwrap();
+		txq.remove(tx.hash(), true);
+		txq.unban(&Ban::Sender(Address::from_low_u64_be(1)));
+
+		assert_eq!(
+			stream.try_iter().map(describe).collect::<Vec<_>>(),
+			vec![("added", 0), ("invalid", 0), ("banned hash", 0), ("banned sender", 1), ("unbanned sender", 1)]
+		);
+	}
+
+	#[test]
+	fn should_disconnect_lagging_subscribers() {
+		let b = TransactionBuilder::default();
+		let mut txq = Pool::new(EventListener::with_capacity(2), DummyScoring::default(), Options::default());
+		let lagging = txq.listener_mut().subscribe();
+		let mut reading = txq.listener_mut().subscribe();
+
+		for i in 0..3 {
+			import(&mut txq, b.tx().sender(i).gas_price(i as usize).new()).unwrap();
+			assert_eq!(reading.next().map(describe), Some(("added", i)));
+		}
+		assert_eq!(txq.listener().subscribers(), 1);
+
+		// the buffered events are still delivered before the stream ends
+		assert_eq!(lagging.map(describe).collect::<Vec<_>>(), vec![("added", 0), ("added", 1)]);
+	}
+}
+
+mod future {
+	use super::*;
+
+	fn nonces(txs: &[SharedTransaction]) -> Vec<u64> {
+		txs.iter().map(|tx| tx.nonce.low_u64()).collect()
+	}
+
+	fn rejected(stream: &EventStream<Transaction>) -> Vec<u64> {
+		stream
+			.try_iter()
+			.filter_map(|event| match event {
+				Event::Rejected { transaction, .. } => Some(transaction.gas_price.low_u64()),
+				_ => None,
+			})
+			.collect()
+	}
+
+	#[test]
+	fn should_park_and_promote_when_gap_fills() {
+		let b = TransactionBuilder::default();
+		let mut txq = TestPool::default();
+		let mut future = FutureQueue::default();
+		let replace = DummyScoring::default();
+
+		assert!(matches!(future.import(&mut txq, b.tx().nonce(2).new(), 0.into(), &replace), Ok(Imported::Future)));
+		assert!(matches!(future.import(&mut txq, b.tx().nonce(3).new(), 0.into(), &replace), Ok(Imported::Future)));
+		assert!(matches!(future.import(&mut txq, b.tx().nonce(5).new(), 0.into(), &replace), Ok(Imported::Future)));
+		assert_eq!(future.len(), 3);
+		assert_eq!(txq.light_status().transaction_count, 0);
+
+		match future.import(&mut txq, b.tx().nonce(0).new(), 0.into(), &replace).unwrap() {
+			Imported::Ready { promoted, .. } => assert!(promoted.is_empty()),
+			Imported::Future => panic!("nonce 0 is ready"),
+		}
+		match future.import(&mut txq, b.tx().nonce(1).new(), 0.into(), &replace).unwrap() {
+			Imported::Ready { promoted, .. } => assert_eq!(nonces(&promoted), vec![2, 3]),
+			Imported::Future => panic!("nonce 1 is ready"),
+		}
+		assert_eq!(future.len(), 1);
+		assert_eq!(txq.light_status().transaction_count, 4);
+		assert_eq!(txq.pending(NonceReady::default()).count(), 4);
+
+		// the state nonce moved past the parked transaction's gap
+		txq.clear();
+		let promoted = future.promote(&mut txq, &Address::zero(), 5.into(), &replace);
+		assert_eq!(nonces(&promoted), vec![5]);
+		assert!(future.is_empty());
+	}
+
+	#[test]
+	fn should_reject_stale_and_duplicate_transactions() {
+		let b = TransactionBuilder::default();
+		let mut txq = TestPool::default();
+		let mut future = FutureQueue::default();
+		let replace = DummyScoring::default();
+
+		let tx = b.tx().nonce(1).new();
+		let hash = tx.hash;
+		assert_eq!(future.import(&mut txq, tx, 2.into(), &replace).unwrap_err(), error::Error::StaleNonce(hash));
+		future.import(&mut txq, b.tx().nonce(3).new(), 0.into(), &replace).unwrap();
+		let tx = b.tx().nonce(3).new();
+		let hash = tx.hash;
+		assert_eq!(future.import(&mut txq, tx, 0.into(), &replace).unwrap_err(), error::Error::AlreadyImported(hash));
+
+		// a parked transaction is replaced according to scoring
+		let tx = b.tx().nonce(3).gas_price(1).new();
+		let hash = tx.hash;
+		future.import(&mut txq, tx, 0.into(), &replace).unwrap();
+		assert!(future.contains(&hash));
+		assert_eq!(future.len(), 1);
+		assert_eq!(future.remove(&hash).map(|tx| tx.gas_price), Some(1.into()));
+		assert!(future.is_empty());
+	}
+
+	#[test]
+	fn should_respect_future_limits() {
+		let b = TransactionBuilder::default();
+		let mut txq = TestPool::default();
+		let mut future = FutureQueue::new(FutureOptions { max_count: 3, max_per_sender: 2 });
+		let replace = DummyScoring::default();
+
+		future.import(&mut txq, b.tx().nonce(2).new(), 0.into(), &replace).unwrap();
+		future.import(&mut txq, b.tx().nonce(4).new(), 0.into(), &replace).unwrap();
+		let tx = b.tx().nonce(5).new();
+		let hash = tx.hash;
+		assert_eq!(future.import(&mut txq, tx, 0.into(), &replace).unwrap_err(), error::Error::FutureQueueFull(hash));
+		// a lower nonce pushes out the highest one of the sender
+		future.import(&mut txq, b.tx().nonce(3).new(), 0.into(), &replace).unwrap();
+		assert_eq!(future.from_sender(&Address::zero()).map(|tx| tx.nonce.low_u64()).collect::<Vec<_>>(), vec![2, 3]);
+
+		future.import(&mut txq, b.tx().sender(1).nonce(1).new(), 0.into(), &replace).unwrap();
+		let tx = b.tx().sender(2).nonce(1).new();
+		let hash = tx.hash;
+		assert_eq!(future.import(&mut txq, tx, 0.into(), &replace).unwrap_err(), error::Error::FutureQueueFull(hash));
+		assert_eq!(future.len(), 3);
+	}
+
+	#[test]
+	fn should_replace_parked_according_to_replacement_policy() {
+		let b = TransactionBuilder::default();
+		let mut txq = Pool::new(EventListener::new(), DummyScoring::default(), Options::default());
+		txq.set_replacement_policy(PercentageBump::new(10, |tx: &Transaction| tx.gas_price));
+		let stream = txq.listener_mut().subscribe();
+		let mut future = FutureQueue::new(FutureOptions { max_count: 1, max_per_sender: 1 });
+		let replace = DummyScoring::default();
+
+		future.import(&mut txq, b.tx().nonce(2).gas_price(100).new(), 0.into(), &replace).unwrap();
+		let tx = b.tx().nonce(2).gas_price(109).new();
+		let (old, new) = (future.from_sender(&Address::zero()).next().unwrap().hash, tx.hash);
+		assert_eq!(
+			future.import(&mut txq, tx, 0.into(), &replace).unwrap_err(),
+			error::Error::TooCheapToReplace(old, new)
+		);
+		let tx = b.tx().nonce(2).gas_price(110).new();
+		let hash = tx.hash;
+		future.import(&mut txq, tx, 0.into(), &replace).unwrap();
+		assert!(future.contains(&hash));
+		assert!(!future.contains(&old));
+
+		// a full queue rejects the transaction too
+		assert!(future.import(&mut txq, b.tx().sender(1).nonce(1).gas_price(5).new(), 0.into(), &replace).is_err());
+		assert_eq!(rejected(&stream), vec![109, 5]);
+		assert_eq!(future.len(), 1);
+	}
+
+	#[test]
+	fn should_report_transactions_failing_promotion() {
+		let b = TransactionBuilder::default();
+		let mut txq = Pool::new(EventListener::new(), DummyScoring::default(), Options::default());
+		txq.set_ban_list(BanOptions::default());
+		let stream = txq.listener_mut().subscribe();
+		let mut future = FutureQueue::default();
+		let replace = DummyScoring::default();
+
+		let banned = b.tx().nonce(1).gas_price(1).new();
+		txq.ban(Ban::Hash(banned.hash));
+		future.import(&mut txq, banned, 0.into(), &replace).unwrap();
+		future.import(&mut txq, b.tx().nonce(2).gas_price(2).new(), 0.into(), &replace).unwrap();
+
+		match future.import(&mut txq, b.tx().nonce(0).new(), 0.into(), &replace).unwrap() {
+			Imported::Ready { promoted, .. } => assert!(promoted.is_empty()),
+			Imported::Future => panic!("nonce 0 is ready"),
+		}
+		assert_eq!(rejected(&stream), vec![1]);
+		// the transactions after the rejected one stay parked
+		assert_eq!(future.from_sender(&Address::zero()).map(|tx| tx.nonce.low_u64()).collect::<Vec<_>>(), vec![2]);
+	}
+}
+
+#[cfg(feature = "persistence")]
+mod journal {
+	use super::*;
+	use kvdb::KeyValueDB;
+	use std::sync::Arc;
+
+	struct NonZeroGasPrice;
+
+	impl Verifier<Transaction> for NonZeroGasPrice {
+		type Error = ();
+		type VerifiedTransaction = Transaction;
+
+		fn verify_transaction(&self, tx: Transaction) -> Result<Transaction, ()> {
+			if tx.gas_price.is_zero() {
+				Err(())
+			} else {
+				Ok(tx)
+			}
+		}
+	}
+
+	fn encode(tx: &Transaction) -> Vec<u8> {
+		[tx.nonce.low_u64(), tx.gas_price.low_u64(), tx.sender.to_low_u64_be()]
+			.iter()
+			.flat_map(|x| x.to_be_bytes().to_vec())
+			.collect()
+	}
+
+	fn decode(bytes: &[u8]) -> Option<Transaction> {
+		if bytes.len() != 24 {
+			return None;
+		}
+		let field = |i: usize| {
+			let mut buf = [0u8; 8];
+			buf.copy_from_slice(&bytes[i * 8..(i + 1) * 8]);
+			u64::from_be_bytes(buf)
+		};
+		Some(TransactionBuilder::default().nonce(field(0) as usize).gas_price(field(1) as usize).sender(field(2)).new())
+	}
+
+	#[test]
+	fn should_journal_and_reload_pool() {
+		let b = TransactionBuilder::default();
+		let db = Arc::new(kvdb_memorydb::create(1));
+		let journal = Journal::new(db.clone(), 0);
+		let mut txq =
+			Pool::new(JournalListener::new(journal.clone(), encode), DummyScoring::default(), Options::default());
+
+		// given
+		import(&mut txq, b.tx().nonce(0).gas_price(1).new()).unwrap();
+		let tx1 = import(&mut txq, b.tx().nonce(0).gas_price(2).new()).unwrap();
+		let tx2 = import(&mut txq, b.tx().nonce(1).gas_price(3).new()).unwrap();
+		let tx3 = import(&mut txq, b.tx().sender(1).gas_price(4).new()).unwrap();
+		txq.remove(tx3.hash(), false);
+		let mut invalid = db.transaction();
+		invalid.put(0, b"garbage", b"");
+		invalid.put(0, b"zero", &encode(&b.tx().sender(2).new()));
+		db.write(invalid).unwrap();
+
+		// when
+		let mut loaded = journal.load(decode, &NonZeroGasPrice).unwrap();
+		loaded.sort_by_key(|tx| tx.nonce);
+		let loaded = loaded.iter().map(|tx| tx.hash).collect::<Vec<_>>();
+
+		// then
+		assert_eq!(loaded, vec![tx1.hash, tx2.hash]);
+		assert_eq!(db.iter(0).count(), 2);
+		journal.clear().unwrap();
+		assert_eq!(db.iter(0).count(), 0);
+	}
 }
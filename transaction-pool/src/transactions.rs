@@ -22,6 +22,7 @@ use smallvec::SmallVec;
 use crate::{
 	pool::Transaction,
 	ready::{Readiness, Ready},
+	replace::ReplacementPolicy,
 	scoring::{self, Scoring},
 };
 
@@ -62,6 +63,10 @@ impl<T: fmt::Debug, S: Scoring<T>> Transactions<T, S> {
 		self.transactions.iter()
 	}
 
+	pub fn iter_scored(&self) -> impl Iterator<Item = (&Transaction<T>, &S::Score)> {
+		self.transactions.iter().zip(self.scores.iter())
+	}
+
 	pub fn worst_and_best(&self) -> Option<((S::Score, Transaction<T>), (S::Score, Transaction<T>))> {
 		let len = self.scores.len();
 		self.scores.get(0).cloned().map(|best| {
@@ -107,10 +112,16 @@ impl<T: fmt::Debug, S: Scoring<T>> Transactions<T, S> {
 		scoring.update_scores(&self.transactions, &mut self.scores, scoring::Change::Event(event));
 	}
 
-	pub fn add(&mut self, new: Transaction<T>, scoring: &S, max_count: usize) -> AddResult<Transaction<T>, S::Score> {
-		let index = match self.transactions.binary_search_by(|old| scoring.compare(old, &new)) {
-			Ok(index) => index,
-			Err(index) => index,
+	pub fn add(
+		&mut self,
+		new: Transaction<T>,
+		scoring: &S,
+		replacement: Option<&dyn ReplacementPolicy<T>>,
+		max_count: usize,
+	) -> AddResult<Transaction<T>, S::Score> {
+		let (index, collides) = match self.transactions.binary_search_by(|old| scoring.compare(old, &new)) {
+			Ok(index) => (index, true),
+			Err(index) => (index, false),
 		};
 
 		// Insert at the end.
@@ -119,7 +130,11 @@ impl<T: fmt::Debug, S: Scoring<T>> Transactions<T, S> {
 		}
 
 		// Decide if the transaction should replace some other.
-		match scoring.choose(&self.transactions[index], &new) {
+		let choice = match replacement {
+			Some(replacement) if collides => replacement.choose(&self.transactions[index], &new),
+			_ => scoring.choose(&self.transactions[index], &new),
+		};
+		match choice {
 			// New transaction should be rejected
 			scoring::Choice::RejectNew => AddResult::TooCheap { old: self.transactions[index].clone(), new },
 			// New transaction should be kept along with old ones.
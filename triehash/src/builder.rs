@@ -0,0 +1,350 @@
+// Copyright 2015-2019 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Streaming trie root computation.
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::cmp;
+use core::marker::PhantomData;
+
+use hash_db::Hasher;
+use rlp::RlpStream;
+
+use crate::{hex_prefix_encode, shared_prefix_len, to_nibbles};
+
+/// Branch node on the path of the last inserted key, still accepting children.
+struct OpenBranch {
+	/// Number of key nibbles leading to this branch.
+	depth: usize,
+	/// Rlp of child references, inline nodes or hashes.
+	children: [Option<Vec<u8>>; 16],
+	value: Option<Vec<u8>>,
+}
+
+impl OpenBranch {
+	fn new(depth: usize) -> Self {
+		OpenBranch { depth, children: Default::default(), value: None }
+	}
+
+	fn encode(&self) -> Vec<u8> {
+		let mut stream = RlpStream::new_list(17);
+		for child in &self.children {
+			match child {
+				Some(child) => stream.append_raw(child, 1),
+				None => stream.append_empty_data(),
+			};
+		}
+		match self.value {
+			Some(ref value) => stream.append(&value.as_slice()),
+			None => stream.append_empty_data(),
+		};
+		stream.out()
+	}
+}
+
+/// Computes a trie root from key-value pairs inserted one at a time in ascending key order.
+///
+/// Produces the same root as `trie_root`, but only keeps the nodes on the path of the last
+/// inserted key in memory, so the size of the input doesn't matter.
+///
+/// ```rust
+/// use keccak_hasher::KeccakHasher;
+/// use triehash::{trie_root, TrieRootBuilder};
+///
+/// let v = vec![("doe", "reindeer"), ("dog", "puppy"), ("dogglesworth", "cat")];
+/// let mut builder = TrieRootBuilder::<KeccakHasher>::new();
+/// for (key, value) in &v {
+/// 	builder.insert(key.as_bytes(), value.as_bytes());
+/// }
+/// assert_eq!(builder.finish(), trie_root::<KeccakHasher, _, _, _>(v));
+/// ```
+pub struct TrieRootBuilder<H> {
+	/// Open branches, ordered by depth.
+	stack: Vec<OpenBranch>,
+	/// Last inserted pair, as nibbles and value. Its position depends on the next key.
+	pending: Option<(Vec<u8>, Vec<u8>)>,
+	/// Length of the prefix the pending key shares with its predecessor.
+	pending_shared: usize,
+	_marker: PhantomData<H>,
+}
+
+impl<H: Hasher> Default for TrieRootBuilder<H> {
+	fn default() -> Self {
+		TrieRootBuilder::new()
+	}
+}
+
+impl<H: Hasher> TrieRootBuilder<H> {
+	/// Create a builder for an empty trie.
+	pub fn new() -> Self {
+		TrieRootBuilder { stack: Vec::new(), pending: None, pending_shared: 0, _marker: PhantomData }
+	}
+
+	/// Insert a key-value pair.
+	///
+	/// Keys must be inserted in ascending order. Inserting the last key again replaces its
+	/// value, like duplicates do in `trie_root`.
+	///
+	/// # Panics
+	///
+	/// Panics if `key` is smaller than the previously inserted key.
+	pub fn insert(&mut self, key: &[u8], value: &[u8]) {
+		let key = to_nibbles(key);
+		let (last_key, last_value) = match self.pending.take() {
+			None => {
+				self.pending = Some((key, value.to_vec()));
+				return;
+			}
+			Some(pending) => pending,
+		};
+
+		if key == last_key {
+			self.pending = Some((key, value.to_vec()));
+			return;
+		}
+		assert!(last_key < key, "keys must be inserted in ascending order");
+
+		let shared = shared_prefix_len(&last_key, &key);
+		self.place(&last_key, last_value, cmp::max(self.pending_shared, shared));
+		self.close_deeper_than(&last_key, Some(shared));
+
+		self.pending = Some((key, value.to_vec()));
+		self.pending_shared = shared;
+	}
+
+	/// Compute the trie root of all inserted pairs.
+	pub fn finish(mut self) -> H::Out {
+		let (key, value) = match self.pending.take() {
+			None => return H::hash(&rlp::NULL_RLP),
+			Some(pending) => pending,
+		};
+
+		if self.stack.is_empty() {
+			return H::hash(&leaf(&key, &value));
+		}
+
+		self.place(&key, value, self.pending_shared);
+		let root = self.close_deeper_than(&key, None).expect("stack is not empty; qed");
+		let root = match root.depth {
+			0 => root.encode(),
+			depth => extension::<H>(&key[..depth], root.encode()),
+		};
+		H::hash(&root)
+	}
+
+	/// Put the pair in the branch at `depth`, either as its value or as a leaf child.
+	fn place(&mut self, key: &[u8], value: Vec<u8>, depth: usize) {
+		if self.stack.last().map_or(true, |top| top.depth < depth) {
+			self.stack.push(OpenBranch::new(depth));
+		}
+		let branch = self.stack.last_mut().expect("branch pushed above; qed");
+		if key.len() == depth {
+			branch.value = Some(value);
+		} else {
+			branch.children[key[depth] as usize] = Some(node_ref::<H>(leaf(&key[depth + 1..], &value)));
+		}
+	}
+
+	/// Finish all branches deeper than `depth` on the path to `key`, attaching each to its parent.
+	///
+	/// With no `depth`, all branches are closed and the last one is returned.
+	fn close_deeper_than(&mut self, key: &[u8], depth: Option<usize>) -> Option<OpenBranch> {
+		while self.stack.last().map_or(false, |top| depth.map_or(true, |depth| top.depth > depth)) {
+			let branch = self.stack.pop().expect("checked by loop condition; qed");
+			let parent_depth = match (self.stack.last().map(|parent| parent.depth), depth) {
+				(Some(parent), Some(depth)) => cmp::max(parent, depth),
+				(None, Some(depth)) => depth,
+				(Some(parent), None) => parent,
+				(None, None) => return Some(branch),
+			};
+			if self.stack.last().map_or(true, |parent| parent.depth < parent_depth) {
+				self.stack.push(OpenBranch::new(parent_depth));
+			}
+
+			let node = match branch.depth - parent_depth {
+				1 => branch.encode(),
+				_ => extension::<H>(&key[parent_depth + 1..branch.depth], branch.encode()),
+			};
+			let parent = self.stack.last_mut().expect("parent pushed above; qed");
+			parent.children[key[parent_depth] as usize] = Some(node_ref::<H>(node));
+		}
+		None
+	}
+}
+
+/// Computes an ordered trie root from values pushed one at a time.
+///
+/// Produces the same root as `ordered_trie_root` with bounded memory.
+///
+/// ```rust
+/// use keccak_hasher::KeccakHasher;
+/// use triehash::{ordered_trie_root, OrderedTrieRootBuilder};
+///
+/// let v = &["doe", "reindeer"];
+/// let mut builder = OrderedTrieRootBuilder::<KeccakHasher>::new();
+/// for value in v {
+/// 	builder.push(value.as_bytes());
+/// }
+/// assert_eq!(builder.finish(), ordered_trie_root::<KeccakHasher, _>(v));
+/// ```
+pub struct OrderedTrieRootBuilder<H> {
+	inner: TrieRootBuilder<H>,
+	/// Value at index 0. Its key, `rlp(0) = 0x80`, sorts after the keys of indexes 1 to 127.
+	first: Option<Vec<u8>>,
+	len: usize,
+}
+
+impl<H: Hasher> Default for OrderedTrieRootBuilder<H> {
+	fn default() -> Self {
+		OrderedTrieRootBuilder::new()
+	}
+}
+
+impl<H: Hasher> OrderedTrieRootBuilder<H> {
+	/// Create a builder for an empty trie.
+	pub fn new() -> Self {
+		OrderedTrieRootBuilder { inner: TrieRootBuilder::new(), first: None, len: 0 }
+	}
+
+	/// Append the next value.
+	pub fn push(&mut self, value: &[u8]) {
+		match self.len {
+			0 => self.first = Some(value.to_vec()),
+			index => self.inner.insert(&rlp::encode(&index), value),
+		}
+		self.len += 1;
+		if self.len == 128 {
+			self.insert_first();
+		}
+	}
+
+	/// Compute the trie root of all pushed values.
+	pub fn finish(mut self) -> H::Out {
+		self.insert_first();
+		self.inner.finish()
+	}
+
+	fn insert_first(&mut self) {
+		if let Some(value) = self.first.take() {
+			self.inner.insert(&rlp::encode(&0usize), &value);
+		}
+	}
+}
+
+fn leaf(partial: &[u8], value: &[u8]) -> Vec<u8> {
+	let mut stream = RlpStream::new_list(2);
+	stream.append_iter(hex_prefix_encode(partial, true));
+	stream.append(&value);
+	stream.out()
+}
+
+fn extension<H: Hasher>(partial: &[u8], child: Vec<u8>) -> Vec<u8> {
+	let mut stream = RlpStream::new_list(2);
+	stream.append_iter(hex_prefix_encode(partial, false));
+	stream.append_raw(&node_ref::<H>(child), 1);
+	stream.out()
+}
+
+/// Reference to a node from its parent: the node itself if short enough, its hash otherwise.
+fn node_ref<H: Hasher>(node: Vec<u8>) -> Vec<u8> {
+	match node.len() {
+		0..=31 => node,
+		_ => rlp::encode(&H::hash(&node).as_ref()),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{OrderedTrieRootBuilder, TrieRootBuilder};
+	use crate::{ordered_trie_root, trie_root};
+	use keccak_hasher::KeccakHasher;
+	use std::collections::BTreeMap;
+	use trie_standardmap::{Alphabet, StandardMap, ValueMode};
+
+	fn build(input: &BTreeMap<Vec<u8>, Vec<u8>>) -> <KeccakHasher as hash_db::Hasher>::Out {
+		let mut builder = TrieRootBuilder::<KeccakHasher>::new();
+		for (key, value) in input {
+			builder.insert(key, value);
+		}
+		builder.finish()
+	}
+
+	fn check(input: Vec<(Vec<u8>, Vec<u8>)>) {
+		let sorted: BTreeMap<_, _> = input.iter().cloned().collect();
+		assert_eq!(build(&sorted), trie_root::<KeccakHasher, _, _, _>(input));
+	}
+
+	#[test]
+	fn empty_and_single() {
+		check(vec![]);
+		check(vec![(b"".to_vec(), b"value".to_vec())]);
+		check(vec![(b"a".to_vec(), b"value".to_vec())]);
+	}
+
+	#[test]
+	fn keys_prefixing_other_keys() {
+		check(vec![(b"a".to_vec(), b"1".to_vec()), (b"ab".to_vec(), b"2".to_vec())]);
+		check(vec![
+			(b"".to_vec(), b"root".to_vec()),
+			(b"do".to_vec(), b"verb".to_vec()),
+			(b"dog".to_vec(), b"puppy".to_vec()),
+			(b"doge".to_vec(), b"coin".to_vec()),
+			(b"horse".to_vec(), b"stallion".to_vec()),
+		]);
+	}
+
+	#[test]
+	fn matches_trie_root_on_random_input() {
+		for (alphabet, min_key, journal_key, value_mode, count) in vec![
+			(Alphabet::All, 32, 0, ValueMode::Mirror, 1000),
+			(Alphabet::Low, 5, 0, ValueMode::Random, 1000),
+			(Alphabet::Custom(b"abcd".to_vec()), 1, 3, ValueMode::Index, 500),
+		] {
+			let input = StandardMap { alphabet, min_key, journal_key, value_mode, count }.make();
+			check(input);
+		}
+	}
+
+	#[test]
+	fn last_duplicate_wins() {
+		let mut builder = TrieRootBuilder::<KeccakHasher>::new();
+		builder.insert(b"a", b"1");
+		builder.insert(b"b", b"2");
+		builder.insert(b"b", b"3");
+		assert_eq!(builder.finish(), trie_root::<KeccakHasher, _, _, _>(vec![(b"a", b"1"), (b"b", b"3")]));
+	}
+
+	#[test]
+	#[should_panic(expected = "ascending order")]
+	fn unsorted_insert_panics() {
+		let mut builder = TrieRootBuilder::<KeccakHasher>::new();
+		builder.insert(b"b", b"1");
+		builder.insert(b"a", b"2");
+	}
+
+	#[test]
+	fn ordered_matches_ordered_trie_root() {
+		for &len in &[0, 1, 2, 127, 128, 129, 300] {
+			let values: Vec<Vec<u8>> = (0..len).map(|i: u32| i.to_be_bytes().to_vec()).collect();
+			let mut builder = OrderedTrieRootBuilder::<KeccakHasher>::new();
+			for value in &values {
+				builder.push(value);
+			}
+			assert_eq!(builder.finish(), ordered_trie_root::<KeccakHasher, _>(&values), "len {}", len);
+		}
+	}
+}
@@ -17,6 +17,9 @@
 //! Generetes trie root.
 //!
 //! This module should be used to generate trie root hash.
+//!
+//! All functions are generic over the `hash_db::Hasher` used to hash nodes, the
+//! `keccak` feature adds shortcuts for the keccak-256 hashed tries used by Ethereum.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
@@ -41,6 +44,14 @@ use rstd::*;
 use hash_db::Hasher;
 use rlp::RlpStream;
 
+mod builder;
+#[cfg(feature = "parallel")]
+mod parallel;
+
+pub use builder::{OrderedTrieRootBuilder, TrieRootBuilder};
+#[cfg(feature = "parallel")]
+pub use parallel::{par_ordered_trie_root, par_sec_trie_root, par_trie_root};
+
 fn shared_prefix_len<T: Eq>(first: &[T], second: &[T]) -> usize {
 	first.iter().zip(second.iter()).position(|(f, s)| f != s).unwrap_or_else(|| cmp::min(first.len(), second.len()))
 }
@@ -101,6 +112,65 @@ where
 	B: AsRef<[u8]>,
 	H: Hasher,
 	<H as hash_db::Hasher>::Out: cmp::Ord,
+{
+	trie_root_inner::<H, _, _, _>(input, &mut None)
+}
+
+/// Generates a trie root hash for a vector of key-value tuples, together with a proof for `key`.
+///
+/// The proof is the list of rlp-encoded trie nodes on the path from the root towards `key`,
+/// starting with the root node. Nodes short enough to be inlined into their parent are not
+/// listed separately. If `key` is not in the trie, the nodes prove its absence.
+///
+/// ```rust
+/// use keccak_hasher::KeccakHasher;
+/// use triehash::{trie_root, trie_root_with_proof};
+///
+/// let v = vec![("doe", "reindeer"), ("dog", "puppy"), ("dogglesworth", "cat")];
+/// let (root, proof) = trie_root_with_proof::<KeccakHasher, _, _, _>(v.clone(), b"dog");
+/// assert_eq!(root, trie_root::<KeccakHasher, _, _, _>(v));
+/// assert_eq!(KeccakHasher::hash(&proof[0]), root);
+/// # use hash_db::Hasher;
+/// ```
+pub fn trie_root_with_proof<H, I, A, B>(input: I, key: &[u8]) -> (H::Out, Vec<Vec<u8>>)
+where
+	I: IntoIterator<Item = (A, B)>,
+	A: AsRef<[u8]> + Ord,
+	B: AsRef<[u8]>,
+	H: Hasher,
+	<H as hash_db::Hasher>::Out: cmp::Ord,
+{
+	let mut proof = Some(ProofRecorder { key: to_nibbles(key), nodes: Vec::new() });
+	let root = trie_root_inner::<H, _, _, _>(input, &mut proof);
+	let mut nodes = proof.map(|p| p.nodes).unwrap_or_default();
+	// nodes are recorded bottom-up
+	nodes.reverse();
+	(root, nodes)
+}
+
+fn trie_root_inner<H, I, A, B>(input: I, proof: &mut Option<ProofRecorder>) -> H::Out
+where
+	I: IntoIterator<Item = (A, B)>,
+	A: AsRef<[u8]> + Ord,
+	B: AsRef<[u8]>,
+	H: Hasher,
+{
+	with_nibble_keys(input, |input| {
+		let mut stream = RlpStream::new();
+		hash256rlp::<H, _, _>(input, 0, &mut stream, proof);
+		let out = stream.out();
+		if let Some(ref mut proof) = proof {
+			proof.nodes.push(out.clone());
+		}
+		H::hash(&out)
+	})
+}
+
+/// Sort and deduplicate `input`, then call `f` with the keys split into nibbles.
+fn with_nibble_keys<I, A, B, R>(input: I, f: impl FnOnce(&[(&[u8], B)]) -> R) -> R
+where
+	I: IntoIterator<Item = (A, B)>,
+	A: AsRef<[u8]> + Ord,
 {
 	// first put elements into btree to sort them and to remove duplicates
 	let input = input.into_iter().collect::<BTreeMap<_, _>>();
@@ -119,9 +189,7 @@ where
 	// then move them to a vector
 	let input = input.into_iter().zip(lens.windows(2)).map(|((_, v), w)| (&nibbles[w[0]..w[1]], v)).collect::<Vec<_>>();
 
-	let mut stream = RlpStream::new();
-	hash256rlp::<H, _, _>(&input, 0, &mut stream);
-	H::hash(&stream.out())
+	f(&input)
 }
 
 /// Generates a key-hashed (secure) trie root hash for a vector of key-value tuples.
@@ -157,6 +225,72 @@ where
 	trie_root::<H, _, _, _>(input.into_iter().map(|(k, v)| (H::hash(k.as_ref()), v)))
 }
 
+/// Generates a key-hashed (secure) trie root hash for a vector of key-value tuples, together
+/// with a proof for the unhashed `key`.
+///
+/// See `trie_root_with_proof` for the format of the proof.
+pub fn sec_trie_root_with_proof<H, I, A, B>(input: I, key: &[u8]) -> (H::Out, Vec<Vec<u8>>)
+where
+	I: IntoIterator<Item = (A, B)>,
+	A: AsRef<[u8]>,
+	B: AsRef<[u8]>,
+	H: Hasher,
+	<H as hash_db::Hasher>::Out: cmp::Ord,
+{
+	trie_root_with_proof::<H, _, _, _>(input.into_iter().map(|(k, v)| (H::hash(k.as_ref()), v)), H::hash(key).as_ref())
+}
+
+/// Collects the nodes on the path to `key` while the root is computed.
+struct ProofRecorder {
+	/// Key we build the proof for, as nibbles.
+	key: Vec<u8>,
+	/// Rlp of the hashed nodes on the path, deepest first.
+	nodes: Vec<Vec<u8>>,
+}
+
+impl ProofRecorder {
+	/// Whether the subtrie of `input` whose keys share the first `pre_len` nibbles is on the path.
+	fn is_on_path<A: AsRef<[u8]>, B>(&self, input: &[(A, B)], pre_len: usize) -> bool {
+		input.first().map_or(false, |(k, _)| self.key.len() >= pre_len && k.as_ref()[..pre_len] == self.key[..pre_len])
+	}
+}
+
+fn to_nibbles(bytes: &[u8]) -> Vec<u8> {
+	bytes.iter().flat_map(|b| once(b >> 4).chain(once(b & 0x0F))).collect()
+}
+
+/// Generates a keccak-256 trie root hash for a vector of values.
+#[cfg(feature = "keccak")]
+pub fn keccak_ordered_trie_root<I>(input: I) -> [u8; 32]
+where
+	I: IntoIterator,
+	I::Item: AsRef<[u8]>,
+{
+	ordered_trie_root::<keccak_hasher::KeccakHasher, _>(input)
+}
+
+/// Generates a keccak-256 trie root hash for a vector of key-value tuples.
+#[cfg(feature = "keccak")]
+pub fn keccak_trie_root<I, A, B>(input: I) -> [u8; 32]
+where
+	I: IntoIterator<Item = (A, B)>,
+	A: AsRef<[u8]> + Ord,
+	B: AsRef<[u8]>,
+{
+	trie_root::<keccak_hasher::KeccakHasher, _, _, _>(input)
+}
+
+/// Generates a keccak-256 key-hashed (secure) trie root hash for a vector of key-value tuples.
+#[cfg(feature = "keccak")]
+pub fn keccak_sec_trie_root<I, A, B>(input: I) -> [u8; 32]
+where
+	I: IntoIterator<Item = (A, B)>,
+	A: AsRef<[u8]>,
+	B: AsRef<[u8]>,
+{
+	sec_trie_root::<keccak_hasher::KeccakHasher, _, _, _>(input)
+}
+
 /// Hex-prefix Notation. First nibble has flags: oddness = 2^0 & termination = 2^1.
 ///
 /// The "termination marker" and "leaf-node" specifier are completely equivalent.
@@ -190,7 +324,7 @@ fn hex_prefix_encode<'a>(nibbles: &'a [u8], leaf: bool) -> impl Iterator<Item =
 	once(first_byte).chain(nibbles[oddness_factor..].chunks(2).map(|ch| ch[0] << 4 | ch[1]))
 }
 
-fn hash256rlp<H, A, B>(input: &[(A, B)], pre_len: usize, stream: &mut RlpStream)
+fn hash256rlp<H, A, B>(input: &[(A, B)], pre_len: usize, stream: &mut RlpStream, proof: &mut Option<ProofRecorder>)
 where
 	A: AsRef<[u8]>,
 	B: AsRef<[u8]>,
@@ -231,7 +365,7 @@ where
 	if shared_prefix > pre_len {
 		stream.begin_list(2);
 		stream.append_iter(hex_prefix_encode(&key[pre_len..shared_prefix], false));
-		hash256aux::<H, _, _>(input, shared_prefix, stream);
+		hash256aux::<H, _, _>(input, shared_prefix, stream, proof);
 		return;
 	}
 
@@ -253,7 +387,7 @@ where
 			0 => {
 				stream.append_empty_data();
 			}
-			_ => hash256aux::<H, _, _>(&input[begin..(begin + len)], pre_len + 1, stream),
+			_ => hash256aux::<H, _, _>(&input[begin..(begin + len)], pre_len + 1, stream, proof),
 		}
 		begin += len;
 	}
@@ -266,27 +400,39 @@ where
 	}
 }
 
-fn hash256aux<H, A, B>(input: &[(A, B)], pre_len: usize, stream: &mut RlpStream)
+fn hash256aux<H, A, B>(input: &[(A, B)], pre_len: usize, stream: &mut RlpStream, proof: &mut Option<ProofRecorder>)
 where
 	A: AsRef<[u8]>,
 	B: AsRef<[u8]>,
 	H: Hasher,
 {
 	let mut s = RlpStream::new();
-	hash256rlp::<H, _, _>(input, pre_len, &mut s);
+	hash256rlp::<H, _, _>(input, pre_len, &mut s, proof);
 	let out = s.out();
 	match out.len() {
 		0..=31 => stream.append_raw(&out, 1),
-		_ => stream.append(&H::hash(&out).as_ref()),
+		_ => {
+			if let Some(ref mut proof) = proof {
+				if proof.is_on_path(input, pre_len) {
+					proof.nodes.push(out.clone());
+				}
+			}
+			stream.append(&H::hash(&out).as_ref())
+		}
 	};
 }
 
 #[cfg(test)]
 mod tests {
-	use super::{hex_prefix_encode, shared_prefix_len, trie_root};
+	use super::{
+		hex_prefix_encode, sec_trie_root, sec_trie_root_with_proof, shared_prefix_len, trie_root, trie_root_with_proof,
+	};
 	use ethereum_types::H256;
+	use hash_db::Hasher;
 	use hex_literal::hex;
 	use keccak_hasher::KeccakHasher;
+	use std::collections::BTreeMap;
+	use trie_standardmap::{Alphabet, StandardMap, ValueMode};
 
 	#[test]
 	fn test_hex_prefix_encode() {
@@ -348,6 +494,135 @@ mod tests {
 		);
 	}
 
+	/// SHA3-256 hasher, to check roots don't depend on keccak.
+	struct Sha3Hasher;
+
+	impl hash_db::Hasher for Sha3Hasher {
+		type Out = [u8; 32];
+		type StdHasher = std::collections::hash_map::DefaultHasher;
+		const LENGTH: usize = 32;
+
+		fn hash(x: &[u8]) -> Self::Out {
+			use tiny_keccak::Hasher;
+			let mut sha3 = tiny_keccak::Sha3::v256();
+			let mut out = [0u8; 32];
+			sha3.update(x);
+			sha3.finalize(&mut out);
+			out
+		}
+	}
+
+	#[test]
+	fn test_custom_hasher() {
+		let v = vec![("doe", "reindeer"), ("dog", "puppy"), ("dogglesworth", "cat")];
+		let keccak_root = trie_root::<KeccakHasher, _, _, _>(v.clone());
+		let sha3_root = trie_root::<Sha3Hasher, _, _, _>(v.clone());
+		assert_ne!(keccak_root, sha3_root);
+
+		// a single short leaf is hashed directly, so the root is the hash of its rlp
+		let leaf = trie_root::<Sha3Hasher, _, _, _>(vec![(b"A", b"a")]);
+		let mut stream = rlp::RlpStream::new_list(2);
+		stream.append(&vec![0x20u8, 0x41]).append(&"a");
+		assert_eq!(leaf, Sha3Hasher::hash(&stream.out()));
+		assert_eq!(
+			sec_trie_root::<Sha3Hasher, _, _, _>(v.clone()),
+			trie_root::<Sha3Hasher, _, _, _>(v.into_iter().map(|(k, v)| (Sha3Hasher::hash(k.as_bytes()), v)))
+		);
+	}
+
+	#[cfg(feature = "keccak")]
+	#[test]
+	fn test_keccak_shortcuts() {
+		let v = vec![("doe", "reindeer"), ("dog", "puppy"), ("dogglesworth", "cat")];
+		assert_eq!(super::keccak_trie_root(v.clone()), trie_root::<KeccakHasher, _, _, _>(v.clone()));
+		assert_eq!(super::keccak_sec_trie_root(v.clone()), super::sec_trie_root::<KeccakHasher, _, _, _>(v));
+		assert_eq!(
+			super::keccak_ordered_trie_root(&["doe", "reindeer"]),
+			hex!("e766d5d51b89dc39d981b41bda63248d7abce4f0225eefd023792a540bcffee3")
+		);
+	}
+
+	/// Walks `proof` from `root` towards `key`, returning the value found at `key`.
+	fn verify_proof(root: [u8; 32], key: &[u8], proof: &[Vec<u8>]) -> Option<Vec<u8>> {
+		let key = super::to_nibbles(key);
+		assert_eq!(KeccakHasher::hash(&proof[0]), root);
+		let mut node = proof[0].clone();
+		let mut used = 1;
+		let mut pos = 0;
+		let value = loop {
+			let rlp = rlp::Rlp::new(&node);
+			let child = match rlp.item_count().unwrap() {
+				17 if pos == key.len() => break rlp.val_at::<Vec<u8>>(16).unwrap(),
+				17 => {
+					pos += 1;
+					rlp.at(key[pos - 1] as usize).unwrap()
+				}
+				2 => {
+					let partial = super::to_nibbles(&rlp.val_at::<Vec<u8>>(0).unwrap());
+					let (leaf, odd) = (partial[0] & 2 == 2, partial[0] & 1 == 1);
+					let partial = &partial[if odd { 1 } else { 2 }..];
+					if !key[pos..].starts_with(partial) {
+						break Vec::new();
+					}
+					pos += partial.len();
+					if leaf {
+						break if pos == key.len() { rlp.val_at(1).unwrap() } else { Vec::new() };
+					}
+					rlp.at(1).unwrap()
+				}
+				n => panic!("unexpected node with {} items", n),
+			};
+			node = if child.is_list() {
+				child.as_raw().to_vec()
+			} else if child.is_empty() {
+				break Vec::new();
+			} else {
+				let next = proof[used].clone();
+				assert_eq!(&KeccakHasher::hash(&next)[..], child.data().unwrap());
+				used += 1;
+				next
+			};
+		};
+		assert_eq!(used, proof.len());
+		if value.is_empty() {
+			None
+		} else {
+			Some(value)
+		}
+	}
+
+	#[test]
+	fn test_trie_root_with_proof() {
+		let st = StandardMap {
+			alphabet: Alphabet::Low,
+			min_key: 1,
+			journal_key: 5,
+			value_mode: ValueMode::Random,
+			count: 500,
+		};
+		let data: BTreeMap<_, _> = st.make().into_iter().collect();
+		let root = trie_root::<KeccakHasher, _, _, _>(data.clone());
+
+		for (key, value) in &data {
+			let (proof_root, proof) = trie_root_with_proof::<KeccakHasher, _, _, _>(data.clone(), key);
+			assert_eq!(proof_root, root);
+			assert_eq!(verify_proof(root, key, &proof).as_ref(), Some(value));
+		}
+
+		for key in &[&b""[..], b"zz", b"abcdefghij"] {
+			let (_, proof) = trie_root_with_proof::<KeccakHasher, _, _, _>(data.clone(), key);
+			assert_eq!(verify_proof(root, key, &proof), None);
+		}
+	}
+
+	#[test]
+	fn test_sec_trie_root_with_proof() {
+		let v = vec![("doe", "reindeer"), ("dog", "puppy"), ("dogglesworth", "cat")];
+		let (root, proof) = sec_trie_root_with_proof::<KeccakHasher, _, _, _>(v.clone(), b"dog");
+		assert_eq!(root, sec_trie_root::<KeccakHasher, _, _, _>(v));
+		assert_eq!(verify_proof(root, &KeccakHasher::hash(b"dog"), &proof), Some(b"puppy".to_vec()));
+	}
+
 	#[test]
 	fn test_shared_prefix() {
 		let a = vec![1, 2, 3, 4, 5, 6];
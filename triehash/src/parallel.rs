@@ -0,0 +1,174 @@
+// Copyright 2015-2019 Parity Technologies (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Trie root computation hashing the subtries of large tries on the rayon thread pool.
+
+use core::cmp;
+
+use hash_db::Hasher;
+use rayon::prelude::*;
+use rlp::RlpStream;
+
+use crate::{hash256rlp, hex_prefix_encode, shared_prefix_len, with_nibble_keys};
+
+/// Subtries with fewer items are hashed on the current thread.
+const PARALLEL_MIN_ITEMS: usize = 1024;
+
+/// Generates a trie root hash for a vector of values, like `ordered_trie_root`,
+/// hashing the subtries of large tries in parallel.
+pub fn par_ordered_trie_root<H, I>(input: I) -> H::Out
+where
+	I: IntoIterator,
+	I::Item: AsRef<[u8]> + Sync,
+	H: Hasher,
+	<H as hash_db::Hasher>::Out: cmp::Ord,
+{
+	par_trie_root::<H, _, _, _>(input.into_iter().enumerate().map(|(i, v)| (rlp::encode(&i), v)))
+}
+
+/// Generates a trie root hash for a vector of key-value tuples, like `trie_root`,
+/// hashing the subtries of large tries in parallel.
+///
+/// ```rust
+/// use keccak_hasher::KeccakHasher;
+/// use triehash::{par_trie_root, trie_root};
+///
+/// let v = (0..5000u32).map(|i| (i.to_be_bytes(), vec![7u8; 40])).collect::<Vec<_>>();
+/// assert_eq!(par_trie_root::<KeccakHasher, _, _, _>(v.clone()), trie_root::<KeccakHasher, _, _, _>(v));
+/// ```
+pub fn par_trie_root<H, I, A, B>(input: I) -> H::Out
+where
+	I: IntoIterator<Item = (A, B)>,
+	A: AsRef<[u8]> + Ord,
+	B: AsRef<[u8]> + Sync,
+	H: Hasher,
+	<H as hash_db::Hasher>::Out: cmp::Ord,
+{
+	with_nibble_keys(input, |input| {
+		let mut stream = RlpStream::new();
+		par_hash256rlp::<H, _, _>(input, 0, &mut stream);
+		H::hash(&stream.out())
+	})
+}
+
+/// Generates a key-hashed (secure) trie root hash for a vector of key-value tuples, like
+/// `sec_trie_root`, hashing the subtries of large tries in parallel.
+pub fn par_sec_trie_root<H, I, A, B>(input: I) -> H::Out
+where
+	I: IntoIterator<Item = (A, B)>,
+	A: AsRef<[u8]>,
+	B: AsRef<[u8]> + Sync,
+	H: Hasher,
+	<H as hash_db::Hasher>::Out: cmp::Ord,
+{
+	par_trie_root::<H, _, _, _>(input.into_iter().map(|(k, v)| (H::hash(k.as_ref()), v)))
+}
+
+/// Same as `hash256rlp`, with the children of large branches hashed in parallel.
+fn par_hash256rlp<H, A, B>(input: &[(A, B)], pre_len: usize, stream: &mut RlpStream)
+where
+	A: AsRef<[u8]> + Sync,
+	B: AsRef<[u8]> + Sync,
+	H: Hasher,
+{
+	if input.len() < PARALLEL_MIN_ITEMS {
+		hash256rlp::<H, _, _>(input, pre_len, stream, &mut None);
+		return;
+	}
+
+	let key: &[u8] = input[0].0.as_ref();
+	let value: &[u8] = input[0].1.as_ref();
+	let shared_prefix =
+		input.iter().skip(1).fold(key.len(), |acc, (k, _)| cmp::min(shared_prefix_len(key, k.as_ref()), acc));
+
+	if shared_prefix > pre_len {
+		stream.begin_list(2);
+		stream.append_iter(hex_prefix_encode(&key[pre_len..shared_prefix], false));
+		stream.append_raw(&par_hash256aux::<H, _, _>(input, shared_prefix), 1);
+		return;
+	}
+
+	// split the items by their next nibble, the first one ends here if its key is the prefix
+	let mut begin = if pre_len == key.len() { 1 } else { 0 };
+	let mut children = Vec::with_capacity(16);
+	for i in 0..16 {
+		let len = input[begin..].iter().take_while(|pair| pair.0.as_ref()[pre_len] == i).count();
+		children.push(&input[begin..begin + len]);
+		begin += len;
+	}
+	let children = children
+		.into_par_iter()
+		.map(|child| if child.is_empty() { None } else { Some(par_hash256aux::<H, _, _>(child, pre_len + 1)) })
+		.collect::<Vec<_>>();
+
+	stream.begin_list(17);
+	for child in children {
+		match child {
+			Some(child) => stream.append_raw(&child, 1),
+			None => stream.append_empty_data(),
+		};
+	}
+	if pre_len == key.len() {
+		stream.append(&value);
+	} else {
+		stream.append_empty_data();
+	}
+}
+
+/// Same as `hash256aux`, returning the rlp of the node or of its hash.
+fn par_hash256aux<H, A, B>(input: &[(A, B)], pre_len: usize) -> Vec<u8>
+where
+	A: AsRef<[u8]> + Sync,
+	B: AsRef<[u8]> + Sync,
+	H: Hasher,
+{
+	let mut s = RlpStream::new();
+	par_hash256rlp::<H, _, _>(input, pre_len, &mut s);
+	let out = s.out();
+	match out.len() {
+		0..=31 => out,
+		_ => rlp::encode(&H::hash(&out).as_ref()),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{ordered_trie_root, sec_trie_root, trie_root};
+	use keccak_hasher::KeccakHasher;
+
+	#[test]
+	fn matches_sequential_roots() {
+		for &n in &[0, 1, 17, PARALLEL_MIN_ITEMS - 1, PARALLEL_MIN_ITEMS, 5 * PARALLEL_MIN_ITEMS] {
+			let values = (0..n).map(|i| vec![i as u8; 1 + i % 50]).collect::<Vec<_>>();
+			assert_eq!(
+				par_ordered_trie_root::<KeccakHasher, _>(&values),
+				ordered_trie_root::<KeccakHasher, _>(&values)
+			);
+
+			// keys sharing a long prefix, some of them prefixes of others
+			let pairs = values.iter().enumerate().map(|(i, v)| (format!("shared/{}", i), v)).collect::<Vec<_>>();
+			assert_eq!(
+				par_trie_root::<KeccakHasher, _, _, _>(pairs.clone()),
+				trie_root::<KeccakHasher, _, _, _>(pairs.clone())
+			);
+			assert_eq!(
+				par_sec_trie_root::<KeccakHasher, _, _, _>(pairs.clone()),
+				sec_trie_root::<KeccakHasher, _, _, _>(pairs)
+			);
+		}
+	}
+}
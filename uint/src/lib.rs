@@ -38,3 +38,6 @@ pub use crunchy::unroll;
 #[rustfmt::skip]
 mod uint;
 pub use crate::uint::*;
+
+mod montgomery;
+pub use crate::montgomery::{MontgomeryUint, ReductionContext};
@@ -0,0 +1,119 @@
+// Copyright 2015-2019 Parity Technologies
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Montgomery modular multiplication.
+
+use core::ops::Rem;
+
+/// Operations `ReductionContext` needs from the uint types, implemented by `construct_uint!`.
+#[doc(hidden)]
+pub trait MontgomeryUint: Copy + Ord + Rem<Output = Self> {
+	/// Number of bits of the type.
+	const BITS: usize;
+
+	fn zero() -> Self;
+	fn one() -> Self;
+	fn low_u64(&self) -> u64;
+	fn bits(&self) -> usize;
+	fn bit(&self, index: usize) -> bool;
+	fn overflowing_add(self, other: Self) -> (Self, bool);
+	fn overflowing_sub(self, other: Self) -> (Self, bool);
+
+	/// `self * other / R mod modulus` for `R = 2^BITS`, with `inv = -modulus^-1 mod 2^64`.
+	///
+	/// Both operands must be lower than the modulus.
+	fn montgomery_mul(&self, other: &Self, modulus: &Self, inv: u64) -> Self;
+}
+
+/// Precomputed values for fast modular multiplication by a fixed odd modulus.
+///
+/// Multiplications use Montgomery reduction instead of a division, which pays off when
+/// the same modulus is used many times, e.g. for operations in a prime field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ReductionContext<T> {
+	modulus: T,
+	// -modulus^-1 mod 2^64
+	inv: u64,
+	// R mod modulus, i.e. 1 in Montgomery form
+	one: T,
+	// R^2 mod modulus, to convert into Montgomery form
+	r2: T,
+}
+
+impl<T: MontgomeryUint> ReductionContext<T> {
+	/// Precompute the context of `modulus`.
+	///
+	/// Returns `None` if the modulus is even, which includes zero.
+	pub fn new(modulus: T) -> Option<Self> {
+		let m0 = modulus.low_u64();
+		if m0 & 1 == 0 {
+			return None;
+		}
+		// Newton's iteration, every step doubles the number of correct low bits
+		let mut inv = 1u64;
+		for _ in 0..6 {
+			inv = inv.wrapping_mul(2u64.wrapping_sub(m0.wrapping_mul(inv)));
+		}
+		// 2^BITS - modulus is congruent to R
+		let one = T::zero().overflowing_sub(modulus).0 % modulus;
+		let mut r2 = one;
+		for _ in 0..T::BITS {
+			r2 = double_mod(r2, modulus);
+		}
+		Some(ReductionContext { modulus, inv: inv.wrapping_neg(), one, r2 })
+	}
+
+	/// The modulus.
+	pub fn modulus(&self) -> T {
+		self.modulus
+	}
+
+	/// `a * b mod modulus`.
+	pub fn mul_mod(&self, a: T, b: T) -> T {
+		let a = self.reduce(a);
+		let b = self.reduce(b);
+		// a * b / R, then multiplied by R^2 / R
+		self.mul(&self.mul(&a, &b), &self.r2)
+	}
+
+	/// `base ^ exp mod modulus`.
+	pub fn pow_mod(&self, base: T, exp: T) -> T {
+		let base = self.mul(&self.reduce(base), &self.r2);
+		let mut acc = self.one;
+		for i in (0..exp.bits()).rev() {
+			acc = self.mul(&acc, &acc);
+			if exp.bit(i) {
+				acc = self.mul(&acc, &base);
+			}
+		}
+		// out of Montgomery form
+		self.mul(&acc, &self.reduce(T::one()))
+	}
+
+	fn reduce(&self, x: T) -> T {
+		if x < self.modulus {
+			x
+		} else {
+			x % self.modulus
+		}
+	}
+
+	fn mul(&self, a: &T, b: &T) -> T {
+		a.montgomery_mul(b, &self.modulus, self.inv)
+	}
+}
+
+/// `2 * x mod modulus` for `x < modulus`.
+fn double_mod<T: MontgomeryUint>(x: T, modulus: T) -> T {
+	let (sum, overflow) = x.overflowing_add(x);
+	if overflow || sum >= modulus {
+		sum.overflowing_sub(modulus).0
+	} else {
+		sum
+	}
+}
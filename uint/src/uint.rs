@@ -38,6 +38,73 @@ pub enum FromDecStrErr {
 	InvalidLength,
 }
 
+/// Conversion from a string in an arbitrary radix error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FromStrRadixErr {
+	/// Radix not from range 2-36
+	UnsupportedRadix(u32),
+	/// No digits to parse
+	Empty,
+	/// Char at given byte index is not a digit of the radix
+	InvalidCharacter {
+		/// Byte index of the char in the parsed string
+		index: usize,
+	},
+	/// Value does not fit into type
+	Overflow,
+}
+
+impl core::fmt::Display for FromStrRadixErr {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		match *self {
+			FromStrRadixErr::UnsupportedRadix(radix) => write!(f, "unsupported radix {}", radix),
+			FromStrRadixErr::Empty => write!(f, "cannot parse integer from empty string"),
+			FromStrRadixErr::InvalidCharacter { index } => write!(f, "invalid digit at position {}", index),
+			FromStrRadixErr::Overflow => write!(f, "number too large to fit in target type"),
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FromStrRadixErr {}
+
+/// Writes `digits` of an unsigned number honoring the width, fill, alignment, `#`, `0` and
+/// precision flags of the formatter. Precision is the minimal number of digits.
+#[doc(hidden)]
+pub fn fmt_digits(f: &mut core::fmt::Formatter, prefix: &str, digits: &str) -> core::fmt::Result {
+	use core::fmt::{Alignment, Write};
+
+	fn repeat(f: &mut core::fmt::Formatter, c: char, n: usize) -> core::fmt::Result {
+		for _ in 0..n {
+			f.write_char(c)?;
+		}
+		Ok(())
+	}
+
+	let prefix = if f.alternate() { prefix } else { "" };
+	let mut zeros = f.precision().unwrap_or(0).saturating_sub(digits.len());
+	let len = prefix.len() + zeros + digits.len();
+	let padding = f.width().unwrap_or(0).saturating_sub(len);
+
+	let (pre, post) = if f.sign_aware_zero_pad() {
+		zeros += padding;
+		(0, 0)
+	} else {
+		match f.align() {
+			Some(Alignment::Left) => (0, padding),
+			Some(Alignment::Center) => (padding / 2, padding - padding / 2),
+			Some(Alignment::Right) | None => (padding, 0),
+		}
+	};
+
+	let fill = f.fill();
+	repeat(f, fill, pre)?;
+	f.write_str(prefix)?;
+	repeat(f, '0', zeros)?;
+	f.write_str(digits)?;
+	repeat(f, fill, post)
+}
+
 #[macro_export]
 #[doc(hidden)]
 macro_rules! impl_map_from {
@@ -471,6 +538,55 @@ macro_rules! construct_uint {
 				Ok(res)
 			}
 
+			/// Convert from a string of digits in given radix, from 2 to 36.
+			///
+			/// Letters are case insensitive. No sign, prefix or whitespace is accepted, see `parse_any`.
+			pub fn from_str_radix(src: &str, radix: u32) -> $crate::core_::result::Result<Self, $crate::FromStrRadixErr> {
+				if radix < 2 || radix > 36 {
+					return Err($crate::FromStrRadixErr::UnsupportedRadix(radix));
+				}
+				if src.is_empty() {
+					return Err($crate::FromStrRadixErr::Empty);
+				}
+
+				let mut res = Self::zero();
+				for (index, c) in src.char_indices() {
+					let digit = c.to_digit(radix).ok_or($crate::FromStrRadixErr::InvalidCharacter { index })?;
+					let (r, overflow) = res.overflowing_mul_u64(radix as u64);
+					if overflow > 0 {
+						return Err($crate::FromStrRadixErr::Overflow);
+					}
+					let (r, overflow) = r.overflowing_add(digit.into());
+					if overflow {
+						return Err($crate::FromStrRadixErr::Overflow);
+					}
+					res = r;
+				}
+				Ok(res)
+			}
+
+			/// Convert from a hexadecimal (`0x`), octal (`0o`) or binary (`0b`) string, or a decimal
+			/// string without prefix.
+			///
+			/// Surrounding whitespace is ignored. The index of an invalid character is relative to `src`.
+			pub fn parse_any(src: &str) -> $crate::core_::result::Result<Self, $crate::FromStrRadixErr> {
+				let trimmed = src.trim_start();
+				let offset = src.len() - trimmed.len();
+				let trimmed = trimmed.trim_end();
+				let (radix, skip) = match trimmed.get(..2) {
+					Some("0x") | Some("0X") => (16, 2),
+					Some("0o") | Some("0O") => (8, 2),
+					Some("0b") | Some("0B") => (2, 2),
+					_ => (10, 0),
+				};
+				Self::from_str_radix(&trimmed[skip..], radix).map_err(|e| match e {
+					$crate::FromStrRadixErr::InvalidCharacter { index } => {
+						$crate::FromStrRadixErr::InvalidCharacter { index: index + offset + skip }
+					}
+					e => e,
+				})
+			}
+
 			/// Conversion to u32
 			#[inline]
 			pub fn low_u32(&self) -> u32 {
@@ -825,6 +941,13 @@ macro_rules! construct_uint {
 				self.div_mod_knuth(other, n, m)
 			}
 
+			/// Precompute a context for fast multiplication and exponentiation modulo `modulus`.
+			///
+			/// Returns `None` if the modulus is even, which includes zero.
+			pub fn reduction_context(modulus: Self) -> Option<$crate::ReductionContext<Self>> {
+				$crate::ReductionContext::new(modulus)
+			}
+
 			/// Fast exponentiation by squaring
 			/// https://en.wikipedia.org/wiki/Exponentiation_by_squaring
 			///
@@ -960,6 +1083,75 @@ macro_rules! construct_uint {
 				}
 			}
 
+			/// Fused multiply-add: `self * a + b`, returning a flag if it overflows.
+			///
+			/// The product is kept in double width, so the flag is only set if the final result
+			/// doesn't fit.
+			pub fn overflowing_mul_add(self, a: $name, b: $name) -> ($name, bool) {
+				let mut wide: [u64; $n_words * 2] = $crate::uint_full_mul_reg!($name, $n_words, self, a);
+				let carry = Self::add_to_wide(&mut wide, &b.0);
+				Self::narrow(&wide, carry)
+			}
+
+			/// Fused multiply-add: `self * a + b`.
+			///
+			/// # Panics
+			///
+			/// Panics if the result overflows the type.
+			pub fn mul_add(self, a: $name, b: $name) -> $name {
+				let (result, overflow) = self.overflowing_mul_add(a, b);
+				$crate::panic_on_overflow!(overflow);
+				result
+			}
+
+			/// Checked fused multiply-add: `self * a + b`. Returns `None` if overflow occurred.
+			pub fn checked_mul_add(self, a: $name, b: $name) -> Option<$name> {
+				match self.overflowing_mul_add(a, b) {
+					(_, true) => None,
+					(val, false) => Some(val),
+				}
+			}
+
+			/// Checked sum of the products of the given pairs. Returns `None` if overflow occurred.
+			///
+			/// The sum is accumulated in double width, so it's only `None` if the final result
+			/// doesn't fit.
+			pub fn checked_dot(pairs: &[($name, $name)]) -> Option<$name> {
+				let mut wide = [0u64; $n_words * 2];
+				for &(a, b) in pairs {
+					let product: [u64; $n_words * 2] = $crate::uint_full_mul_reg!($name, $n_words, a, b);
+					if Self::add_to_wide(&mut wide, &product) {
+						return None;
+					}
+				}
+				match Self::narrow(&wide, false) {
+					(_, true) => None,
+					(val, false) => Some(val),
+				}
+			}
+
+			// Adds `words` to the double width `wide`, returning the carry.
+			#[inline(always)]
+			fn add_to_wide(wide: &mut [u64; $n_words * 2], words: &[u64]) -> bool {
+				let mut carry = false;
+				for (i, limb) in wide.iter_mut().enumerate() {
+					let word = words.get(i).cloned().unwrap_or(0);
+					let (sum, o0) = limb.overflowing_add(word);
+					let (sum, o1) = sum.overflowing_add(carry as u64);
+					*limb = sum;
+					carry = o0 | o1;
+				}
+				carry
+			}
+
+			// The low half of `wide`, with an overflow flag if the high half isn't zero.
+			#[inline(always)]
+			fn narrow(wide: &[u64; $n_words * 2], carry: bool) -> ($name, bool) {
+				let mut low = [0u64; $n_words];
+				low.copy_from_slice(&wide[..$n_words]);
+				($name(low), carry || wide[$n_words..].iter().any(|&word| word != 0))
+			}
+
 			/// Checked division. Returns `None` if `other == 0`.
 			pub fn checked_div(self, other: $name) -> Option<$name> {
 				if other.is_zero() {
@@ -1479,7 +1671,7 @@ macro_rules! construct_uint {
 		impl $crate::core_::fmt::Display for $name {
 			fn fmt(&self, f: &mut $crate::core_::fmt::Formatter) -> $crate::core_::fmt::Result {
 				if self.is_zero() {
-					return $crate::core_::write!(f, "0");
+					return $crate::fmt_digits(f, "", "0");
 				}
 
 				let mut buf = [0_u8; $n_words*20];
@@ -1501,35 +1693,120 @@ macro_rules! construct_uint {
 				let s = unsafe {
 					$crate::core_::str::from_utf8_unchecked(&buf[i..])
 				};
-				f.write_str(s)
+				$crate::fmt_digits(f, "", s)
 			}
 		}
 
 		impl $crate::core_::fmt::LowerHex for $name {
 			fn fmt(&self, f: &mut $crate::core_::fmt::Formatter) -> $crate::core_::fmt::Result {
 				let &$name(ref data) = self;
-				if f.alternate() {
-					$crate::core_::write!(f, "0x")?;
+				let mut buf = [0_u8; $n_words*16];
+				for (i, c) in buf.iter_mut().rev().enumerate() {
+					*c = b"0123456789abcdef"[((data[i / 16] >> (i % 16 * 4)) & 0xf) as usize];
 				}
-				// special case.
-				if self.is_zero() {
-					return $crate::core_::write!(f, "0");
+				let start = buf.iter().position(|&c| c != b'0').unwrap_or(buf.len() - 1);
+
+				// sequence of hex digits is guaranteed to be a valid UTF8 string
+				let s = unsafe {
+					$crate::core_::str::from_utf8_unchecked(&buf[start..])
+				};
+				$crate::fmt_digits(f, "0x", s)
+			}
+		}
+
+		impl $crate::core_::fmt::Binary for $name {
+			fn fmt(&self, f: &mut $crate::core_::fmt::Formatter) -> $crate::core_::fmt::Result {
+				let &$name(ref data) = self;
+				let mut buf = [0_u8; $n_words*64];
+				for (i, c) in buf.iter_mut().rev().enumerate() {
+					*c = b'0' + ((data[i / 64] >> (i % 64)) & 1) as u8;
 				}
+				let start = buf.iter().position(|&c| c != b'0').unwrap_or(buf.len() - 1);
 
-				let mut latch = false;
-				for ch in data.iter().rev() {
-					for x in 0..16 {
-						let nibble = (ch & (15u64 << ((15 - x) * 4) as u64)) >> (((15 - x) * 4) as u64);
-						if !latch {
-							latch = nibble != 0;
-						}
+				// sequence of `'0'` and `'1'` chars is guaranteed to be a valid UTF8 string
+				let s = unsafe {
+					$crate::core_::str::from_utf8_unchecked(&buf[start..])
+				};
+				$crate::fmt_digits(f, "0b", s)
+			}
+		}
 
-						if latch {
-							$crate::core_::write!(f, "{:x}", nibble)?;
-						}
+		impl $crate::MontgomeryUint for $name {
+			const BITS: usize = $n_words * 64;
+
+			fn zero() -> Self {
+				$name::zero()
+			}
+
+			fn one() -> Self {
+				$name::one()
+			}
+
+			fn low_u64(&self) -> u64 {
+				$name::low_u64(self)
+			}
+
+			fn bits(&self) -> usize {
+				$name::bits(self)
+			}
+
+			fn bit(&self, index: usize) -> bool {
+				$name::bit(self, index)
+			}
+
+			fn overflowing_add(self, other: Self) -> (Self, bool) {
+				$name::overflowing_add(self, other)
+			}
+
+			fn overflowing_sub(self, other: Self) -> (Self, bool) {
+				$name::overflowing_sub(self, other)
+			}
+
+			// Coarsely integrated operand scanning, see "Analyzing and Comparing Montgomery
+			// Multiplication Algorithms" by Koç, Acar and Kaliski.
+			fn montgomery_mul(&self, other: &Self, modulus: &Self, inv: u64) -> Self {
+				#[inline(always)]
+				fn mac(a: u64, b: u64, c: u64, carry: u64) -> (u64, u64) {
+					let t = a as u128 + b as u128 * c as u128 + carry as u128;
+					(t as u64, (t >> 64) as u64)
+				}
+
+				let $name(ref a) = *self;
+				let $name(ref b) = *other;
+				let $name(ref m) = *modulus;
+				let mut t = [0u64; $n_words + 2];
+				for i in 0..$n_words {
+					let mut carry = 0;
+					for j in 0..$n_words {
+						let (low, high) = mac(t[j], a[j], b[i], carry);
+						t[j] = low;
+						carry = high;
+					}
+					let (sum, overflow) = t[$n_words].overflowing_add(carry);
+					t[$n_words] = sum;
+					t[$n_words + 1] = overflow as u64;
+
+					// add a multiple of the modulus making the lowest word zero and shift it out
+					let k = t[0].wrapping_mul(inv);
+					let (_, mut carry) = mac(t[0], k, m[0], 0);
+					for j in 1..$n_words {
+						let (low, high) = mac(t[j], k, m[j], carry);
+						t[j - 1] = low;
+						carry = high;
 					}
+					let (sum, overflow) = t[$n_words].overflowing_add(carry);
+					t[$n_words - 1] = sum;
+					t[$n_words] = t[$n_words + 1] + overflow as u64;
+				}
+
+				let mut ret = [0u64; $n_words];
+				ret.copy_from_slice(&t[..$n_words]);
+				let ret = $name(ret);
+				if t[$n_words] != 0 || ret >= *modulus {
+					ret.overflowing_sub(*modulus).0
+				} else {
+					ret
 				}
-				Ok(())
 			}
 		}
 
@@ -10,7 +10,7 @@ use core::convert::TryInto;
 use core::str::FromStr;
 use core::u64::MAX;
 use crunchy::unroll;
-use uint::{construct_uint, overflowing, FromDecStrErr};
+use uint::{construct_uint, overflowing, FromDecStrErr, FromStrRadixErr};
 
 construct_uint! {
 	pub struct U256(4);
@@ -394,6 +394,30 @@ fn uint256_pow_overflow_panic() {
 	U256::from(2).pow(U256::from(0x100));
 }
 
+#[test]
+fn should_format_with_flags() {
+	let value = U256::from(0xbeef);
+	assert_eq!(format!("{:#066x}", value), format!("0x{}beef", "0".repeat(60)));
+	assert_eq!(format!("{:#x}", U256::max_value()), format!("0x{}", "f".repeat(64)));
+	assert_eq!(format!("{:8x}", value), "    beef");
+	assert_eq!(format!("{:<8x}|", value), "beef    |");
+	assert_eq!(format!("{:*^10x}", value), "***beef***");
+	assert_eq!(format!("{:.6x}", value), "00beef");
+	assert_eq!(format!("{:#10.6x}", value), "  0x00beef");
+	assert_eq!(format!("{:x}", U256::zero()), "0");
+	assert_eq!(format!("{:#06x}", U256::zero()), "0x0000");
+
+	assert_eq!(format!("{:b}", U256::from(5)), "101");
+	assert_eq!(format!("{:#010b}", U256::from(5)), "0b00000101");
+	assert_eq!(format!("{:b}", U256::zero()), "0");
+	assert_eq!(format!("{:b}", U256::one() << 255), format!("1{}", "0".repeat(255)));
+
+	assert_eq!(format!("{:5}", U256::from(42)), "   42");
+	assert_eq!(format!("{:05}", U256::from(42)), "00042");
+	assert_eq!(format!("{:<5}|", U256::from(42)), "42   |");
+	assert_eq!(format!("{:3}", U256::zero()), "  0");
+}
+
 #[test]
 fn should_format_and_debug_correctly() {
 	let test = |x: usize, hex: &'static str, display: &'static str| {
@@ -557,6 +581,42 @@ fn uint256_from_dec_str() {
 	assert_eq!(U256::from_dec_str("0x11"), Err(FromDecStrErr::InvalidCharacter));
 }
 
+#[test]
+fn uint256_from_str_radix() {
+	assert_eq!(U256::from_str_radix("ff", 16).unwrap(), U256::from(255u64));
+	assert_eq!(U256::from_str_radix("FF", 16).unwrap(), U256::from(255u64));
+	assert_eq!(U256::from_str_radix("777", 8).unwrap(), U256::from(511u64));
+	assert_eq!(U256::from_str_radix("zz", 36).unwrap(), U256::from(1295u64));
+	assert_eq!(U256::from_str_radix(&"1".repeat(256), 2).unwrap(), U256::MAX);
+	assert_eq!(U256::from_str_radix(&"1".repeat(257), 2), Err(FromStrRadixErr::Overflow));
+	assert_eq!(U256::from_str_radix(&"f".repeat(65), 16), Err(FromStrRadixErr::Overflow));
+	assert_eq!(U256::from_str_radix("12", 2), Err(FromStrRadixErr::InvalidCharacter { index: 1 }));
+	assert_eq!(U256::from_str_radix("", 10), Err(FromStrRadixErr::Empty));
+	assert_eq!(U256::from_str_radix("1", 1), Err(FromStrRadixErr::UnsupportedRadix(1)));
+	assert_eq!(U256::from_str_radix("1", 37), Err(FromStrRadixErr::UnsupportedRadix(37)));
+	assert_eq!(
+		U256::from_str_radix("115792089237316195423570985008687907853269984665640564039457584007913129639935", 10)
+			.unwrap(),
+		U256::MAX
+	);
+}
+
+#[test]
+fn uint256_parse_any() {
+	assert_eq!(U256::parse_any("1024").unwrap(), U256::from(1024u64));
+	assert_eq!(U256::parse_any("0x400").unwrap(), U256::from(1024u64));
+	assert_eq!(U256::parse_any("0X400").unwrap(), U256::from(1024u64));
+	assert_eq!(U256::parse_any("0o2000").unwrap(), U256::from(1024u64));
+	assert_eq!(U256::parse_any("0b10000000000").unwrap(), U256::from(1024u64));
+	assert_eq!(U256::parse_any(" 0x400\n").unwrap(), U256::from(1024u64));
+	assert_eq!(U256::parse_any("0").unwrap(), U256::zero());
+	assert_eq!(U256::parse_any("0x"), Err(FromStrRadixErr::Empty));
+	assert_eq!(U256::parse_any("  "), Err(FromStrRadixErr::Empty));
+	assert_eq!(U256::parse_any(" 0x4g0"), Err(FromStrRadixErr::InvalidCharacter { index: 4 }));
+	assert_eq!(U256::parse_any("-1"), Err(FromStrRadixErr::InvalidCharacter { index: 0 }));
+	assert_eq!(format!("{}", FromStrRadixErr::InvalidCharacter { index: 4 }), "invalid digit at position 4");
+}
+
 #[test]
 fn display_uint() {
 	let s = "12345678987654321023456789";
@@ -849,6 +909,35 @@ fn big_endian() {
 	);
 }
 
+#[test]
+fn u256_mul_add() {
+	let max = U256::max_value();
+	assert_eq!(U256::from(3).mul_add(4.into(), 5.into()), 17.into());
+	assert_eq!(U256::zero().mul_add(max, max), max);
+	assert_eq!((max >> 1).mul_add(2.into(), 1.into()), max);
+	assert_eq!((max >> 1).checked_mul_add(2.into(), 2.into()), None);
+	assert_eq!(max.overflowing_mul_add(max, max), (0.into(), true));
+	assert_eq!(max.checked_mul_add(2.into(), 0.into()), None);
+}
+
+#[test]
+#[should_panic(expected = "arithmetic operation overflow")]
+fn u256_mul_add_panics_on_overflow() {
+	U256::max_value().mul_add(1.into(), 1.into());
+}
+
+#[test]
+fn u256_checked_dot() {
+	let max = U256::max_value();
+	assert_eq!(U256::checked_dot(&[]), Some(0.into()));
+	assert_eq!(U256::checked_dot(&[(2.into(), 3.into()), (4.into(), 5.into())]), Some(26.into()));
+	assert_eq!(U256::checked_dot(&[(max >> 1, 2.into()), (1.into(), 1.into())]), Some(max));
+	assert_eq!(U256::checked_dot(&[(max, max), (1.into(), 1.into())]), None);
+	assert_eq!(U256::checked_dot(&[(max, 1.into()), (1.into(), 1.into())]), None);
+	// products larger than the type that overflow the double width accumulator
+	assert_eq!(U256::checked_dot(&[(max, max), (max, max)]), None);
+}
+
 #[test]
 fn u256_multi_muls2() {
 	let (result, _) = U256([0, 0, 0, 0]).overflowing_mul(U256([0, 0, 0, 0]));
@@ -1105,6 +1194,13 @@ pub mod laws {
 					}
 				}
 
+				quickcheck! {
+					fn mul_add_matches_mul_and_add(x: $uint_ty, y: $uint_ty, z: $uint_ty) -> bool {
+						let expected = x.checked_mul(y).and_then(|p| p.checked_add(z));
+						x.checked_mul_add(y, z) == expected && $uint_ty::checked_dot(&[(x, y), (z, 1.into())]) == expected
+					}
+				}
+
 				quickcheck! {
 					fn identity_add(x: $uint_ty) -> bool {
 						x + $uint_ty::zero() == x
@@ -1234,3 +1330,63 @@ pub mod laws {
 	uint_laws!(u512, U512);
 	uint_laws!(u1024, U1024);
 }
+
+#[test]
+fn reduction_context_matches_plain_arithmetic() {
+	fn widen(x: U256) -> U512 {
+		let mut words = [0; 8];
+		words[..4].copy_from_slice(&x.0);
+		U512(words)
+	}
+	fn narrow(x: U512) -> U256 {
+		let mut words = [0; 4];
+		words.copy_from_slice(&x.0[..4]);
+		U256(words)
+	}
+	let mul_mod = |a: U256, b: U256, m: U256| narrow(widen(a) * widen(b) % widen(m));
+
+	let moduli = [
+		// BN254 base field
+		U256::from_dec_str("21888242871839275222246405745257275088696311157297823662689037894645226208583").unwrap(),
+		// secp256k1 base field
+		U256::from_str("fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f").unwrap(),
+		U256::MAX,
+		U256::from(1_000_000_007u64),
+		U256::from(3),
+		U256::one(),
+	];
+	let values = [
+		U256::zero(),
+		U256::one(),
+		U256::from(2),
+		U256::from(MAX),
+		U256::from_str("123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef").unwrap(),
+		U256::MAX - 1,
+		U256::MAX,
+	];
+	for &m in moduli.iter() {
+		let ctx = U256::reduction_context(m).unwrap();
+		assert_eq!(ctx.modulus(), m);
+		for &a in values.iter() {
+			for &b in values.iter() {
+				assert_eq!(ctx.mul_mod(a, b), mul_mod(a, b, m), "{} * {} mod {}", a, b, m);
+			}
+			let mut expected = U256::one() % m;
+			for e in 0..20u64 {
+				assert_eq!(ctx.pow_mod(a, e.into()), expected, "{} ^ {} mod {}", a, e, m);
+				expected = mul_mod(expected, a, m);
+			}
+		}
+	}
+
+	// Fermat's little theorem
+	let ctx = U256::reduction_context(moduli[0]).unwrap();
+	assert_eq!(ctx.pow_mod(U256::from(5), moduli[0] - 1), U256::one());
+}
+
+#[test]
+fn reduction_context_rejects_even_moduli() {
+	assert!(U256::reduction_context(U256::zero()).is_none());
+	assert!(U256::reduction_context(U256::from(1_000_000_006u64)).is_none());
+	assert!(U512::reduction_context(U512::from(7)).is_some());
+}